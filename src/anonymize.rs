@@ -0,0 +1,216 @@
+//! Pseudonyms instead of the names and ids of the participants, derived with a secret key so the exports of separate runs stay linkable for whoever holds the key.
+use std::io;
+
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+
+use crate::{
+    checksum::hex,
+    model::{Meeting, Message},
+    transform::MessageTransform,
+};
+
+/// How many hex digits of the HMAC make up a pseudonym, 64 bits are plenty to tell the participants apart.
+const DIGITS: usize = 16;
+
+/// Derives the pseudonyms with a keyed hash.
+///
+/// The pseudonym of a participant with an external user id is `p-` followed by the first 16 hex digits of HMAC-SHA256 of that id, the same in every meeting and every run with the same key. Without the key it can not be computed nor reversed. The internal user ids and the names of the participants without an external one (e.g. guests or phone callers) get pseudonyms of their own.
+pub struct Pseudonyms {
+    key: Vec<u8>,
+}
+
+impl Pseudonyms {
+    pub fn new(key: &str) -> Pseudonyms {
+        Pseudonyms {
+            key: key.as_bytes().to_vec(),
+        }
+    }
+
+    fn derive(&self, prefix: &str, input: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC takes any key");
+        mac.update(input.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        format!("{}-{}", prefix, &hex(&digest)[..DIGITS])
+    }
+
+    /// The pseudonym of an external user id, what a holder of the key computes to find a person.
+    pub fn of_ext_id(&self, ext_id: &str) -> String {
+        self.derive("p", ext_id)
+    }
+
+    // the internal ids and names are hashed in their own domains so they never collide with an external user id
+    fn of_internal_id(&self, user_id: &str) -> String {
+        self.derive("u", &format!("internal:{}", user_id))
+    }
+
+    fn of_name(&self, name: &str) -> String {
+        self.derive("p", &format!("name:{}", name))
+    }
+
+    /// The name of a participant: the pseudonym of their external user id, without one of their name.
+    fn name(&self, name: &str, ext_id: Option<&str>) -> String {
+        match ext_id {
+            Some(ext_id) => self.of_ext_id(ext_id),
+            None => self.of_name(name),
+        }
+    }
+}
+
+impl MessageTransform for Pseudonyms {
+    /// Replace the author of the message, the participants it mentions and its id, which may contain the internal user id. The text is left as it is.
+    fn message(&mut self, msg: &mut Message) -> io::Result<bool> {
+        msg.id = self.derive("m", &format!("message:{}", msg.id));
+        msg.author = self.name(&msg.author, msg.ext_id.as_deref());
+        msg.author_id = self.of_internal_id(&msg.author_id);
+        msg.ext_id = msg.ext_id.as_deref().map(|id| self.of_ext_id(id));
+        for mention in &mut msg.mentions {
+            mention.name = self.name(&mention.name, mention.ext_id.as_deref());
+            mention.user_id = self.of_internal_id(&mention.user_id);
+            mention.ext_id = mention.ext_id.as_deref().map(|id| self.of_ext_id(id));
+        }
+        Ok(true)
+    }
+
+    /// Replace the participants of the meeting.
+    fn meeting(&mut self, meeting: &mut Meeting) -> io::Result<()> {
+        meeting.users = meeting
+            .users
+            .drain()
+            .map(|(_, mut user)| {
+                user.name = self.name(&user.name, user.ext_id.as_deref());
+                user.user_id = self.of_internal_id(&user.user_id);
+                user.ext_id = user.ext_id.as_deref().map(|id| self.of_ext_id(id));
+                (user.user_id.clone(), user)
+            })
+            .collect();
+        for clear in &mut meeting.public_chat_clears {
+            clear.name = self.name(&clear.name, clear.ext_id.as_deref());
+            clear.user_id = self.of_internal_id(&clear.user_id);
+            clear.ext_id = clear.ext_id.as_deref().map(|id| self.of_ext_id(id));
+        }
+        for (_, name) in &mut meeting.api_joins {
+            *name = self.of_name(name);
+        }
+        for session in &mut meeting.audio {
+            session.name = self.of_name(&session.name);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::model::{ChatClear, Mention};
+
+    fn message() -> Message {
+        let time = NaiveDate::from_ymd(2021, 3, 1).and_hms(10, 0, 0);
+        let mut msg = Message::example("w_alice", "Alice", "@Bob @Gast hi", time);
+        msg.ext_id = Some("moodle-1".to_string());
+        msg.mentions = vec![
+            Mention {
+                user_id: "w_bob".to_string(),
+                name: "Bob".to_string(),
+                ext_id: Some("moodle-2".to_string()),
+            },
+            Mention {
+                user_id: "w_gast".to_string(),
+                name: "Gast".to_string(),
+                ext_id: None,
+            },
+        ];
+        msg
+    }
+
+    fn anonymized(key: &str) -> Message {
+        let mut msg = message();
+        assert!(Pseudonyms::new(key).message(&mut msg).unwrap());
+        msg
+    }
+
+    #[test]
+    fn the_same_key_gives_the_same_pseudonyms() {
+        let msg = anonymized("secret");
+        assert_eq!(msg.author, Pseudonyms::new("secret").of_ext_id("moodle-1"));
+        assert!(msg.author.starts_with("p-") && msg.author.len() == 2 + DIGITS);
+        assert_eq!(msg.ext_id.as_deref(), Some(msg.author.as_str()));
+        assert!(msg.author_id.starts_with("u-"));
+        assert!(msg.id.starts_with("m-"));
+        let again = anonymized("secret");
+        assert_eq!(
+            (&again.author, &again.author_id, &again.id),
+            (&msg.author, &msg.author_id, &msg.id)
+        );
+        // the text is left as it is
+        assert_eq!(msg.message, "@Bob @Gast hi");
+    }
+
+    #[test]
+    fn another_key_gives_other_pseudonyms() {
+        let (msg, other) = (anonymized("secret"), anonymized("other secret"));
+        assert_ne!(msg.author, other.author);
+        assert_ne!(msg.author_id, other.author_id);
+        assert_ne!(msg.id, other.id);
+    }
+
+    #[test]
+    fn ids_and_names_have_their_own_domains() {
+        let pseudonyms = Pseudonyms::new("secret");
+        // somebody whose external id is the internal id or the name of another participant
+        assert_ne!(
+            pseudonyms.of_internal_id("w_1"),
+            pseudonyms.of_ext_id("w_1")
+        );
+        assert_ne!(pseudonyms.of_name("Alice"), pseudonyms.of_ext_id("Alice"));
+        assert_eq!(pseudonyms.name("Alice", None), pseudonyms.of_name("Alice"));
+        assert_eq!(
+            pseudonyms.name("Alice", Some("moodle-1")),
+            pseudonyms.of_ext_id("moodle-1")
+        );
+    }
+
+    #[test]
+    fn mentions_are_replaced() {
+        let pseudonyms = Pseudonyms::new("secret");
+        let msg = anonymized("secret");
+        let mentions: Vec<(&str, &str, Option<&str>)> = msg
+            .mentions
+            .iter()
+            .map(|m| (m.user_id.as_str(), m.name.as_str(), m.ext_id.as_deref()))
+            .collect();
+        let bob = pseudonyms.of_ext_id("moodle-2");
+        let (bob_id, gast_id) = (
+            pseudonyms.of_internal_id("w_bob"),
+            pseudonyms.of_internal_id("w_gast"),
+        );
+        let gast = pseudonyms.of_name("Gast");
+        assert_eq!(
+            mentions,
+            [
+                (bob_id.as_str(), bob.as_str(), Some(bob.as_str())),
+                (gast_id.as_str(), gast.as_str(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn the_clears_of_the_public_chat_are_replaced() {
+        let time = NaiveDate::from_ymd(2021, 3, 1).and_hms(10, 0, 0);
+        let mut meeting = Meeting::new("meeting".to_string(), time);
+        meeting.public_chat_clears.push(ChatClear {
+            time,
+            user_id: "w_alice".to_string(),
+            name: "Alice".to_string(),
+            ext_id: Some("moodle-1".to_string()),
+        });
+        let mut pseudonyms = Pseudonyms::new("secret");
+        pseudonyms.meeting(&mut meeting).unwrap();
+        let clear = &meeting.public_chat_clears[0];
+        assert_eq!(clear.name, pseudonyms.of_ext_id("moodle-1"));
+        assert_eq!(clear.ext_id.as_deref(), Some(clear.name.as_str()));
+        assert_eq!(clear.user_id, pseudonyms.of_internal_id("w_alice"));
+    }
+}
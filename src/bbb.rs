@@ -0,0 +1,177 @@
+//! Enriching the meetings with the metadata the BigBlueButton API knows about them.
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use sha1::{Digest, Sha1};
+
+use crate::model::Meeting;
+
+/// Responses in the cache are used instead of calling the API again for this long.
+const CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The number of record ids asked for in one `getRecordings` call so the urls do not get too long.
+const RECORDS_PER_CALL: usize = 25;
+
+/// What the API knows about one meeting.
+#[derive(Default)]
+struct Info {
+    name: Option<String>,
+    external_id: Option<String>,
+    metadata: Vec<(String, String)>,
+    playback_url: Option<String>,
+}
+
+/// A BigBlueButton server whose API responses are cached in a local directory.
+pub struct Api {
+    /// The url of the API, e.g. `https://bbb.example.org/bigbluebutton/api`.
+    url: String,
+    /// The shared secret the calls are signed with.
+    secret: String,
+    cache: PathBuf,
+}
+
+/// The default cache directory: `$XDG_CACHE_HOME/chatter` or `~/.cache/chatter`.
+pub fn default_cache() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("chatter")
+}
+
+impl Api {
+    pub fn new(url: String, secret: String, cache: PathBuf) -> Self {
+        Api {
+            url: url.trim_end_matches('/').to_string(),
+            secret,
+            cache,
+        }
+    }
+
+    /// The signed url of an API call, the checksum is the SHA-1 of the call name, the query and the secret.
+    fn call_url(&self, call: &str, query: &str) -> String {
+        let checksum = sha1_hex(&format!("{}{}{}", call, query, self.secret));
+        let separator = if query.is_empty() { "" } else { "&" };
+        format!(
+            "{}/{}?{}{}checksum={}",
+            self.url, call, query, separator, checksum
+        )
+    }
+
+    /// Call the API or take the response from the cache if it is recent enough.
+    fn call(&self, call: &str, query: &str) -> io::Result<String> {
+        // the queries get too long for file names
+        let key = sha1_hex(&format!("{}/{}?{}", self.url, call, query));
+        let path = self.cache.join(format!("{}-{}.xml", call, key));
+        let fresh = fs::metadata(&path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age < CACHE_MAX_AGE);
+        if fresh {
+            return fs::read_to_string(&path);
+        }
+        let response = ureq::get(&self.call_url(call, query))
+            .call()
+            .map_err(|e| io::Error::other(format!("{}: {}", call, e)))?
+            .into_string()?;
+        fs::create_dir_all(&self.cache)?;
+        fs::write(&path, &response)?;
+        Ok(response)
+    }
+
+    /// Add the names, the metadata and the playback urls to the meetings.
+    ///
+    /// The running meetings come from `getMeetings`, the ended ones from `getRecordings` (if they were recorded).
+    pub fn enrich(&self, meetings: &mut HashMap<String, Meeting>) -> io::Result<()> {
+        let mut infos = HashMap::new();
+        parse(&self.call("getMeetings", "")?, "meeting", &mut infos)?;
+        let mut ids: Vec<&String> = meetings.keys().collect();
+        ids.sort();
+        for chunk in ids.chunks(RECORDS_PER_CALL) {
+            let ids: Vec<&str> = chunk.iter().map(|id| id.as_str()).collect();
+            let query = format!("recordID={}", ids.join(","));
+            parse(
+                &self.call("getRecordings", &query)?,
+                "recording",
+                &mut infos,
+            )?;
+        }
+        for (id, info) in infos {
+            if let Some(meeting) = meetings.get_mut(&id) {
+                meeting.name = meeting.name.take().or(info.name);
+                meeting.external_id = meeting.external_id.take().or(info.external_id);
+                meeting.metadata.extend(info.metadata);
+                meeting.playback_url = meeting.playback_url.take().or(info.playback_url);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Collect the meetings or recordings of a response keyed by their internal meeting id.
+fn parse(xml: &str, element: &str, infos: &mut HashMap<String, Info>) -> io::Result<()> {
+    let doc = roxmltree::Document::parse(xml)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let root = doc.root_element();
+    let return_code = child_text(root, "returncode");
+    if return_code != Some("SUCCESS") {
+        let message = child_text(root, "message").unwrap_or_default();
+        return Err(io::Error::other(format!(
+            "the API call failed: {}",
+            message
+        )));
+    }
+    for node in root.descendants().filter(|n| n.has_tag_name(element)) {
+        let id =
+            match child_text(node, "internalMeetingID").or_else(|| child_text(node, "recordID")) {
+                Some(id) => id.to_string(),
+                None => continue,
+            };
+        let info = infos.entry(id).or_default();
+        let name = child_text(node, "meetingName").or_else(|| child_text(node, "name"));
+        if let Some(name) = name {
+            info.name = Some(name.to_string());
+        }
+        // both calls name the external meeting id meetingID
+        if let Some(id) = child_text(node, "meetingID") {
+            info.external_id = Some(id.to_string());
+        }
+        if let Some(metadata) = node.children().find(|n| n.has_tag_name("metadata")) {
+            for entry in metadata.children().filter(|n| n.is_element()) {
+                let value = entry.text().unwrap_or_default().trim();
+                info.metadata
+                    .push((entry.tag_name().name().to_string(), value.to_string()));
+            }
+        }
+        // the presentation playback is the one the transcripts link into
+        let url = node
+            .descendants()
+            .filter(|n| n.has_tag_name("format"))
+            .find(|n| child_text(*n, "type") == Some("presentation"))
+            .and_then(|n| child_text(n, "url"));
+        if let Some(url) = url {
+            info.playback_url = Some(url.to_string());
+        }
+    }
+    Ok(())
+}
+
+fn sha1_hex(text: &str) -> String {
+    Sha1::digest(text)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|n| n.has_tag_name(name))
+        .and_then(|n| n.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
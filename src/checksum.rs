@@ -0,0 +1,223 @@
+//! Checksums and signatures of the written files, so archived transcripts can be proven unmodified.
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{cli::Options, split::Target};
+
+/// The name of the checksum manifest, in the format of `sha256sum` so `sha256sum -c` can check it.
+pub const SUMS: &str = "SHA256SUMS";
+
+/// The algorithms the files can be checksummed with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Algorithm {
+    Sha256,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(Algorithm::Sha256),
+            _ => Err(format!("unknown checksum: {}", s)),
+        }
+    }
+}
+
+/// The bytes of a digest as lower case hex digits.
+pub fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Passes everything on and computes the checksum of it on the way.
+pub struct Hashing<'a> {
+    inner: &'a mut dyn Write,
+    hasher: Sha256,
+}
+
+impl<'a> Hashing<'a> {
+    pub fn new(inner: &'a mut dyn Write) -> Self {
+        Hashing {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    pub fn digest(self) -> String {
+        hex(&self.hasher.finalize())
+    }
+}
+
+impl Write for Hashing<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The checksum of a file that is already written.
+pub fn of_file(path: &Path) -> io::Result<String> {
+    let mut sink = io::sink();
+    let mut hashing = Hashing::new(&mut sink);
+    io::copy(&mut File::open(path)?, &mut hashing)?;
+    Ok(hashing.digest())
+}
+
+fn write_sums<W: Write + ?Sized>(out: &mut W, sums: &[(String, String)]) -> io::Result<()> {
+    for (name, sum) in sums {
+        writeln!(out, "{}  {}", sum, name)?;
+    }
+    Ok(())
+}
+
+/// Run the signing commands given on the command line on the file.
+fn sign(options: &Options, path: &Path) -> io::Result<()> {
+    let mut commands = Vec::new();
+    if let Some(command) = &options.sign_command {
+        // `{}` is replaced by the file, without it the file is the last argument
+        let path = path.display().to_string();
+        let command = if command.contains("{}") {
+            command.replace("{}", &path)
+        } else {
+            format!("{} {}", command, path)
+        };
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        commands.push(shell);
+    }
+    if let Some(key) = &options.minisign_key {
+        let mut minisign = Command::new("minisign");
+        minisign.arg("-S").arg("-s").arg(key).arg("-m").arg(path);
+        commands.push(minisign);
+    }
+    for mut command in commands {
+        let status = command.status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "signing {} failed: {}",
+                path.display(),
+                status
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Finish the target, with the checksums of all its files if they are requested.
+///
+/// A directory gets a `SHA256SUMS` file. An archive gets a `SHA256SUMS` entry and a `.sha256` file next to it with the checksum of the archive itself. The file with the checksums is signed.
+pub fn finish(options: &Options, mut target: Target, sums: &[(String, String)]) -> io::Result<()> {
+    if options.checksum.is_none() {
+        return target.finish();
+    }
+    target.file(SUMS, "", |out| write_sums(out, sums))?;
+    let signed = match &target {
+        Target::Dir(dir, _) => Some(dir.join(SUMS)),
+        Target::Archive(_) => None,
+    };
+    target.finish()?;
+    let signed = match (signed, &options.archive) {
+        (Some(signed), _) => signed,
+        (None, Some(archive)) => {
+            let name = archive
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let mut sidecar = archive.clone().into_os_string();
+            sidecar.push(".sha256");
+            let sidecar = PathBuf::from(sidecar);
+            let mut out = File::create(&sidecar)?;
+            write_sums(&mut out, &[(name, of_file(archive)?)])?;
+            sidecar
+        }
+        (None, None) => return Ok(()),
+    };
+    sign(options, &signed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// The SHA-256 of `abc` from FIPS 180-2.
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn options(test: &str) -> Options {
+        let dir =
+            std::env::temp_dir().join(format!("chatter-sums-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Options {
+            checksum: Some(Algorithm::Sha256),
+            output_dir: Some(dir),
+            ..Options::default()
+        }
+    }
+
+    /// Write a file `a.txt` with `abc` and finish the directory with its checksums.
+    fn write(options: &Options) -> io::Result<PathBuf> {
+        let mut target = Target::create(options)?;
+        let sum = target.file("a.txt", "", |out| out.write_all(b"abc"))?;
+        finish(options, target, &[("a.txt".to_string(), sum)])?;
+        Ok(options.output_dir.clone().unwrap())
+    }
+
+    #[test]
+    fn hashing_passes_everything_on() {
+        let mut out = Vec::new();
+        let mut hashing = Hashing::new(&mut out);
+        hashing.write_all(b"ab").unwrap();
+        hashing.write_all(b"c").unwrap();
+        assert_eq!(hashing.digest(), ABC);
+        assert_eq!(out, b"abc");
+    }
+
+    #[test]
+    fn the_sums_are_in_the_format_of_sha256sum() {
+        let dir = write(&options("format")).unwrap();
+        assert_eq!(of_file(&dir.join("a.txt")).unwrap(), ABC);
+        assert_eq!(
+            fs::read_to_string(dir.join(SUMS)).unwrap(),
+            format!("{}  a.txt\n", ABC)
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn the_sums_are_signed_with_the_command() {
+        let options = Options {
+            sign_command: Some("cp {} {}.sig".to_string()),
+            ..options("sign")
+        };
+        let dir = write(&options).unwrap();
+        assert_eq!(
+            fs::read(dir.join("SHA256SUMS.sig")).unwrap(),
+            fs::read(dir.join(SUMS)).unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_failing_signature_is_an_error() {
+        let options = Options {
+            sign_command: Some("false".to_string()),
+            ..options("failing")
+        };
+        let error = write(&options).unwrap_err();
+        assert!(error.to_string().starts_with("signing"), "{}", error);
+        fs::remove_dir_all(options.output_dir.unwrap()).unwrap();
+    }
+}
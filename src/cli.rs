@@ -0,0 +1,1868 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use regex::Regex;
+use whatlang::Lang;
+
+use crate::{
+    checksum::Algorithm,
+    collation::Collation,
+    compress::Compression,
+    document::HtmlStyle,
+    event::EventTypes,
+    expr::Expr,
+    grade::GradeFormula,
+    header::{self, HeaderField},
+    input, language,
+    model::{Meeting, Message, TimeDisplay},
+    normalize::Normalization,
+    provenance,
+    sample::Sampling,
+    split::SplitBy,
+    stats, table,
+    talk::Sink,
+    tenant,
+    transform::Oversized,
+};
+
+pub const USAGE: &str = "\
+Usage: chatter [export|stats|list|count|sar|report|query SQL|diff|convert|merge|explain LINE|inspect|schema] [OPTIONS] [FILE]...
+
+Reads BigBlueButton akka-apps log lines from the given files (or standard in if there are none) and prints the chats ordered by their meetings.
+
+Commands:
+  export                    print the transcripts, the same as no command
+  stats                     print the number of chats, messages and authors per meeting instead of the transcripts
+  list                      print one line per meeting: its handle, start, messages, participants and name
+  count                     print the number of meetings, chats, messages, authors and participants of all the inputs together
+  sar                       write a ZIP with the messages and presence of one person for a subject access request (needs --subject and --archive)
+  report                    print everything from, to (in the private chats with) and mentioning one person as one chronological transcript, e.g. to investigate an incident (needs --subject)
+  diff OLD NEW              print the messages added, removed or changed between two json or ndjson exports, exits with 1 if there are any
+  convert EXPORT...         write json, ndjson or csv exports (also .gz and .zst) again in another --format, without the logs
+  merge EXPORT... -o FILE   combine the exports of several servers into one json export (or ndjson or csv with --format), the meetings and messages that are in more than one are written once
+  query SQL                 run an SQL query over the tables meetings, messages and presence and print the result as a table (or with --format csv)
+  explain LINE              print step by step how one log line is read and whether its message is kept by the filters, exits with 1 if not
+  inspect                   print the envelope names of the first --sample lines, how often they occur and which fields are found in them, to see whether a BigBlueButton version is supported
+  schema                    print the JSON Schema of the json export (or of the ndjson lines with --format ndjson), the exports carry its schema_version
+
+Options:
+      --ignore-author NAME  drop the messages of this author, a regular expression matching the whole name (repeatable)
+      --ignore-author-file FILE
+                            drop the messages of the authors in this file, one name or regular expression per line
+      --dir DIR             read all the log files in DIR (compressed rotated files are skipped)
+  -f, --follow              keep reading the inputs and print every new message as soon as it arrives, SIGHUP reads the --field-map, --tenant-map and --ignore-author-file again
+      --input kafka|redis   read the events from a message broker instead of files, like --follow (needs the kafka or redis feature)
+      --input s3://BUCKET/PREFIX/
+                            read the log files under the prefix in S3 compatible storage, e.g. MinIO, the same as giving the URL as a file (needs the s3 feature, see the README for the credentials)
+      --input URL           read the log file at this http(s):// URL as it is downloaded, the same as giving the URL as a file
+      --input FILE          read this file, e.g. /dev/fd/3 of a process substitution or a named pipe, the same as giving it as a file
+      --input-format bbb|tool-chatter-json
+                            read the files that follow as akka-apps logs (the default) or as json, ndjson or csv exports of chatter, whose meetings are merged with those of the logs
+      --http-user USER:PASSWORD
+                            (http) the user and password for the basic authentication of the URLs
+      --http-header 'NAME: VALUE'
+                            (http) send this header with the requests for the URLs, e.g. 'Authorization: Bearer TOKEN' (repeatable)
+      --brokers LIST        (kafka) the comma separated host:port of the brokers
+      --topic TOPIC         (kafka) the topic the events are published to
+      --group GROUP         (kafka) the consumer group that remembers what was read (default chatter)
+      --url URL             (redis) the server of BigBlueButton, e.g. redis://127.0.0.1:6379
+      --channel NAME        (redis) subscribe to this channel instead of from-akka-apps-redis-channel (repeatable)
+      --format FORMAT       output format: text (default), json, ndjson, csv, markdown, html, epub (one chapter per meeting), odt, or srt and vtt (subtitles of the public chat for the recording)
+      --time-display wall|offset|both
+                            show the times of the messages as time of day (default), as +HH:MM:SS since the start of the meeting to find them in the recording, or both
+      --time-precision minutes|seconds|millis
+                            how exact the transcripts show the times, e.g. to see the order of a rapid exchange (default minutes, json, ndjson and csv always have the milliseconds)
+      --header-fields LIST  (text, markdown, html, epub) the fields under the id of every meeting instead of its start and name, a comma separated list of: name, extId, start, end, duration, participants, recording
+      --html-theme dark|light|auto
+                            (html) the colors of the transcript: light (default), dark, or as the system of the reader is set
+      --html-css FILE       (html) embed this stylesheet after the theme, e.g. with the colors and fonts of the institution
+      --field-map FILE      a TOML file with selectors for the fields of the chat events of other BigBlueButton versions
+      --include-raw         (json, ndjson) add the log line every message was read from
+      --no-bodies           leave out the message texts and keep the meetings, chats, authors, times and statistics, e.g. for people who may not read the chats
+      --participants        (export) print an attendance sheet as CSV instead: every participant with role, join and leave time
+      --participation-moodle
+                            (export) print the grades of the chat participation as CSV for the grade import of Moodle instead
+      --grade-formula LIST  (participation-moodle) the weights of the grade, a comma separated list of: meetings=W, messages=W, words=W, characters=W, max=N (default messages=1,max=100)
+      --grade-item NAME     (participation-moodle) the name of the grade column (default Chat participation)
+      --rollup INTERVAL     (export) print the number of messages and authors of every meeting per INTERVAL (e.g. 15m, 1h or 1d) as csv or json instead, without the texts
+      --segment-gap INTERVAL
+                            (export) split the chats into numbered conversations where no message was written for longer than INTERVAL, e.g. 30m
+      --segment-marker REGEX
+                            (export) start a new conversation with every message matching REGEX, e.g. '^#topic'
+      --tenant-map FILE     lines of `host tenant` to know which tenant (e.g. faculty) the servers in the log lines or the paths belong to
+      --tenant NAME         only keep the meetings of this tenant (repeatable)
+      --meta KEY=VALUE      only keep the meetings created with this metadata, e.g. bbb-context-id=42 (repeatable, every key has to have one of its values)
+      --moderators-only     only keep the messages of moderators
+      --viewers-only        only keep the messages of viewers (and authors without a known role)
+      --guests-only         only keep the messages of guests, the participants that were not authenticated
+      --respect-clear       drop the messages of the public chat from before a moderator cleared it the last time, as the participants saw it at the end
+      --since TIME          only keep the messages from this time of every meeting on, HH:MM of the day it started or +HH:MM after its start
+      --until TIME          only keep the messages up to this time of every meeting, HH:MM or +HH:MM like --since
+      --author-ext-id ID    only keep messages of the author with this external user id (repeatable)
+      --filter-expr EXPR    only keep the messages the expression is true for, e.g. 'author != \"SYSTEM\" and text | contains(\"http\")'
+      --chat ID             only keep the chat with this id, e.g. a private chat somebody complained about; the text transcript then quotes it with the meeting, the times its participants joined and left and the seconds of the messages
+      --events LIST         only read these types of events, a comma separated list of: meetings, joins, recording, locks, polls, chat, web (the bbb-web log) and audio (the FreeSWITCH log)
+      --exclude-events LIST skip these types of events, the same list as for --events
+      --normalize LIST      normalize the message texts, a comma separated list of: whitespace, strip-emoji, ascii, max-length=N
+      --detect-language     detect the language of every message, added to the json output and the stats as the share of every language per meeting
+      --languages LIST      (detect-language) only choose from these ISO 639-3 codes, e.g. deu,eng, which makes the short messages a lot more reliable
+      --anonymize-key SECRET
+                            replace the names and ids of the participants by pseudonyms derived from their external user id with this key, the same in every run with the same key
+      --max-memory SIZE     (export, csv, ndjson) move the meetings to a temporary database when they take more than about SIZE (e.g. 512M or 2G) of memory and write them batch by batch
+      --max-meetings N      (follow) keep at most N meetings in memory and forget the ones that were quiet the longest, for a follow that runs for weeks
+      --sample N            (inspect) read only the first N lines of the inputs (default 10000)
+      --max-message-lines N keep only the first N lines of long messages
+      --max-message-bytes N cap the texts of the messages at N bytes, e.g. of pasted source files, so spreadsheets and documents do not break
+      --oversized truncate|drop|flag
+                            what happens to the longer messages: keep the first N bytes (the default), drop them or mark them with their size in the json formats
+      --min-messages N      skip the meetings with fewer than N chat messages (e.g. echo tests)
+      --sample P%           (export, stats, list, count, query) keep about P percent of the meetings, chosen by --seed, e.g. to try an analysis of a large archive
+      --sample-meetings N   (export, stats, list, count, query) keep N of the meetings, chosen by --seed
+      --seed N              which meetings --sample and --sample-meetings choose, the same seed keeps the same meetings (default 0)
+      --unattributed FILE   write the chat messages without a meeting or chat id to FILE as CSV with their logged timestamps instead of only counting them
+      --max-clock-skew S    report the log lines whose time goes back more than S seconds (default 10)
+      --no-collapse-spam    print every message of a flood instead of collapsing near-identical repetitions
+      --participation       (stats) print the messages, words and characters of every participant over all meetings, e.g. for grading
+      --mentions            (stats) print who addresses whom with @Name over all meetings, the pairs with the most mentions first
+      --name-collisions     (stats) print the names used by different people in a meeting and the participants that changed their name during one, e.g. to find impersonations during an exam
+      --score-cmd CMD       score every message with this shell command: it reads one message per line and answers each with a number on a line (e.g. a toxicity model)
+      --min-score X         (score-cmd) only keep the messages with a score of at least X
+      --flagged             (stats, score-cmd) list the scored messages with the highest score first, e.g. with --min-score for a moderation report
+      --anomalies           (stats) list message bursts and authors dominating a meeting, most severe first
+      --max-author-share P  (stats) percentage of the messages of a meeting one author may write before it is an anomaly (default 50)
+      --sparklines          (stats) show the messages per minute of every meeting as a line of block characters
+      --histogram MEETING   (stats) also print the number of messages per author of this meeting (its id or handle) as a bar chart
+      --compare-room ID     (stats) line up the sessions of the room with this external meeting id and show the trend of their messages and participation
+      --sort-by COLUMN      (stats) sort the rows by this column, descending with a leading -, e.g. --sort-by -messages
+      --collation-locale LOCALE
+                            (stats, participants) sort the names by the rules of this language, e.g. de or sv (needs the icu feature)
+      --svg FILE            (stats) also draw the messages per minute, the polls and the joining and leaving participants of every meeting into an SVG image
+      --recording-base-url URL
+                            (markdown, html) link the message times into the recordings, `<recordId>` in URL is replaced by the record id
+      --recording-map FILE  lines of `meeting_id record_id` for recordings whose id is not the internal meeting id
+      --bbb-url URL         the BigBlueButton API (e.g. https://bbb.example.org/bigbluebutton/api) to get the names, metadata and recordings of the meetings from
+      --bbb-secret SECRET   the shared secret of the BigBlueButton API
+      --bbb-cache DIR       where the API responses are cached for a day (default ~/.cache/chatter)
+      --sink nextcloud-talk
+                            post the public chat of every meeting into a Nextcloud Talk conversation instead of printing the transcripts
+      --nextcloud-url URL   the Nextcloud server the conversation is on
+      --nextcloud-user USER the user that posts the chats
+      --nextcloud-password PASSWORD
+                            the (app) password of the user
+      --talk-conversation TOKEN
+                            the token of the conversation (the last part of its url)
+      --split-by author|meeting|month|week|day
+                            write one file per participant with all their messages, one file per meeting or one file per calendar period with the meetings that started in it, e.g. 2024/2024-03.ndjson (needs --output-dir or --archive), {tenant} in their path writes one directory or archive per tenant
+      --output FILE         write the output to FILE or to an s3://BUCKET/KEY (a KEY ending in / gets a file name with the time) instead of the standard out, compressed if it ends in .gz or .zst
+      --compress gzip|zstd  compress the output on the fly, also the standard out and every file of --split-by (zstd needs the zstd feature)
+      --no-pager            print into the terminal instead of showing a longer output in $PAGER (default less)
+  -o, --output-dir DIR      the directory the files of --split-by are written to, for merge the same as --output
+      --resume              do not write the files of --output-dir again that an earlier (interrupted) run completed
+      --jobs N              render N files of --split-by at the same time, they are still written in order (default: one per processor)
+      --progress            print every file of --split-by when it has been written
+      --subject NAME|ID     (sar, report) the person the bundle or report is about, matched against the external user ids and the names
+      --from DAY            (report) only the messages from this day on, YYYY-MM-DD
+      --to DAY              (report) only the messages up to and including this day, YYYY-MM-DD
+      --archive FILE        the ZIP file to write, with --split-by the files and an index.html go into it instead of --output-dir
+      --report FILE         write the checks of the run (parse errors, alert words, retention) as a JUnit XML report
+      --alert-word WORD     (report) fail the meetings with messages containing this word, ignoring case (repeatable)
+      --retention-days N    (report) fail the meetings older than N days
+      --notify-webhook URL  post a summary of the run to this Slack or Teams incoming webhook
+      --notify-template FILE
+                            the message of the summary with the placeholders {meetings}, {messages}, {floods}, {anomalies}, {skews} and {exports}, a JSON template is sent as it is
+      --rate-limit N/s      (sink, webhook) send at most N posts per second (or N/m per minute)
+      --max-inflight N      (sink, webhook) send N posts at the same time, the chats may end up out of order (default 1)
+      --max-retries N       (sink, webhook) try a post N more times with a growing pause when the receiver fails (default 5)
+      --dead-letter FILE    (sink, webhook) append the posts that still fail to FILE as JSON lines and go on with the rest
+      --checksum sha256     write a SHA256SUMS file with the checksums of all written files (with --output-dir, --archive or sar)
+      --sign-command CMD    sign the checksums with this shell command, `{}` is replaced by the file, e.g. 'gpg --detach-sign {}'
+      --minisign-key FILE   sign the checksums with minisign and this secret key
+      --deterministic       write exactly the same output for the same input: no time of the run, the same paths on all platforms
+      --dry-run             read and filter everything, then print how many meetings and messages would be written to which files with their sizes, without writing anything
+      --provenance          (export, convert, merge) record the version, the inputs with their checksums, the command line and the time of the run, in every meeting of the json format and in a provenance.json next to the other formats
+  -v, --verbose             print where the start time of every meeting comes from
+  -h, --help                print this help
+";
+
+/// The different formats the collected chats can be printed in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// The human readable transcript.
+    Text,
+    /// One JSON document containing all the meetings.
+    Json,
+    /// One JSON object per message and line.
+    Ndjson,
+    /// One row per message.
+    Csv,
+    /// A Markdown document.
+    Markdown,
+    /// A self contained HTML page.
+    Html,
+    /// An e-book with one chapter per meeting.
+    Epub,
+    /// An OpenDocument text with every meeting on its own pages.
+    Odt,
+    /// SubRip subtitles of the public chat.
+    Srt,
+    /// WebVTT subtitles of the public chat.
+    Vtt,
+}
+
+impl Format {
+    /// The file extension of files in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Json => "json",
+            Format::Ndjson => "ndjson",
+            Format::Csv => "csv",
+            Format::Markdown => "md",
+            Format::Html => "html",
+            Format::Epub => "epub",
+            Format::Odt => "odt",
+            Format::Srt => "srt",
+            Format::Vtt => "vtt",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "csv" => Ok(Format::Csv),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "html" => Ok(Format::Html),
+            "epub" => Ok(Format::Epub),
+            "odt" => Ok(Format::Odt),
+            "srt" => Ok(Format::Srt),
+            "vtt" | "webvtt" => Ok(Format::Vtt),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+/// Where the log lines are read from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Input {
+    /// The files, the directory or standard in.
+    Files,
+    /// A Kafka topic.
+    Kafka,
+    /// The Redis channels of BigBlueButton.
+    Redis,
+}
+
+impl FromStr for Input {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "files" => Ok(Input::Files),
+            "kafka" => Ok(Input::Kafka),
+            "redis" => Ok(Input::Redis),
+            _ => Err(format!("unknown input: {}", s)),
+        }
+    }
+}
+
+/// A time in every meeting, of the day the meeting started (`HH:MM`) or since its start (`+HH:MM`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SessionTime {
+    Clock(NaiveTime),
+    Offset(Duration),
+}
+
+impl FromStr for SessionTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (offset, time) = match s.strip_prefix('+') {
+            Some(time) => (true, time),
+            None => (false, s),
+        };
+        let time = NaiveTime::parse_from_str(time, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+            .map_err(|_| format!("{} is neither HH:MM nor +HH:MM", s))?;
+        Ok(if offset {
+            SessionTime::Offset(Duration::seconds(time.num_seconds_from_midnight() as i64))
+        } else {
+            SessionTime::Clock(time)
+        })
+    }
+}
+
+impl SessionTime {
+    /// The time in the meeting that started at `start`.
+    pub fn at(self, start: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            SessionTime::Clock(time) => start.date().and_time(time),
+            SessionTime::Offset(offset) => start + offset,
+        }
+    }
+}
+
+/// What to do with the collected meetings.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    /// Print the transcripts.
+    Transcript,
+    /// Print statistics about the meetings.
+    Stats,
+    /// Print one line per meeting.
+    List,
+    /// Print the totals of all meetings.
+    Count,
+    /// Bundle all data of one person for a subject access request.
+    Sar,
+    /// Print everything from, to and about one person.
+    Report,
+    /// Run an SQL query over the messages.
+    Query,
+    /// Compare two exports.
+    Diff,
+    /// Write exports again in another format.
+    Convert,
+    /// Combine exports into one.
+    Merge,
+    /// Show how one log line is read.
+    Explain,
+    /// Show the events of a log sample and their fields.
+    Inspect,
+    /// Print the JSON Schema of the exports.
+    Schema,
+}
+
+/// A regular expression that has to match the whole name of an author.
+fn author_pattern(pattern: &str) -> Result<Regex, String> {
+    Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| format!("--ignore-author: {}", e))
+}
+
+/// The posts per second of a rate like `10/s` or `30/m`.
+fn rate(rate: &str) -> Result<f64, String> {
+    let (count, unit) = rate.split_once('/').unwrap_or((rate, "s"));
+    let count: f64 = count
+        .parse()
+        .map_err(|e| format!("--rate-limit: {}: {}", rate, e))?;
+    let seconds = match unit {
+        "s" => 1.0,
+        "m" | "min" => 60.0,
+        _ => return Err(format!("--rate-limit: unknown unit {}, use s or m", unit)),
+    };
+    if !(count > 0.0 && count.is_finite()) {
+        return Err("--rate-limit has to be a number more than 0".to_string());
+    }
+    // the time between two posts has to fit into a Duration
+    std::time::Duration::try_from_secs_f64(seconds / count)
+        .map_err(|_| format!("--rate-limit: {} is too slow", rate))?;
+    Ok(count / seconds)
+}
+
+/// The length of an interval like `15m`, `1h` or `1d`.
+fn interval(flag: &str, interval: &str) -> Result<Duration, String> {
+    let split = interval
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(interval.len());
+    let (count, unit) = interval.split_at(split);
+    let count: i64 = count
+        .parse()
+        .map_err(|e| format!("{}: {}: {}", flag, interval, e))?;
+    let duration = match unit {
+        "s" => Duration::seconds(count),
+        "m" | "min" => Duration::minutes(count),
+        "h" => Duration::hours(count),
+        "d" => Duration::days(count),
+        _ => {
+            return Err(format!(
+                "{}: unknown unit {:?}, use s, m, h or d",
+                flag, unit
+            ))
+        }
+    };
+    if count <= 0 {
+        return Err(format!("{} has to be more than 0", flag));
+    }
+    Ok(duration)
+}
+
+/// The number of bytes of a size like `512M`, `2G` or just `1000000`.
+fn size(size: &str) -> Result<usize, String> {
+    let split = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (count, unit) = size.split_at(split);
+    let count: usize = count
+        .parse()
+        .map_err(|e| format!("--max-memory: {}: {}", size, e))?;
+    let factor = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        _ => {
+            return Err(format!(
+                "--max-memory: unknown unit {:?}, use K, M or G",
+                unit
+            ))
+        }
+    };
+    if count == 0 {
+        return Err("--max-memory has to be more than 0".to_string());
+    }
+    Ok(count * factor)
+}
+
+/// The options given on the command line.
+#[derive(Clone)]
+pub struct Options {
+    pub command: Command,
+    /// The SQL of the query command.
+    pub query: Option<String>,
+    /// The log line of the explain command.
+    pub explain: Option<String>,
+    /// How many lines the inspect command reads.
+    pub sample: usize,
+    pub format: Format,
+    /// How the times of the messages are shown in the transcripts.
+    pub time_display: TimeDisplay,
+    /// The fields of the header of every meeting, `None` for the start and the name in the heading.
+    pub header_fields: Option<Vec<HeaderField>>,
+    /// The theme and the custom stylesheet of the html transcript.
+    pub html: HtmlStyle,
+    /// Where to find the fields of the chat messages.
+    pub field_map: Option<PathBuf>,
+    /// Add the log lines to the messages of the JSON formats.
+    pub include_raw: bool,
+    /// Leave out the message texts, only their number of words and characters is written.
+    pub no_bodies: bool,
+    /// Print the participants with their join and leave times instead of the transcripts.
+    pub participants: bool,
+    /// Print the grades of the participation for the grade import of Moodle instead of the transcripts.
+    pub participation_moodle: bool,
+    /// Print the messages and authors per bucket of this length instead of the transcripts.
+    pub rollup: Option<Duration>,
+    /// A pause in a chat longer than this starts a new conversation segment.
+    pub segment_gap: Option<Duration>,
+    /// A message matching this starts a new conversation segment.
+    pub segment_marker: Option<Regex>,
+    /// About how many bytes the meetings may take in memory before they are spilled to disk.
+    pub max_memory: Option<usize>,
+    /// How many meetings are kept in memory while following the inputs.
+    pub max_meetings: Option<usize>,
+    /// How the grades are computed.
+    pub grade_formula: GradeFormula,
+    /// The name of the grade item the grades are imported into.
+    pub grade_item: String,
+    /// Only keep the messages of authors with one of these external user ids. Empty means keep everything.
+    pub author_ext_ids: Vec<String>,
+    /// Only keep the messages this expression is true for.
+    pub filter_expr: Option<Expr>,
+    /// Only keep the chat with this id.
+    pub chat: Option<String>,
+    /// Only keep the messages of moderators (`true`) or of viewers (`false`).
+    pub moderators: Option<bool>,
+    /// Only keep the messages written from this time of every meeting on.
+    pub since: Option<SessionTime>,
+    /// Only keep the messages written up to this time of every meeting.
+    pub until: Option<SessionTime>,
+    /// Only keep the messages of guests.
+    pub guests_only: bool,
+    /// Drop the messages of the public chat from before it was cleared.
+    pub respect_clear: bool,
+    /// Drop the messages of the authors whose whole name matches one of these, e.g. recording bots.
+    pub ignore_authors: Vec<Regex>,
+    /// The log files to read. If empty (and no directory is given) the standard in is read.
+    pub inputs: Vec<PathBuf>,
+    /// The exports of chatter to read again, given after `--input-format tool-chatter-json`.
+    pub imports: Vec<PathBuf>,
+    /// A directory of which all the log files are read.
+    pub dir: Option<PathBuf>,
+    /// A file mapping the hosts to their tenants.
+    pub tenant_map: Option<PathBuf>,
+    /// Only keep the meetings of these tenants. Empty means keep everything.
+    pub tenants: Vec<String>,
+    /// Only keep the meetings created with these metadata, the keys without `meta_` and in lower case.
+    pub metadata: Vec<(String, String)>,
+    /// Keep reading the inputs instead of printing the collected meetings at the end.
+    pub follow: bool,
+    /// Where the log lines come from, anything but files is always followed.
+    pub input: Input,
+    /// The Kafka brokers.
+    pub brokers: Vec<String>,
+    /// The Kafka topic of the events.
+    pub topic: Option<String>,
+    /// The Kafka consumer group.
+    pub group: String,
+    /// The Redis server.
+    pub url: Option<String>,
+    /// The Redis channels, empty means the one akka-apps publishes its events to.
+    pub channels: Vec<String>,
+    /// The `USER:PASSWORD` of the http(s) inputs.
+    pub http_user: Option<String>,
+    /// More headers of the requests for the http(s) inputs, e.g. with a token.
+    pub http_headers: Vec<(String, String)>,
+    /// How the message texts are normalized.
+    pub normalize: Normalization,
+    /// The types of the events that are read.
+    pub events: EventTypes,
+    /// Detect the language of every message.
+    pub detect_language: bool,
+    /// The languages the detection chooses from, all it knows if empty.
+    pub languages: Vec<Lang>,
+    /// The secret the pseudonyms of the participants are derived with.
+    pub anonymize_key: Option<String>,
+    /// Cut messages with more lines.
+    pub max_message_lines: Option<usize>,
+    /// The size in bytes the message texts are capped at.
+    pub max_message_bytes: Option<usize>,
+    /// What happens to the messages with more bytes.
+    pub oversized: Oversized,
+    /// Skip the meetings with fewer messages.
+    pub min_messages: Option<usize>,
+    /// Keep only a part of the meetings.
+    pub sampling: Option<Sampling>,
+    /// Which meetings the sampling chooses.
+    pub seed: u64,
+    /// Collapse floods of repeated messages in the text transcript.
+    pub collapse_spam: bool,
+    /// How many seconds the time may go back from one line to the next before it is reported.
+    pub max_clock_skew: i64,
+    /// Print the anomalies instead of the plain statistics.
+    pub anomalies: bool,
+    /// Print the messages, words and characters per participant instead of the plain statistics.
+    pub participation: bool,
+    /// Print who mentions whom instead of the plain statistics.
+    pub mentions: bool,
+    /// Print the shared and changed names instead of the plain statistics.
+    pub name_collisions: bool,
+    /// The shell command that scores every message.
+    pub score_cmd: Option<String>,
+    /// Only keep the messages with at least this score.
+    pub min_score: Option<f64>,
+    /// Print the scored messages instead of the plain statistics.
+    pub flagged: bool,
+    /// The percentage of messages of one author in a meeting that is flagged as an anomaly.
+    pub max_author_share: f64,
+    /// Add the activity of the meetings to the statistics.
+    pub sparklines: bool,
+    /// The meeting to print the messages per author of.
+    pub histogram: Option<String>,
+    /// The external meeting id of the room whose sessions are compared.
+    pub compare_room: Option<String>,
+    /// The column the rows of the statistics are sorted by, descending with a leading `-`.
+    pub sort_by: Option<String>,
+    /// The language whose rules sort the names of the authors.
+    pub collation_locale: Option<String>,
+    /// Where the timeline of the meetings is drawn to.
+    pub svg: Option<PathBuf>,
+    /// The playback url to link the messages into the recordings.
+    pub recording_base_url: Option<String>,
+    /// A file mapping meeting ids to record ids.
+    pub recording_map: Option<PathBuf>,
+    /// The BigBlueButton API the meetings are enriched from.
+    pub bbb_url: Option<String>,
+    /// The shared secret of the API.
+    pub bbb_secret: Option<String>,
+    /// Where the API responses are cached.
+    pub bbb_cache: Option<PathBuf>,
+    /// Send the transcripts somewhere else instead of printing them.
+    pub sink: Option<Sink>,
+    /// The Nextcloud server of the Talk sink.
+    pub nextcloud_url: Option<String>,
+    pub nextcloud_user: Option<String>,
+    pub nextcloud_password: Option<String>,
+    /// The token of the Talk conversation the chats are posted to.
+    pub talk_conversation: Option<String>,
+    /// Write several files instead of printing to standard out.
+    pub split_by: Option<SplitBy>,
+    /// Where the files of `split_by` are written to.
+    pub output_dir: Option<PathBuf>,
+    /// The file the output is written to instead of the standard out.
+    pub output: Option<PathBuf>,
+    /// How the output is compressed.
+    pub compress: Option<Compression>,
+    /// Print into the terminal without the `$PAGER`.
+    pub no_pager: bool,
+    /// Skip the files an earlier run has completely written.
+    pub resume: bool,
+    /// How many files of `--split-by` are rendered at the same time, by default one per processor.
+    pub jobs: Option<usize>,
+    /// Print every file of `--split-by` once it is written.
+    pub progress: bool,
+    /// The person of the subject access request.
+    pub subject: Option<String>,
+    /// The first day of the report.
+    pub from: Option<NaiveDate>,
+    /// The last day of the report.
+    pub to: Option<NaiveDate>,
+    /// The ZIP file to write.
+    pub archive: Option<PathBuf>,
+    /// Write the checksums of the written files.
+    pub checksum: Option<Algorithm>,
+    /// The shell command that signs the checksums.
+    pub sign_command: Option<String>,
+    /// The minisign secret key the checksums are signed with.
+    pub minisign_key: Option<PathBuf>,
+    /// Leave out everything that differs between runs on the same input.
+    pub deterministic: bool,
+    /// Print what would be written instead of writing it.
+    pub dry_run: bool,
+    /// Record where the export comes from.
+    pub provenance: bool,
+    /// The command line without the values of the secrets, for the provenance.
+    pub arguments: Vec<String>,
+    /// Tell more about how the logs were understood.
+    pub verbose: bool,
+    /// Where the JUnit report of the checks is written to.
+    pub report: Option<PathBuf>,
+    /// The words the messages are checked for in the report.
+    pub alert_words: Vec<String>,
+    /// How many days the chats may be kept, older meetings fail the check of the report.
+    pub retention_days: Option<i64>,
+    /// The incoming webhook that is told about the result of the run.
+    pub notify_webhook: Option<String>,
+    /// The message posted to the webhook.
+    pub notify_template: Option<PathBuf>,
+    /// How many posts per second the sinks and webhooks get at most.
+    pub rate_limit: Option<f64>,
+    /// How many posts are sent at the same time.
+    pub max_inflight: usize,
+    /// How often a failed post is tried again.
+    pub max_retries: u32,
+    /// Where the posts go that could not be delivered.
+    pub dead_letter: Option<PathBuf>,
+    /// Where the chat messages without a meeting or chat id are written to.
+    pub unattributed: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            command: Command::Transcript,
+            query: None,
+            explain: None,
+            sample: 10000,
+            format: Format::Text,
+            time_display: TimeDisplay::default(),
+            header_fields: None,
+            html: HtmlStyle::default(),
+            field_map: None,
+            include_raw: false,
+            no_bodies: false,
+            participants: false,
+            participation_moodle: false,
+            rollup: None,
+            segment_gap: None,
+            segment_marker: None,
+            max_memory: None,
+            max_meetings: None,
+            grade_formula: GradeFormula::default(),
+            grade_item: "Chat participation".to_string(),
+            author_ext_ids: Vec::new(),
+            filter_expr: None,
+            chat: None,
+            ignore_authors: Vec::new(),
+            moderators: None,
+            since: None,
+            until: None,
+            guests_only: false,
+            respect_clear: false,
+            inputs: Vec::new(),
+            imports: Vec::new(),
+            dir: None,
+            tenant_map: None,
+            tenants: Vec::new(),
+            metadata: Vec::new(),
+            follow: false,
+            input: Input::Files,
+            brokers: Vec::new(),
+            topic: None,
+            group: "chatter".to_string(),
+            url: None,
+            channels: Vec::new(),
+            http_user: None,
+            http_headers: Vec::new(),
+            normalize: Normalization::default(),
+            events: EventTypes::default(),
+            anonymize_key: None,
+            detect_language: false,
+            languages: Vec::new(),
+            max_message_lines: None,
+            max_message_bytes: None,
+            oversized: Oversized::Truncate,
+            min_messages: None,
+            sampling: None,
+            seed: 0,
+            collapse_spam: true,
+            max_clock_skew: 10,
+            anomalies: false,
+            participation: false,
+            mentions: false,
+            name_collisions: false,
+            score_cmd: None,
+            min_score: None,
+            flagged: false,
+            max_author_share: 50.0,
+            sparklines: false,
+            histogram: None,
+            compare_room: None,
+            sort_by: None,
+            collation_locale: None,
+            svg: None,
+            recording_base_url: None,
+            recording_map: None,
+            bbb_url: None,
+            bbb_secret: None,
+            bbb_cache: None,
+            sink: None,
+            nextcloud_url: None,
+            nextcloud_user: None,
+            nextcloud_password: None,
+            talk_conversation: None,
+            split_by: None,
+            output_dir: None,
+            output: None,
+            compress: None,
+            no_pager: false,
+            resume: false,
+            jobs: None,
+            progress: false,
+            subject: None,
+            from: None,
+            to: None,
+            archive: None,
+            checksum: None,
+            sign_command: None,
+            minisign_key: None,
+            deterministic: false,
+            dry_run: false,
+            provenance: false,
+            arguments: Vec::new(),
+            verbose: false,
+            report: None,
+            alert_words: Vec::new(),
+            retention_days: None,
+            notify_webhook: None,
+            notify_template: None,
+            rate_limit: None,
+            max_inflight: 1,
+            max_retries: 5,
+            dead_letter: None,
+            unattributed: None,
+        }
+    }
+}
+
+impl Options {
+    /// Parse the command line arguments (without the program name).
+    ///
+    /// Both `--flag value` and `--flag=value` are accepted.
+    pub fn parse<I: Iterator<Item = String>>(args: I) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut exclude_events = None;
+        let mut oversized = false;
+        let mut html = false;
+        // the --sample without a % given, the lines of inspect
+        let mut sample_lines = None;
+        // whether the files that follow are exports instead of logs
+        let mut exports = false;
+        let args: Vec<String> = args.collect();
+        options.arguments = provenance::redact(&args);
+        let mut args = args.into_iter().peekable();
+        let command = match args.peek().map(String::as_str) {
+            Some("export") => Some(Command::Transcript),
+            Some("stats") => Some(Command::Stats),
+            Some("list") => Some(Command::List),
+            Some("count") => Some(Command::Count),
+            Some("sar") => Some(Command::Sar),
+            Some("report") => Some(Command::Report),
+            Some("query") => Some(Command::Query),
+            Some("diff") => Some(Command::Diff),
+            Some("convert") => Some(Command::Convert),
+            Some("merge") => Some(Command::Merge),
+            Some("explain") => Some(Command::Explain),
+            Some("inspect") => Some(Command::Inspect),
+            Some("schema") => Some(Command::Schema),
+            _ => None,
+        };
+        if let Some(command) = command {
+            options.command = command;
+            args.next();
+        }
+        if options.command == Command::Merge {
+            // an archive again, unless --format says otherwise
+            options.format = Format::Json;
+        }
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{} needs a value", flag))
+            };
+            match flag.as_str() {
+                "--format" => options.format = value()?.parse()?,
+                "--time-display" => options.time_display.kind = value()?.parse()?,
+                "--time-precision" => options.time_display.precision = value()?.parse()?,
+                "--header-fields" => options.header_fields = Some(header::parse(&value()?)?),
+                "--html-theme" => {
+                    html = true;
+                    options.html.theme = value()?.parse()?;
+                }
+                "--html-css" => {
+                    html = true;
+                    let path = value()?;
+                    let css = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+                    // it is embedded in a <style> element, which would end there
+                    if css.to_lowercase().contains("</style") {
+                        return Err(format!(
+                            "{}: the stylesheet must not contain </style>",
+                            path
+                        ));
+                    }
+                    options.html.css = Some(css);
+                }
+                "--field-map" => options.field_map = Some(value()?.into()),
+                "--include-raw" => options.include_raw = true,
+                "--no-bodies" => options.no_bodies = true,
+                "--participants" => options.participants = true,
+                "--participation-moodle" => options.participation_moodle = true,
+                "--rollup" => options.rollup = Some(interval("--rollup", &value()?)?),
+                "--segment-gap" => {
+                    options.segment_gap = Some(interval("--segment-gap", &value()?)?)
+                }
+                "--segment-marker" => {
+                    let marker = value()?;
+                    options.segment_marker =
+                        Some(Regex::new(&marker).map_err(|e| format!("--segment-marker: {}", e))?)
+                }
+                "--max-memory" => options.max_memory = Some(size(&value()?)?),
+                "--max-meetings" => {
+                    options.max_meetings = Some(
+                        value()?
+                            .parse()
+                            .map_err(|e| format!("--max-meetings: {}", e))?,
+                    )
+                }
+                "--sample" => {
+                    let sample = value()?;
+                    // a share of the meetings, or the lines of inspect
+                    if sample.ends_with('%') {
+                        options.sampling = Some(Sampling::share(&sample)?);
+                    } else {
+                        options.sample = sample.parse().map_err(|e| format!("--sample: {}", e))?;
+                        sample_lines = Some(sample);
+                    }
+                }
+                "--sample-meetings" => {
+                    options.sampling = Some(Sampling::Meetings(
+                        value()?
+                            .parse()
+                            .map_err(|e| format!("--sample-meetings: {}", e))?,
+                    ))
+                }
+                "--seed" => {
+                    options.seed = value()?.parse().map_err(|e| format!("--seed: {}", e))?
+                }
+                "--grade-formula" => options.grade_formula = value()?.parse()?,
+                "--grade-item" => options.grade_item = value()?,
+                "--moderators-only" | "--viewers-only" => {
+                    let moderators = flag == "--moderators-only";
+                    if options.moderators == Some(!moderators) {
+                        return Err(
+                            "--moderators-only and --viewers-only exclude each other".to_string()
+                        );
+                    }
+                    options.moderators = Some(moderators);
+                }
+                "--guests-only" => options.guests_only = true,
+                "--respect-clear" => options.respect_clear = true,
+                "--since" => options.since = Some(value()?.parse()?),
+                "--until" => options.until = Some(value()?.parse()?),
+                "--author-ext-id" => options.author_ext_ids.push(value()?),
+                "--filter-expr" => {
+                    options.filter_expr = Some(
+                        value()?
+                            .parse()
+                            .map_err(|e| format!("--filter-expr: {}", e))?,
+                    )
+                }
+                "--chat" => options.chat = Some(value()?),
+                "--ignore-author" => options.ignore_authors.push(author_pattern(&value()?)?),
+                "--ignore-author-file" => {
+                    let path = value()?;
+                    let content =
+                        fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+                    // one name or pattern per line, empty lines and comments are skipped
+                    for line in content.lines().map(str::trim) {
+                        if !line.is_empty() && !line.starts_with('#') {
+                            options.ignore_authors.push(author_pattern(line)?);
+                        }
+                    }
+                }
+                "--dir" => options.dir = Some(value()?.into()),
+                "--tenant-map" => options.tenant_map = Some(value()?.into()),
+                "--tenant" => options.tenants.push(value()?),
+                "--meta" => {
+                    let meta = value()?;
+                    let (key, value) = meta
+                        .split_once('=')
+                        .ok_or_else(|| format!("--meta needs KEY=VALUE, not {}", meta))?;
+                    // BigBlueButton keeps the keys of the create call without the prefix in lower case
+                    let key = key.trim().to_lowercase();
+                    let key = key.strip_prefix("meta_").unwrap_or(&key);
+                    options.metadata.push((key.to_string(), value.to_string()));
+                }
+                "-f" | "--follow" => options.follow = true,
+                "--input" => {
+                    let input = value()?;
+                    // a path like /dev/fd/3 is read like the files
+                    let path = Path::new(&input);
+                    let file = input.parse::<Input>().is_err() && path.exists();
+                    if input::is_object(path) || input::is_url(path) || file {
+                        if exports {
+                            options.imports.push(input.into());
+                        } else {
+                            options.inputs.push(input.into());
+                        }
+                    } else {
+                        options.input = input.parse()?;
+                    }
+                }
+                "--input-format" => {
+                    exports = match value()?.as_str() {
+                        "bbb" => false,
+                        "tool-chatter-json" => true,
+                        format => return Err(format!("unknown input format: {}", format)),
+                    }
+                }
+                "--brokers" => options
+                    .brokers
+                    .extend(value()?.split(',').map(|b| b.trim().to_string())),
+                "--topic" => options.topic = Some(value()?),
+                "--group" => options.group = value()?,
+                "--url" => options.url = Some(value()?),
+                "--channel" => options.channels.push(value()?),
+                "--http-user" => options.http_user = Some(value()?),
+                "--http-header" => {
+                    let header = value()?;
+                    let (name, value) = header
+                        .split_once(':')
+                        .ok_or_else(|| format!("--http-header needs NAME: VALUE: {}", header))?;
+                    options
+                        .http_headers
+                        .push((name.trim().to_string(), value.trim().to_string()));
+                }
+                "--normalize" => options.normalize = value()?.parse()?,
+                "--events" => {
+                    options.events = value()?.parse().map_err(|e| format!("--events: {}", e))?
+                }
+                "--exclude-events" => {
+                    exclude_events = Some(
+                        value()?
+                            .parse::<EventTypes>()
+                            .map_err(|e| format!("--exclude-events: {}", e))?,
+                    )
+                }
+                "--anonymize-key" => options.anonymize_key = Some(value()?),
+                "--detect-language" => options.detect_language = true,
+                "--languages" => options.languages = language::parse_list(&value()?)?,
+                "--max-message-lines" => {
+                    options.max_message_lines = Some(
+                        value()?
+                            .parse()
+                            .map_err(|e| format!("--max-message-lines: {}", e))?,
+                    )
+                }
+                "--max-message-bytes" => {
+                    options.max_message_bytes = Some(
+                        value()?
+                            .parse()
+                            .map_err(|e| format!("--max-message-bytes: {}", e))?,
+                    )
+                }
+                "--oversized" => {
+                    oversized = true;
+                    options.oversized = value()?.parse()?;
+                }
+                "--min-messages" => {
+                    options.min_messages = Some(
+                        value()?
+                            .parse()
+                            .map_err(|e| format!("--min-messages: {}", e))?,
+                    )
+                }
+                "--no-collapse-spam" => options.collapse_spam = false,
+                "--max-clock-skew" => {
+                    options.max_clock_skew = value()?
+                        .parse()
+                        .map_err(|e| format!("--max-clock-skew: {}", e))?
+                }
+                "--anomalies" => options.anomalies = true,
+                "--participation" => options.participation = true,
+                "--mentions" => options.mentions = true,
+                "--name-collisions" => options.name_collisions = true,
+                "--score-cmd" => options.score_cmd = Some(value()?),
+                "--min-score" => {
+                    options.min_score = Some(
+                        value()?
+                            .parse()
+                            .map_err(|e| format!("--min-score: {}", e))?,
+                    )
+                }
+                "--flagged" => options.flagged = true,
+                "--max-author-share" => {
+                    options.max_author_share = value()?
+                        .parse()
+                        .map_err(|e| format!("--max-author-share: {}", e))?
+                }
+                "--sparklines" => options.sparklines = true,
+                "--histogram" => options.histogram = Some(value()?),
+                "--compare-room" => options.compare_room = Some(value()?),
+                "--sort-by" => options.sort_by = Some(value()?),
+                "--collation-locale" => options.collation_locale = Some(value()?),
+                "--svg" => options.svg = Some(value()?.into()),
+                "--recording-base-url" => options.recording_base_url = Some(value()?),
+                "--recording-map" => options.recording_map = Some(value()?.into()),
+                "--bbb-url" => options.bbb_url = Some(value()?),
+                "--bbb-secret" => options.bbb_secret = Some(value()?),
+                "--bbb-cache" => options.bbb_cache = Some(value()?.into()),
+                "--sink" => options.sink = Some(value()?.parse()?),
+                "--nextcloud-url" => options.nextcloud_url = Some(value()?),
+                "--nextcloud-user" => options.nextcloud_user = Some(value()?),
+                "--nextcloud-password" => options.nextcloud_password = Some(value()?),
+                "--talk-conversation" => options.talk_conversation = Some(value()?),
+                "--split-by" => options.split_by = Some(value()?.parse()?),
+                // merge writes one file and no directory
+                "-o" if options.command == Command::Merge => options.output = Some(value()?.into()),
+                "-o" | "--output-dir" => options.output_dir = Some(value()?.into()),
+                "--output" => options.output = Some(value()?.into()),
+                "--compress" => options.compress = Some(value()?.parse()?),
+                "--no-pager" => options.no_pager = true,
+                "--resume" => options.resume = true,
+                "--jobs" => {
+                    options.jobs = Some(value()?.parse().map_err(|e| format!("--jobs: {}", e))?)
+                }
+                "--progress" => options.progress = true,
+                "--subject" => options.subject = Some(value()?),
+                "--from" => {
+                    options.from = Some(
+                        NaiveDate::parse_from_str(&value()?, "%Y-%m-%d")
+                            .map_err(|e| format!("--from: {}", e))?,
+                    )
+                }
+                "--to" => {
+                    options.to = Some(
+                        NaiveDate::parse_from_str(&value()?, "%Y-%m-%d")
+                            .map_err(|e| format!("--to: {}", e))?,
+                    )
+                }
+                "--archive" => options.archive = Some(value()?.into()),
+                "--checksum" => options.checksum = Some(value()?.parse()?),
+                "--sign-command" => options.sign_command = Some(value()?),
+                "--minisign-key" => options.minisign_key = Some(value()?.into()),
+                "--deterministic" => options.deterministic = true,
+                "--dry-run" => options.dry_run = true,
+                "--provenance" => options.provenance = true,
+                "-v" | "--verbose" => options.verbose = true,
+                "--report" => options.report = Some(value()?.into()),
+                "--alert-word" => options.alert_words.push(value()?),
+                "--retention-days" => {
+                    options.retention_days = Some(
+                        value()?
+                            .parse()
+                            .map_err(|e| format!("--retention-days: {}", e))?,
+                    )
+                }
+                "--notify-webhook" => options.notify_webhook = Some(value()?),
+                "--notify-template" => options.notify_template = Some(value()?.into()),
+                "--rate-limit" => options.rate_limit = Some(rate(&value()?)?),
+                "--max-inflight" => {
+                    options.max_inflight = value()?
+                        .parse()
+                        .map_err(|e| format!("--max-inflight: {}", e))?
+                }
+                "--max-retries" => {
+                    options.max_retries = value()?
+                        .parse()
+                        .map_err(|e| format!("--max-retries: {}", e))?
+                }
+                "--dead-letter" => options.dead_letter = Some(value()?.into()),
+                "--unattributed" => options.unattributed = Some(value()?.into()),
+                "-h" | "--help" => {
+                    print!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                _ if flag.starts_with('-') && flag != "-" => {
+                    return Err(format!("unknown argument: {}", flag))
+                }
+                _ if options.command == Command::Query && options.query.is_none() => {
+                    options.query = Some(flag)
+                }
+                _ if options.command == Command::Explain && options.explain.is_none() => {
+                    options.explain = Some(flag)
+                }
+                _ if exports => options.imports.push(flag.into()),
+                _ => options.inputs.push(flag.into()),
+            }
+        }
+        if let Some(exclude) = &exclude_events {
+            options.events.exclude(exclude);
+        }
+        if options.events.is_empty() {
+            return Err("--events and --exclude-events leave no events to read".to_string());
+        }
+        if options.input != Input::Files {
+            if !options.inputs.is_empty() || options.dir.is_some() {
+                return Err(format!("--input {:?} reads no files", options.input).to_lowercase());
+            }
+            // the live inputs are followed like the files
+            options.follow = true;
+        }
+        if options.input == Input::Kafka {
+            if cfg!(not(feature = "kafka")) {
+                return Err("--input kafka needs chatter built with the kafka feature".to_string());
+            }
+            if options.brokers.is_empty() || options.topic.is_none() {
+                return Err("--input kafka needs --brokers and a --topic".to_string());
+            }
+        }
+        if options.input == Input::Redis {
+            if cfg!(not(feature = "redis")) {
+                return Err("--input redis needs chatter built with the redis feature".to_string());
+            }
+            if options.url.is_none() {
+                return Err("--input redis needs a --url".to_string());
+            }
+        }
+        if options.max_inflight == 0 {
+            return Err("--max-inflight has to be at least 1".to_string());
+        }
+        if !options.languages.is_empty() && !options.detect_language {
+            return Err("--languages needs --detect-language".to_string());
+        }
+        if options.follow && options.detect_language {
+            return Err("--detect-language can not be combined with --follow".to_string());
+        }
+        if options.anonymize_key.is_some() {
+            if options.command == Command::Sar {
+                return Err("a subject access request can not be anonymized".to_string());
+            }
+            if options.include_raw {
+                return Err(
+                    "--include-raw can not be combined with --anonymize-key, the log lines contain the names"
+                        .to_string(),
+                );
+            }
+            if options.unattributed.is_some() {
+                return Err(
+                    "--unattributed can not be combined with --anonymize-key, the messages without a meeting are not anonymized"
+                        .to_string(),
+                );
+            }
+        }
+        if options.no_bodies {
+            if options.include_raw || options.unattributed.is_some() {
+                return Err(
+                    "--no-bodies can not be combined with --include-raw or --unattributed, they contain the texts"
+                        .to_string(),
+                );
+            }
+            if options.sink.is_some() {
+                return Err("--no-bodies leaves nothing to post to the --sink".to_string());
+            }
+            if !options.alert_words.is_empty() {
+                return Err(
+                    "--alert-word can not be combined with --no-bodies, there are no texts to search"
+                        .to_string(),
+                );
+            }
+        }
+        // the archive of a subject access request always has an html transcript
+        if html && options.format != Format::Html && options.command != Command::Sar {
+            return Err("--html-theme and --html-css only work with the html format".to_string());
+        }
+        if options.header_fields.is_some()
+            && !matches!(
+                options.format,
+                Format::Text | Format::Markdown | Format::Html | Format::Epub
+            )
+            && options.command != Command::Sar
+        {
+            return Err(
+                "--header-fields only works with the text, markdown, html and epub formats"
+                    .to_string(),
+            );
+        }
+        if options.include_raw && !matches!(options.format, Format::Json | Format::Ndjson) {
+            return Err("--include-raw only works with the json and ndjson formats".to_string());
+        }
+        if options.bbb_url.is_some() != options.bbb_secret.is_some() {
+            return Err("--bbb-url and --bbb-secret are needed together".to_string());
+        }
+        if options.command == Command::Sar
+            && (options.subject.is_none() || options.archive.is_none())
+        {
+            return Err("sar needs a --subject and an --archive".to_string());
+        }
+        if options.command == Command::Report {
+            if options.subject.is_none() {
+                return Err("report needs a --subject".to_string());
+            }
+            if options
+                .from
+                .zip(options.to)
+                .is_some_and(|(from, to)| from > to)
+            {
+                return Err("--from is after --to".to_string());
+            }
+            if options.anonymize_key.is_some() || options.max_memory.is_some() {
+                return Err(
+                    "report can not be combined with --anonymize-key or --max-memory".to_string(),
+                );
+            }
+        } else if options.from.is_some() || options.to.is_some() {
+            return Err("--from and --to only work with report".to_string());
+        }
+        if options.command == Command::Query {
+            if options.query.is_none() {
+                return Err("query needs an SQL statement".to_string());
+            }
+            if !matches!(options.format, Format::Text | Format::Csv) {
+                return Err("query only prints text and csv".to_string());
+            }
+        }
+        if matches!(options.command, Command::List | Command::Count)
+            && !matches!(
+                options.format,
+                Format::Text | Format::Csv | Format::Json | Format::Markdown
+            )
+        {
+            return Err("list and count are written as text, csv, json or markdown".to_string());
+        }
+        if options.command == Command::Explain
+            && (options.explain.is_none() || !options.inputs.is_empty())
+        {
+            return Err("explain needs exactly one log line".to_string());
+        }
+        if options.command == Command::Inspect && options.sample == 0 {
+            return Err("--sample has to be at least 1".to_string());
+        }
+        if let Some(lines) = sample_lines.filter(|_| options.command != Command::Inspect) {
+            return Err(format!(
+                "--sample {} only gives the lines of inspect, did you mean --sample {}% or --sample-meetings {}?",
+                lines, lines, lines
+            ));
+        }
+        if options.command == Command::Schema {
+            if !matches!(options.format, Format::Text | Format::Json | Format::Ndjson) {
+                return Err("schema only prints the schemas of json and ndjson".to_string());
+            }
+            if !options.inputs.is_empty() {
+                return Err("schema reads no files".to_string());
+            }
+        }
+        if options.command == Command::Diff && options.inputs.len() != 2 {
+            return Err("diff needs the old and the new export".to_string());
+        }
+        if options.command == Command::Merge {
+            if options.inputs.is_empty() {
+                return Err("merge needs the exports to combine".to_string());
+            }
+            if !matches!(options.format, Format::Json | Format::Ndjson | Format::Csv) {
+                return Err(
+                    "merge writes json, ndjson or csv, convert writes the others".to_string(),
+                );
+            }
+        }
+        if options.follow
+            && matches!(
+                options.command,
+                Command::List
+                    | Command::Count
+                    | Command::Sar
+                    | Command::Report
+                    | Command::Query
+                    | Command::Diff
+                    | Command::Convert
+                    | Command::Merge
+                    | Command::Explain
+                    | Command::Inspect
+            )
+        {
+            return Err(
+                "--follow can not be combined with list, count, sar, report, query, diff, convert, merge, explain or inspect"
+                    .to_string(),
+            );
+        }
+        if !options.imports.is_empty() {
+            if !matches!(
+                options.command,
+                Command::Transcript | Command::Stats | Command::Sar | Command::Query
+            ) {
+                return Err(
+                    "--input-format tool-chatter-json only works with export, stats, sar and query"
+                        .to_string(),
+                );
+            }
+            if options.follow || options.max_memory.is_some() {
+                return Err(
+                    "--input-format tool-chatter-json can not be combined with --follow or --max-memory"
+                        .to_string(),
+                );
+            }
+        }
+        if options.follow && options.unattributed.is_some() {
+            return Err("--unattributed can not be combined with --follow".to_string());
+        }
+        let objects = options.inputs.iter().any(|path| input::is_object(path));
+        if objects || options.output.as_deref().is_some_and(input::is_object) {
+            if cfg!(not(feature = "s3")) {
+                return Err("s3:// needs chatter built with the s3 feature".to_string());
+            }
+            if objects && options.follow {
+                return Err("--follow can not read from s3://".to_string());
+            }
+        }
+        if options.follow && options.inputs.iter().any(|path| input::is_url(path)) {
+            return Err("--follow can not read from http(s):// URLs".to_string());
+        }
+        if options.follow && options.inputs.iter().any(|path| input::is_pipe(path)) {
+            let files = options
+                .inputs
+                .iter()
+                .any(|path| !input::is_pipe(path) && path.as_path() != Path::new("-"));
+            if files || options.dir.is_some() {
+                return Err(
+                    "--follow can not read named pipes together with files or a --dir".to_string(),
+                );
+            }
+        }
+        if options.compress.is_none() {
+            options.compress = options.output.as_deref().and_then(Compression::of);
+        }
+        if let Some(output) = options
+            .output
+            .as_mut()
+            .filter(|path| input::is_object(path) && path.to_string_lossy().ends_with('/'))
+        {
+            let extension = options
+                .compress
+                .map_or(String::new(), |c| format!(".{}", c.extension()));
+            let name = format!(
+                "chatter-{}.{}{}",
+                chrono::Utc::now().format("%Y%m%dT%H%M%SZ"),
+                options.format.extension(),
+                extension
+            );
+            *output = PathBuf::from(format!("{}{}", output.display(), name));
+        }
+        if options.output.is_some() || options.compress.is_some() {
+            if !matches!(
+                options.command,
+                Command::Transcript
+                    | Command::Stats
+                    | Command::List
+                    | Command::Count
+                    | Command::Report
+                    | Command::Query
+                    | Command::Convert
+                    | Command::Merge
+            ) {
+                return Err(
+                    "--output and --compress only work for export, stats, list, count, report, query, convert and merge"
+                        .to_string(),
+                );
+            }
+            if options.follow || options.sink.is_some() {
+                return Err(
+                    "--output and --compress can not be combined with --follow or --sink"
+                        .to_string(),
+                );
+            }
+            if options.output.is_some() && options.split_by.is_some() {
+                return Err("--output can not be combined with --split-by, it writes the files into the --output-dir".to_string());
+            }
+            if options.compress == Some(Compression::Zstd) && cfg!(not(feature = "zstd")) {
+                return Err("--compress zstd needs chatter built with the zstd feature".to_string());
+            }
+        }
+        if options.split_by.is_some() {
+            if options.output_dir.is_none() && options.archive.is_none() {
+                return Err("--split-by needs an --output-dir or an --archive".to_string());
+            }
+            if options.follow || !matches!(options.command, Command::Transcript | Command::Convert)
+            {
+                return Err("--split-by only works for transcripts and convert".to_string());
+            }
+        }
+        if options.sink == Some(Sink::NextcloudTalk) {
+            if options.nextcloud_url.is_none()
+                || options.nextcloud_user.is_none()
+                || options.nextcloud_password.is_none()
+                || options.talk_conversation.is_none()
+            {
+                return Err("--sink nextcloud-talk needs --nextcloud-url, --nextcloud-user, --nextcloud-password and --talk-conversation".to_string());
+            }
+            if options.follow
+                || options.split_by.is_some()
+                || options.command != Command::Transcript
+            {
+                return Err("--sink only works for transcripts".to_string());
+            }
+        }
+        if options.checksum.is_some()
+            && options.split_by.is_none()
+            && options.command != Command::Sar
+        {
+            return Err("--checksum only works for the files of --split-by and sar".to_string());
+        }
+        if options.dry_run {
+            if !matches!(
+                options.command,
+                Command::Transcript | Command::Convert | Command::Merge | Command::Sar
+            ) {
+                return Err("--dry-run only works with export, convert, merge and sar".to_string());
+            }
+            if options.follow || options.input != Input::Files || options.sink.is_some() {
+                return Err(
+                    "--dry-run can not be combined with --follow, --input or --sink".to_string(),
+                );
+            }
+            if options.max_memory.is_some() {
+                return Err("--dry-run can not be combined with --max-memory".to_string());
+            }
+        }
+        if options.provenance {
+            if !matches!(
+                options.command,
+                Command::Transcript | Command::Convert | Command::Merge
+            ) || options.follow
+                || options.sink.is_some()
+            {
+                return Err("--provenance only works with export, convert and merge".to_string());
+            }
+            if options.max_memory.is_some() {
+                return Err("--provenance can not be combined with --max-memory".to_string());
+            }
+            if options.participants || options.participation_moodle || options.rollup.is_some() {
+                return Err(
+                    "--provenance only works for transcripts, not with --participants, --participation-moodle or --rollup"
+                        .to_string(),
+                );
+            }
+            // only the json format carries it, the others get a file next to them
+            if options.format != Format::Json
+                && options.split_by.is_none()
+                && options.output.is_none()
+            {
+                return Err(format!(
+                    "--provenance with the {} format needs an --output to write the provenance next to",
+                    options.format.extension()
+                ));
+            }
+        }
+        if (options.sign_command.is_some() || options.minisign_key.is_some())
+            && options.checksum.is_none()
+        {
+            return Err("the checksums are signed, a --checksum is needed".to_string());
+        }
+        if options.jobs == Some(0) {
+            return Err("--jobs has to be at least 1".to_string());
+        }
+        if (options.jobs.is_some() || options.progress) && options.split_by.is_none() {
+            return Err("--jobs and --progress need --split-by".to_string());
+        }
+        if options.resume && (options.output_dir.is_none() || options.archive.is_some()) {
+            return Err("--resume only works when writing to an --output-dir".to_string());
+        }
+        if options.follow && options.respect_clear {
+            return Err(
+                "--respect-clear can not be combined with --follow, the messages are printed before the chat is cleared"
+                    .to_string(),
+            );
+        }
+        if oversized && options.max_message_bytes.is_none() {
+            return Err("--oversized needs a --max-message-bytes".to_string());
+        }
+        if options.max_message_bytes == Some(0) {
+            return Err("--max-message-bytes has to be at least 1".to_string());
+        }
+        if options.sampling.is_some() {
+            if !matches!(
+                options.command,
+                Command::Transcript
+                    | Command::Stats
+                    | Command::List
+                    | Command::Count
+                    | Command::Query
+            ) {
+                return Err(
+                    "--sample P% and --sample-meetings only work with export, stats, list, count and query"
+                        .to_string(),
+                );
+            }
+            if options.follow || options.max_memory.is_some() {
+                return Err(
+                    "--sample P% and --sample-meetings can not be combined with --follow or --max-memory"
+                        .to_string(),
+                );
+            }
+        }
+        if options.follow && options.min_messages.is_some() {
+            return Err("--min-messages can not be combined with --follow".to_string());
+        }
+        if options.follow && options.report.is_some() {
+            return Err("--report can not be combined with --follow".to_string());
+        }
+        if options.follow && options.notify_webhook.is_some() {
+            return Err("--notify-webhook can not be combined with --follow".to_string());
+        }
+        if (options.svg.is_some() || options.sparklines || options.histogram.is_some())
+            && options.command != Command::Stats
+        {
+            return Err("--svg, --sparklines and --histogram only work with stats".to_string());
+        }
+        if options.participants {
+            if options.command != Command::Transcript {
+                return Err("--participants only works with export".to_string());
+            }
+            if !matches!(options.format, Format::Text | Format::Csv) {
+                return Err("--participants is always written as csv".to_string());
+            }
+            if options.follow || options.split_by.is_some() || options.sink.is_some() {
+                return Err(
+                    "--participants can not be combined with --follow, --split-by or --sink"
+                        .to_string(),
+                );
+            }
+        }
+        if options.compare_room.is_some() && options.command != Command::Stats {
+            return Err("--compare-room only works with stats".to_string());
+        }
+        if options.participation_moodle {
+            if options.command != Command::Transcript || options.participants {
+                return Err("--participation-moodle only works with export".to_string());
+            }
+            if !matches!(options.format, Format::Text | Format::Csv) {
+                return Err("--participation-moodle is always written as csv".to_string());
+            }
+            if options.follow || options.split_by.is_some() || options.sink.is_some() {
+                return Err(
+                    "--participation-moodle can not be combined with --follow, --split-by or --sink"
+                        .to_string(),
+                );
+            }
+        }
+        if options.segment_gap.is_some() || options.segment_marker.is_some() {
+            if !matches!(options.command, Command::Transcript | Command::Sar) {
+                return Err("--segment-gap and --segment-marker only work with export".to_string());
+            }
+            if options.follow {
+                return Err(
+                    "--segment-gap and --segment-marker can not be combined with --follow"
+                        .to_string(),
+                );
+            }
+        }
+        if options.rollup.is_some() {
+            if options.command != Command::Transcript
+                || options.participants
+                || options.participation_moodle
+            {
+                return Err("--rollup only works with export".to_string());
+            }
+            if !matches!(options.format, Format::Text | Format::Csv | Format::Json) {
+                return Err("--rollup is written as csv or json".to_string());
+            }
+            if options.follow || options.split_by.is_some() || options.sink.is_some() {
+                return Err(
+                    "--rollup can not be combined with --follow, --split-by or --sink".to_string(),
+                );
+            }
+        }
+        if let Some(max) = options.max_meetings {
+            if !options.follow {
+                return Err(
+                    "--max-meetings only works with --follow and the live inputs".to_string(),
+                );
+            }
+            if max == 0 {
+                return Err("--max-meetings has to be at least 1".to_string());
+            }
+        }
+        if options.max_memory.is_some() {
+            if options.command != Command::Transcript
+                || options.participants
+                || options.participation_moodle
+                || options.rollup.is_some()
+                || !matches!(options.format, Format::Csv | Format::Ndjson)
+            {
+                return Err(
+                    "--max-memory only works with export as csv or ndjson, which are written meeting by meeting"
+                        .to_string(),
+                );
+            }
+            if options.follow
+                || options.split_by.is_some()
+                || options.sink.is_some()
+                || options.report.is_some()
+                || options.notify_webhook.is_some()
+            {
+                return Err(
+                    "--max-memory can not be combined with --follow, --split-by, --sink, --report or --notify-webhook"
+                        .to_string(),
+                );
+            }
+        }
+        if (options.min_score.is_some() || options.flagged) && options.score_cmd.is_none() {
+            return Err("--min-score and --flagged need a --score-cmd".to_string());
+        }
+        if options.follow && options.score_cmd.is_some() {
+            return Err("--score-cmd can not be combined with --follow".to_string());
+        }
+        if options.flagged
+            && (options.command != Command::Stats
+                || options.anomalies
+                || options.participation
+                || options.compare_room.is_some())
+        {
+            return Err(
+                "--flagged only works with stats and without --anomalies, --participation or --compare-room"
+                    .to_string(),
+            );
+        }
+        if options.participation {
+            if options.command != Command::Stats {
+                return Err("--participation only works with stats".to_string());
+            }
+            if options.anomalies || options.compare_room.is_some() {
+                return Err(
+                    "--participation can not be combined with --anomalies or --compare-room"
+                        .to_string(),
+                );
+            }
+        }
+        if options.mentions {
+            if options.command != Command::Stats {
+                return Err("--mentions only works with stats".to_string());
+            }
+            if options.anomalies
+                || options.participation
+                || options.flagged
+                || options.compare_room.is_some()
+            {
+                return Err(
+                    "--mentions can not be combined with --anomalies, --participation, --flagged or --compare-room"
+                        .to_string(),
+                );
+            }
+        }
+        if options.name_collisions {
+            if options.command != Command::Stats {
+                return Err("--name-collisions only works with stats".to_string());
+            }
+            if options.anomalies
+                || options.participation
+                || options.flagged
+                || options.mentions
+                || options.compare_room.is_some()
+            {
+                return Err(
+                    "--name-collisions can not be combined with --anomalies, --participation, --flagged, --mentions or --compare-room"
+                        .to_string(),
+                );
+            }
+        }
+        if options.command == Command::Stats {
+            if !matches!(
+                options.format,
+                Format::Text | Format::Csv | Format::Json | Format::Markdown
+            ) {
+                return Err("stats are written as text, csv, json or markdown".to_string());
+            }
+            if options.histogram.is_some() && options.format != Format::Text {
+                return Err("--histogram only works with the text format".to_string());
+            }
+            if let Some(sort_by) = &options.sort_by {
+                table::check_sort(sort_by, &stats::columns(&options))?;
+            }
+        } else if options.sort_by.is_some() {
+            return Err("--sort-by only works with stats".to_string());
+        }
+        Collation::new(options.collation_locale.as_deref())?;
+        if !options.tenants.is_empty() && options.tenant_map.is_none() {
+            return Err("--tenant needs a --tenant-map".to_string());
+        }
+        let per_tenant = [&options.archive, &options.output_dir]
+            .iter()
+            .filter_map(|path| path.as_ref())
+            .any(|path| path.to_string_lossy().contains(tenant::PLACEHOLDER));
+        if per_tenant && (options.tenant_map.is_none() || options.split_by.is_none()) {
+            return Err(
+                "{tenant} in --archive or --output-dir needs --split-by and a --tenant-map"
+                    .to_string(),
+            );
+        }
+        if options.follow && options.command == Command::Stats {
+            return Err("--follow can not be combined with stats".to_string());
+        }
+        if options.follow && !matches!(options.format, Format::Text | Format::Ndjson | Format::Csv)
+        {
+            return Err("--follow only works with the text, ndjson and csv formats".to_string());
+        }
+        Ok(options)
+    }
+
+    /// Check if any filter is given on the command line.
+    pub fn filters(&self) -> bool {
+        !self.author_ext_ids.is_empty()
+            || self.filter_expr.is_some()
+            || self.chat.is_some()
+            || !self.ignore_authors.is_empty()
+            || self.moderators.is_some()
+            || self.guests_only
+            || self.since.is_some()
+            || self.until.is_some()
+    }
+
+    /// Check if a message written at `time` in the meeting that started at `start` is within `--since` and `--until`.
+    pub fn in_session(&self, start: NaiveDateTime, time: NaiveDateTime) -> bool {
+        self.since.is_none_or(|since| since.at(start) <= time)
+            && self.until.is_none_or(|until| time <= until.at(start))
+    }
+
+    /// Check if the meeting belongs to one of the tenants and has the metadata given on the command line.
+    pub fn keeps_meeting(&self, meeting: &Meeting) -> bool {
+        let tenant = self.tenants.is_empty()
+            || meeting
+                .tenant
+                .as_ref()
+                .is_some_and(|t| self.tenants.contains(t));
+        // the values of one key are alternatives
+        let metadata = self.metadata.iter().all(|(key, _)| {
+            self.metadata
+                .iter()
+                .filter(|(k, _)| k == key)
+                .any(|(_, value)| meeting.metadata.get(key) == Some(value))
+        });
+        tenant && metadata
+    }
+
+    /// Check if a message of the given meeting and chat passes the filters given on the command line.
+    pub fn keeps(&self, meeting_id: &str, chat_id: &str, msg: &Message) -> bool {
+        self.dropped_by(meeting_id, chat_id, msg).is_none()
+    }
+
+    /// The filter option that drops the message, `None` if it passes all of them.
+    pub fn dropped_by(
+        &self,
+        meeting_id: &str,
+        chat_id: &str,
+        msg: &Message,
+    ) -> Option<&'static str> {
+        if self.chat.as_ref().is_some_and(|chat| chat != chat_id) {
+            return Some("--chat");
+        }
+        if self.ignore_authors.iter().any(|r| r.is_match(&msg.author)) {
+            return Some("--ignore-author");
+        }
+        match self.moderators {
+            Some(true) if !msg.is_moderator() => return Some("--moderators-only"),
+            Some(false) if msg.is_moderator() => return Some("--viewers-only"),
+            _ => {}
+        }
+        if self.guests_only && !msg.guest {
+            return Some("--guests-only");
+        }
+        if !self.author_ext_ids.is_empty()
+            && !msg
+                .ext_id
+                .as_ref()
+                .is_some_and(|id| self.author_ext_ids.contains(id))
+        {
+            return Some("--author-ext-id");
+        }
+        if self
+            .filter_expr
+            .as_ref()
+            .is_some_and(|expr| !expr.matches(meeting_id, chat_id, msg))
+        {
+            return Some("--filter-expr");
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates() {
+        assert_eq!(rate("10/s"), Ok(10.0));
+        assert_eq!(rate("30/m"), Ok(0.5));
+        assert_eq!(rate("2"), Ok(2.0));
+        for invalid in ["0/s", "-1/s", "NaN", "inf/m", "1e-300/s", "1/h"] {
+            assert!(rate(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn the_input_format_tells_the_exports_from_the_logs() {
+        let options = parse(&[
+            "a.log",
+            "--input-format",
+            "tool-chatter-json",
+            "archive.json",
+            "b.ndjson",
+            "--input-format",
+            "bbb",
+            "c.log",
+        ])
+        .unwrap();
+        assert_eq!(
+            options.inputs,
+            [PathBuf::from("a.log"), PathBuf::from("c.log")]
+        );
+        assert_eq!(
+            options.imports,
+            [PathBuf::from("archive.json"), PathBuf::from("b.ndjson")]
+        );
+        assert!(parse(&["--input-format", "csv", "a.csv"]).is_err());
+    }
+
+    #[test]
+    fn a_sample_without_percent_is_only_for_inspect() {
+        assert_eq!(parse(&["inspect", "--sample", "5"]).unwrap().sample, 5);
+        let sampled = parse(&["export", "--sample", "5%"]).unwrap();
+        assert_eq!(sampled.sampling, Some(Sampling::Share(0.05)));
+        let error = parse(&["export", "--sample", "5"]).err().unwrap();
+        assert!(
+            error.contains("--sample 5% or --sample-meetings 5"),
+            "{}",
+            error
+        );
+        assert!(parse(&["--sample", "5"]).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+//! The order of the names of the authors: with the `icu` feature by the Unicode collation rules (of the `--collation-locale`), so "Ä" sorts next to "A" and Cyrillic or Greek names sort as their readers expect, without it by their code points.
+use std::cmp::Ordering;
+
+#[cfg(feature = "icu")]
+use icu_collator::{options::CollatorOptions, Collator, CollatorBorrowed};
+
+/// How names are compared.
+pub struct Collation {
+    #[cfg(feature = "icu")]
+    collator: CollatorBorrowed<'static>,
+}
+
+impl Collation {
+    /// The collation of the locale, e.g. `de` or `sv`, the root collation of Unicode without one.
+    #[cfg(feature = "icu")]
+    pub fn new(locale: Option<&str>) -> Result<Collation, String> {
+        let locale = match locale {
+            Some(locale) => icu_locale_core::Locale::try_from_str(locale)
+                .map_err(|e| format!("--collation-locale {}: {}", locale, e))?,
+            None => icu_locale_core::Locale::UNKNOWN,
+        };
+        let collator = Collator::try_new(locale.into(), CollatorOptions::default())
+            .map_err(|e| format!("--collation-locale: {}", e))?;
+        Ok(Collation { collator })
+    }
+
+    /// Without the `icu` feature there are no collation rules and no locale can be chosen.
+    #[cfg(not(feature = "icu"))]
+    pub fn new(locale: Option<&str>) -> Result<Collation, String> {
+        match locale {
+            Some(_) => Err("--collation-locale needs the icu feature".to_string()),
+            None => Ok(Collation {}),
+        }
+    }
+
+    /// Compare two names, the ones that are the same for the collation by their code points so the order is always the same.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        #[cfg(feature = "icu")]
+        let order = self.collator.compare(a, b);
+        #[cfg(not(feature = "icu"))]
+        let order = Ordering::Equal;
+        order.then_with(|| a.cmp(b))
+    }
+}
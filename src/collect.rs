@@ -0,0 +1,752 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::{
+    correlate,
+    event::{self, Event, EventTypes},
+    fields::FieldMap,
+    model::{AudioSession, Chat, ChatClear, Meeting, Message, TimeSource, User},
+    spill::Spill,
+    tenant::Tenants,
+};
+
+/// Two creations of the same internal meeting id closer than this are the same session, e.g. the one of akka-apps and the one of bbb-web.
+const SESSION_WINDOW: i64 = 12 * 60 * 60;
+
+/// The meeting of an event that belongs to one, including the ones of the bbb-web log, and whether the event created it.
+fn meeting_of(event: &Event) -> Option<(&str, NaiveDateTime, bool)> {
+    match event {
+        Event::External(correlate::Event::Created {
+            meeting_id, time, ..
+        }) => Some((meeting_id.as_str(), *time, true)),
+        Event::External(correlate::Event::Joined {
+            meeting_id, time, ..
+        }) => Some((meeting_id.as_str(), *time, false)),
+        Event::MeetingCreated {
+            meeting_id, time, ..
+        } => Some((meeting_id.as_str(), *time, true)),
+        _ => event
+            .meeting()
+            .map(|(meeting_id, time)| (meeting_id, time, false)),
+    }
+}
+
+/// Collects the meetings and their chats from the log lines.
+#[derive(Default)]
+pub struct Collector {
+    pub meetings: HashMap<String, Meeting>,
+    /// Keep the log line of every message.
+    pub keep_raw: bool,
+    /// Where the fields of the chat messages are found.
+    pub fields: FieldMap,
+    /// The types of the events that are read, the others are skipped.
+    pub events: EventTypes,
+    /// The audio sessions with their member id by FreeSWITCH conference, they are assigned to the meetings by their voice bridge once everything is read.
+    pub audio: HashMap<String, Vec<(String, AudioSession)>>,
+    /// Which servers belong to which tenant.
+    pub tenants: Tenants,
+    /// The tenant of the file that is read, found in its path. A host in the line itself wins.
+    pub source_tenant: Option<String>,
+    /// The file that is read.
+    pub source: PathBuf,
+    /// The number of the line of the file that is read, counted from 1.
+    pub line_number: usize,
+    /// Watches the time going back.
+    pub clock: Clock,
+    /// The lines whose JSON could not be parsed.
+    pub parse_errors: Vec<ParseError>,
+    /// Where the meetings go that do not fit into memory any more, see `--max-memory`.
+    pub spill: Option<Spill>,
+    /// Why a line could not be written to the spill database, the reading has to stop.
+    pub spill_error: Option<io::Error>,
+    /// The sessions of every internal meeting id by their start with the key of their meeting, see [`Collector::session`].
+    pub sessions: HashMap<String, Vec<(NaiveDateTime, String)>>,
+    /// The chat messages without a meeting or chat id.
+    pub unattributed: Vec<Unattributed>,
+    /// Forget the meetings that were quiet the longest when there are more, see `--max-meetings`.
+    pub max_meetings: Option<usize>,
+    /// The number of the event that last touched every meeting, for `max_meetings`.
+    pub last_active: HashMap<String, u64>,
+    /// The number of events added to the meetings so far.
+    pub events_added: u64,
+}
+
+/// A log line whose JSON could not be parsed.
+pub struct ParseError {
+    /// The file it is in.
+    pub source: PathBuf,
+    pub line_number: usize,
+    pub error: String,
+    pub line: String,
+}
+
+/// A chat message whose event has no meeting or chat id, kept apart instead of making up a meeting or chat called `null`.
+pub struct Unattributed {
+    /// The file it is in.
+    pub source: PathBuf,
+    pub meeting_id: Option<String>,
+    pub chat_id: Option<String>,
+    pub message: Message,
+}
+
+/// Finds the clock skews in the files.
+#[derive(Default)]
+pub struct Clock {
+    /// The time of the last line of every file.
+    pub previous: HashMap<PathBuf, NaiveDateTime>,
+    /// How far the time may go back from one line to the next before it is reported as a clock skew.
+    pub max_skew: Option<Duration>,
+    /// The clock skews found so far.
+    pub skews: Vec<Skew>,
+}
+
+/// The time going back from one line of a file to the next, e.g. because of an NTP jump or logs of several hosts merged into one file.
+pub struct Skew {
+    pub source: PathBuf,
+    pub meeting_id: String,
+    /// The time of the line before.
+    pub before: NaiveDateTime,
+    /// The earlier time of the line after it.
+    pub after: NaiveDateTime,
+}
+
+impl Skew {
+    pub fn back(&self) -> Duration {
+        self.before - self.after
+    }
+}
+
+impl Clock {
+    /// Remember the time of a line of the file and report it if it goes back too far.
+    fn check(&mut self, source: &Path, meeting_id: &str, time: NaiveDateTime) {
+        let before = self.previous.insert(source.to_path_buf(), time);
+        if let (Some(before), Some(max_skew)) = (before, self.max_skew) {
+            if before - time > max_skew {
+                let skew = Skew {
+                    source: source.to_path_buf(),
+                    meeting_id: meeting_id.to_string(),
+                    before,
+                    after: time,
+                };
+                eprintln!(
+                    "clock skew in {}: the time goes back {} s from {} to {} (meeting {})",
+                    skew.source.display(),
+                    skew.back().num_seconds(),
+                    before.format("%d.%m.%Y %H:%M:%S"),
+                    time.format("%d.%m.%Y %H:%M:%S"),
+                    meeting_id
+                );
+                self.skews.push(skew);
+            }
+        }
+    }
+}
+
+impl Collector {
+    /// The following lines are read from this file.
+    pub fn begin(&mut self, path: &Path) {
+        self.source_tenant = self.tenants.find(&path.to_string_lossy());
+        self.source = path.to_path_buf();
+        self.line_number = 0;
+    }
+
+    /// Parse one log line and add its content to the meetings.
+    ///
+    /// Returns the meeting, the chat and the message if the line contained a new chat message.
+    pub fn line(&mut self, line: &str) -> Option<(&Meeting, &Chat, &Message)> {
+        self.line_number += 1;
+        let event = event::parse(&self.fields, line).filter(|e| self.is_read(e))?;
+        if self.spill.is_some() {
+            if let Some((meeting_id, time, created)) =
+                meeting_of(&event).filter(|(meeting_id, ..)| !event::is_missing(meeting_id))
+            {
+                let key = self.session(meeting_id, time, created);
+                let spill = self.spill.as_mut().expect("a spill");
+                let in_memory = match spill.record(&key, time, self.source_tenant.as_deref(), line)
+                {
+                    Ok(in_memory) => in_memory,
+                    Err(e) => {
+                        self.spill_error = Some(e);
+                        return None;
+                    }
+                };
+                if !in_memory {
+                    self.clock.check(&self.source, &key, time);
+                    return None;
+                }
+                spill.evict(&mut self.meetings, &key);
+            }
+        }
+        let prefix = &line[..line.find('{').unwrap_or(0)];
+        let tenant = self
+            .tenants
+            .find(prefix)
+            .or_else(|| self.source_tenant.clone());
+        let raw = self.keep_raw.then(|| line.to_string());
+        self.add(event, tenant, raw)
+    }
+
+    /// Add an event to the meetings, e.g. one of an [`EventStream`](crate::event::EventStream).
+    ///
+    /// Returns the meeting, the chat and the message if the event was a new chat message.
+    pub fn event(&mut self, event: Event) -> Option<(&Meeting, &Chat, &Message)> {
+        if !self.is_read(&event) {
+            return None;
+        }
+        let tenant = self.source_tenant.clone();
+        self.add(event, tenant, None)
+    }
+
+    /// Whether the type of the event is read, the lines that could not be read are always reported.
+    fn is_read(&self, event: &Event) -> bool {
+        event
+            .event_type()
+            .is_none_or(|event_type| self.events.contains(event_type))
+    }
+
+    fn add(
+        &mut self,
+        event: Event,
+        tenant: Option<String>,
+        raw: Option<String>,
+    ) -> Option<(&Meeting, &Chat, &Message)> {
+        let created = matches!(event, Event::MeetingCreated { .. });
+        if let Event::Message {
+            meeting_id,
+            chat_id,
+            ..
+        } = &event
+        {
+            if event::is_missing(meeting_id) || event::is_missing(chat_id) {
+                self.unattributed(event, raw);
+                return None;
+            }
+        }
+        // the other events without a meeting can not tell anything about one
+        if event
+            .meeting()
+            .is_some_and(|(meeting_id, _)| event::is_missing(meeting_id))
+        {
+            return None;
+        }
+        let (meeting_id, time) = match event.meeting() {
+            Some((meeting_id, time)) => (self.session(meeting_id, time, created), time),
+            None => {
+                match event {
+                    Event::External(event) => self.external(event, tenant),
+                    Event::Invalid { line, error: None } => eprintln!("{}", line),
+                    Event::Invalid {
+                        line,
+                        error: Some(e),
+                    } => {
+                        // If for some reason the message could not be parsed print the message at the very beginning.
+                        eprintln!("{}\n{}", e, &line[line.find('{').unwrap_or(0)..]);
+                        self.parse_errors.push(ParseError {
+                            source: self.source.clone(),
+                            line_number: self.line_number,
+                            error: e.to_string(),
+                            line,
+                        });
+                    }
+                    _ => {}
+                }
+                return None;
+            }
+        };
+
+        // create the meeting if it does not exist yet.
+        self.clock.check(&self.source, &meeting_id, time);
+        if !self.meetings.contains_key(&meeting_id) {
+            let meeting = Meeting::new(meeting_id.clone(), time);
+            self.meetings.insert(meeting_id.to_string(), meeting);
+        }
+        if let Some(max) = self.max_meetings {
+            self.events_added += 1;
+            self.last_active
+                .insert(meeting_id.clone(), self.events_added);
+            if self.meetings.len() > max {
+                self.forget_quietest();
+            }
+        }
+
+        // either get the newly created or the already existing meeting
+        let meeting = self
+            .meetings
+            .get_mut(&meeting_id)
+            .expect("there should be a meeting");
+        if meeting.tenant.is_none() {
+            meeting.tenant = tenant;
+        }
+        // the creation events tell when the meeting started, without them it is the earliest event as the lines are not necessarily in order
+        if let Event::MeetingCreated {
+            external_id,
+            welcome,
+            moderator_message,
+            metadata,
+            ..
+        } = event
+        {
+            meeting.metadata.extend(metadata);
+            meeting.time = time;
+            if external_id.is_some() {
+                meeting.external_id = external_id;
+            }
+            if welcome.is_some() {
+                meeting.welcome = welcome;
+            }
+            if moderator_message.is_some() {
+                meeting.moderator_message = moderator_message;
+            }
+            meeting.time_source = TimeSource::Created;
+            return None;
+        }
+        if meeting.time_source == TimeSource::EarliestEvent {
+            meeting.time = meeting.time.min(time);
+        }
+
+        let (chat_id, sender, sender_id, role, message, id) = match event {
+            Event::UserJoined {
+                user_id,
+                name,
+                ext_id,
+                role,
+                guest,
+                ..
+            } => {
+                let user = meeting
+                    .users
+                    .entry(user_id.clone())
+                    .or_insert_with(|| User {
+                        user_id,
+                        name,
+                        ext_id,
+                        role,
+                        guest,
+                        sessions: Vec::new(),
+                    });
+                user.sessions.push((time, None));
+                return None;
+            }
+            Event::UserLeft { user_id, .. } => {
+                if let Some(session) = meeting
+                    .users
+                    .get_mut(&user_id)
+                    .and_then(|u| u.sessions.last_mut())
+                    .filter(|(_, left)| left.is_none())
+                {
+                    session.1 = Some(time);
+                }
+                return None;
+            }
+            Event::RecordingChanged { recording, .. } => {
+                if let Some(recording) = recording {
+                    meeting.recording_changes.push((time, recording));
+                }
+                return None;
+            }
+            Event::LockSettingsChanged {
+                public_chat_locked, ..
+            } => {
+                if let Some(locked) = public_chat_locked {
+                    meeting.public_chat_locks.push((time, locked));
+                }
+                return None;
+            }
+            Event::PublicChatCleared { user_id, .. } => {
+                // without a join of the moderator their id stands for the name
+                let user = meeting.users.get(&user_id);
+                meeting.public_chat_clears.push(ChatClear {
+                    time,
+                    name: user.map_or_else(|| user_id.clone(), |u| u.name.clone()),
+                    ext_id: user.and_then(|u| u.ext_id.clone()),
+                    user_id,
+                });
+                return None;
+            }
+            Event::PollStarted { question, .. } => {
+                meeting.polls.push((time, question));
+                return None;
+            }
+            Event::Message {
+                chat_id,
+                author,
+                author_id,
+                role,
+                text,
+                id,
+                ..
+            } => (chat_id, author, author_id, role, text, id),
+            Event::MeetingCreated { .. } | Event::External(_) | Event::Invalid { .. } => {
+                return None
+            }
+        };
+
+        // check if the chat already exists if not create it
+        if !meeting.chats.contains_key(&chat_id) {
+            let chat = Chat {
+                chat_id: chat_id.clone(),
+                messages: Vec::new(),
+            };
+            meeting.chats.insert(chat_id.clone(), chat);
+        }
+
+        let user = meeting.users.get(&sender_id);
+        let ext_id = user.and_then(|u| u.ext_id.clone());
+        let role = role.or_else(|| user.and_then(|u| u.role.clone()));
+        let guest = user.is_some_and(|u| u.guest);
+        // add the message to the list
+        let chat = meeting.chats.get_mut(&chat_id).unwrap();
+        let id = id.unwrap_or_else(|| {
+            Message::derive_id(&meeting_id, &chat_id, &sender_id, time, &message)
+        });
+        // every message is twice in the logs, the second line has the same id. Messages of the same text with other ids are kept, e.g. a flood for spam::collapse.
+        if chat.messages.last().is_some_and(|last| last.id == id) {
+            return None;
+        }
+        chat.messages.push(Message {
+            id,
+            author: sender,
+            author_id: sender_id,
+            ext_id,
+            role,
+            guest,
+            message,
+            time,
+            repeated: None,
+            language: None,
+            score: None,
+            raw,
+            withheld: None,
+            // found once all the participants are known, see Meeting::resolve_users
+            mentions: Vec::new(),
+            segment: None,
+            oversized: None,
+        });
+
+        let meeting = &self.meetings[&meeting_id];
+        let chat = &meeting.chats[&chat_id];
+        chat.messages.last().map(|msg| (meeting, chat, msg))
+    }
+
+    /// Keep a chat message without a meeting or chat id, the second line of the same message right after it is dropped like for the chats.
+    fn unattributed(&mut self, event: Event, raw: Option<String>) {
+        let (meeting_id, chat_id, time, author, author_id, role, text, id) = match event {
+            Event::Message {
+                meeting_id,
+                chat_id,
+                time,
+                author,
+                author_id,
+                role,
+                text,
+                id,
+            } => (meeting_id, chat_id, time, author, author_id, role, text, id),
+            _ => return,
+        };
+        let id = id
+            .unwrap_or_else(|| Message::derive_id(&meeting_id, &chat_id, &author_id, time, &text));
+        let known = |id: String| (!event::is_missing(&id)).then_some(id);
+        let (meeting_id, chat_id) = (known(meeting_id), known(chat_id));
+        if self
+            .unattributed
+            .last()
+            .is_some_and(|last| last.message.id == id)
+        {
+            return;
+        }
+        self.unattributed.push(Unattributed {
+            source: self.source.clone(),
+            meeting_id,
+            chat_id,
+            message: Message {
+                id,
+                author,
+                author_id,
+                ext_id: None,
+                role,
+                guest: false,
+                message: text,
+                time,
+                repeated: None,
+                language: None,
+                score: None,
+                raw,
+                withheld: None,
+                mentions: Vec::new(),
+                segment: None,
+                oversized: None,
+            },
+        });
+    }
+
+    /// Drop the meeting whose last event is the longest ago, its messages were printed already. A later event of it starts the meeting again, without the participants and earlier messages.
+    fn forget_quietest(&mut self) {
+        let quietest = self
+            .last_active
+            .iter()
+            .min_by_key(|(_, active)| **active)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = quietest {
+            eprintln!("forgetting: {}", key);
+            self.last_active.remove(&key);
+            self.meetings.remove(&key);
+            self.sessions.retain(|_, sessions| {
+                sessions.retain(|(_, session)| *session != key);
+                !sessions.is_empty()
+            });
+        }
+    }
+
+    /// The key of the meeting an event of the internal meeting id at `time` belongs to.
+    ///
+    /// BigBlueButton can give a room that is created again the same internal meeting id, e.g. months later. So a creation that is more than 12 hours away from the start of the other sessions of the id starts a new one, keyed and named `<meeting id>@<creation time>`, and the other events belong to the latest session that started before them. The first session keeps the meeting id as it is.
+    pub fn session(&mut self, meeting_id: &str, time: NaiveDateTime, created: bool) -> String {
+        let sessions = self.sessions.entry(meeting_id.to_string()).or_default();
+        if sessions.is_empty() {
+            sessions.push((time, meeting_id.to_string()));
+        }
+        if created {
+            if let Some(session) = sessions
+                .iter_mut()
+                .find(|(start, _)| (*start - time).num_seconds().abs() <= SESSION_WINDOW)
+            {
+                session.0 = session.0.min(time);
+                return session.1.clone();
+            }
+            let key = format!("{}@{}", meeting_id, time.format("%Y-%m-%dT%H:%M:%S"));
+            sessions.push((time, key.clone()));
+            sessions.sort();
+            return key;
+        }
+        sessions
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= time)
+            .unwrap_or(&sessions[0])
+            .1
+            .clone()
+    }
+
+    fn meeting(
+        &mut self,
+        meeting_id: String,
+        time: NaiveDateTime,
+        tenant: Option<String>,
+    ) -> &mut Meeting {
+        let meeting = self
+            .meetings
+            .entry(meeting_id.clone())
+            .or_insert_with(|| Meeting::new(meeting_id, time));
+        if meeting.tenant.is_none() {
+            meeting.tenant = tenant;
+        }
+        meeting
+    }
+
+    /// Add an event of the bbb-web or FreeSWITCH log.
+    fn external(&mut self, event: correlate::Event, tenant: Option<String>) {
+        match event {
+            correlate::Event::Created {
+                meeting_id,
+                time,
+                voice_bridge,
+            } => {
+                let meeting_id = self.session(&meeting_id, time, true);
+                let meeting = self.meeting(meeting_id, time, tenant);
+                meeting.created = Some(time);
+                if meeting.time_source != TimeSource::Created {
+                    meeting.time = time;
+                    meeting.time_source = TimeSource::Api;
+                }
+                if voice_bridge.is_some() {
+                    meeting.voice_bridge = voice_bridge;
+                }
+            }
+            correlate::Event::Joined {
+                meeting_id,
+                time,
+                name,
+            } => {
+                let meeting_id = self.session(&meeting_id, time, false);
+                self.meeting(meeting_id, time, tenant)
+                    .api_joins
+                    .push((time, name))
+            }
+            correlate::Event::AudioJoined {
+                conference,
+                member_id,
+                name,
+                time,
+            } => self.audio.entry(conference).or_default().push((
+                member_id,
+                AudioSession {
+                    name,
+                    joined: time,
+                    left: None,
+                },
+            )),
+            correlate::Event::AudioLeft {
+                conference,
+                member_id,
+                time,
+            } => {
+                if let Some((_, session)) = self.audio.get_mut(&conference).and_then(|sessions| {
+                    sessions
+                        .iter_mut()
+                        .rev()
+                        .find(|(id, s)| *id == member_id && s.left.is_none())
+                }) {
+                    session.left = Some(time);
+                }
+            }
+        }
+    }
+
+    /// Give the meetings the audio sessions of their conferences.
+    ///
+    /// This is done after all the lines are read as the FreeSWITCH log only knows the voice bridge, which comes from the bbb-web log.
+    pub fn assign_audio(&mut self) {
+        let mut meetings = std::mem::take(&mut self.meetings);
+        for meeting in meetings.values_mut() {
+            self.assign_audio_to(meeting);
+        }
+        self.meetings = meetings;
+    }
+
+    /// Give one meeting the audio sessions of its conference, e.g. one that was read again after it was spilled.
+    pub fn assign_audio_to(&mut self, meeting: &mut Meeting) {
+        if let Some(sessions) = meeting
+            .voice_bridge
+            .as_ref()
+            .and_then(|bridge| self.audio.remove(bridge))
+        {
+            meeting.audio = sessions.into_iter().map(|(_, s)| s).collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2021, 3, day).and_hms(hour, 0, 0)
+    }
+
+    /// A chat message line of the log, `id` is left out for the versions that log none.
+    fn message_line(id: Option<&str>, millis: i64, text: &str) -> String {
+        let id = id.map_or_else(String::new, |id| format!(r#""id": "{}", "#, id));
+        format!(
+            r#"2021-03-01T10:00:00.000Z INFO  o.b.c.a.RedisRecorderActor - {{"envelope": {{"name": "GroupChatMessageBroadcastEvtMsg", "routing": {{"meetingId": "m"}}, "timestamp": {}}}, "core": {{"body": {{"chatId": "MAIN-PUBLIC-GROUP-CHAT", "msg": {{{}"sender": {{"id": "w_1", "name": "Eve"}}, "message": "{}"}}}}}}}}"#,
+            millis, id, text
+        )
+    }
+
+    fn public_chat(collector: &Collector) -> &Chat {
+        &collector.meetings["m"].chats["MAIN-PUBLIC-GROUP-CHAT"]
+    }
+
+    #[test]
+    fn a_flood_logged_twice_is_collapsed() {
+        let mut collector = Collector::default();
+        for i in 0..14 {
+            let line = message_line(Some(&format!("m{}", i)), 1614592800000 + i * 5000, "spam!");
+            // every message is in the log twice
+            assert!(collector.line(&line).is_some());
+            assert!(collector.line(&line).is_none());
+        }
+        let mut chat = public_chat(&collector).clone();
+        assert_eq!(chat.messages.len(), 14);
+        crate::spam::collapse(&mut chat);
+        assert_eq!(chat.messages.len(), 1);
+        assert_eq!(chat.messages[0].repeated.as_ref().unwrap().count, 14);
+    }
+
+    #[test]
+    fn only_chat_messages_without_a_meeting_are_unattributed() {
+        let mut collector = Collector::default();
+        for name in [
+            "UserTalkingVoiceEvtMsg",
+            "PresentationPageChangedEvtMsg",
+            "GroupChatMessageBroadcastEvtMsg",
+        ] {
+            let line = format!(
+                r#"{{"envelope": {{"name": "{}", "timestamp": 1614592800000}}, "core": {{"body": {{"msg": {{"sender": {{"id": "w_1", "name": "Eve"}}, "message": "hi"}}}}}}}}"#,
+                name
+            );
+            collector.line(&line);
+        }
+        assert_eq!(collector.unattributed.len(), 1);
+        let message = &collector.unattributed[0].message;
+        assert_eq!(
+            (message.author.as_str(), message.message.as_str()),
+            ("Eve", "hi")
+        );
+        assert!(collector.meetings.is_empty());
+    }
+
+    #[test]
+    fn the_double_line_of_a_message_without_an_id_is_dropped() {
+        let mut collector = Collector::default();
+        for line in [
+            message_line(None, 1614592800000, "hi"),
+            message_line(None, 1614592800000, "hi"),
+            message_line(None, 1614592805000, "hi"),
+        ] {
+            collector.line(&line);
+        }
+        let ids: Vec<&str> = public_chat(&collector)
+            .messages
+            .iter()
+            .map(|m| m.id.as_str())
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn creations_within_the_window_are_one_session() {
+        let mut collector = Collector::default();
+        assert_eq!(collector.session("m", at(1, 10), true), "m");
+        // akka-apps and bbb-web both log the creation
+        assert_eq!(collector.session("m", at(1, 11), true), "m");
+        assert_eq!(collector.session("m", at(1, 12), false), "m");
+    }
+
+    #[test]
+    fn an_id_reused_after_12_hours_is_a_new_session() {
+        let mut collector = Collector::default();
+        assert_eq!(collector.session("m", at(1, 10), true), "m");
+        assert_eq!(collector.session("m", at(1, 11), false), "m");
+        assert_eq!(
+            collector.session("m", at(8, 10), true),
+            "m@2021-03-08T10:00:00"
+        );
+        assert_eq!(
+            collector.session("m", at(8, 11), false),
+            "m@2021-03-08T10:00:00"
+        );
+        // a late line of the first session still belongs to it
+        assert_eq!(collector.session("m", at(2, 9), false), "m");
+        // the creation of the second session is recognized again
+        assert_eq!(
+            collector.session("m", at(8, 12), true),
+            "m@2021-03-08T10:00:00"
+        );
+    }
+
+    #[test]
+    fn events_before_the_first_session_belong_to_it() {
+        let mut collector = Collector::default();
+        assert_eq!(collector.session("m", at(1, 10), false), "m");
+        assert_eq!(collector.session("m", at(1, 9), false), "m");
+        assert_eq!(collector.session("other", at(1, 9), false), "other");
+    }
+}
@@ -0,0 +1,169 @@
+//! Compressing the output on the fly, the ndjson exports of a year of chats are tens of gigabytes.
+//!
+//! With the s3 feature the output can also be uploaded to `s3://bucket/key`, see [`crate::s3`].
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use flate2::write::GzEncoder;
+
+#[cfg(feature = "s3")]
+use crate::input;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compression {
+    Gzip,
+    /// Needs chatter built with the zstd feature.
+    Zstd,
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            _ => Err(format!("unknown compression: {}", s)),
+        }
+    }
+}
+
+impl Compression {
+    /// The extension of the compressed files, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// The compression of a file name ending in `.gz` or `.zst`.
+    pub fn of(path: &Path) -> Option<Compression> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gz") => Some(Compression::Gzip),
+            Some("zst") => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Where the written bytes end up.
+enum Sink<'a> {
+    Writer(Box<dyn Write + 'a>),
+    #[cfg(feature = "s3")]
+    Upload(Box<crate::s3::Upload>),
+}
+
+impl Sink<'_> {
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Sink::Writer(mut out) => out.flush(),
+            #[cfg(feature = "s3")]
+            Sink::Upload(upload) => upload.finish(),
+        }
+    }
+}
+
+impl Write for Sink<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::Writer(out) => out.write(buf),
+            #[cfg(feature = "s3")]
+            Sink::Upload(upload) => upload.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Writer(out) => out.flush(),
+            #[cfg(feature = "s3")]
+            Sink::Upload(upload) => upload.flush(),
+        }
+    }
+}
+
+enum Encoder<'a> {
+    Plain(Sink<'a>),
+    Gzip(GzEncoder<Sink<'a>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, Sink<'a>>),
+}
+
+/// Where the output goes, the standard out, a file, an object or another writer, compressed or not.
+pub struct Output<'a>(Encoder<'a>);
+
+impl Output<'static> {
+    /// Write to the file or to the standard out if there is none.
+    pub fn create(path: Option<&Path>, compression: Option<Compression>) -> io::Result<Self> {
+        let out = match path {
+            #[cfg(feature = "s3")]
+            Some(path) if input::is_object(path) => {
+                Sink::Upload(Box::new(crate::s3::Upload::create(path)?))
+            }
+            Some(path) => Sink::Writer(Box::new(BufWriter::new(
+                File::create(path)
+                    .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?,
+            ))),
+            None => Sink::Writer(Box::new(BufWriter::new(io::stdout()))),
+        };
+        Output::encode(out, compression)
+    }
+}
+
+impl<'a> Output<'a> {
+    /// Compress what is written into `out`, e.g. one file of a split output.
+    pub fn wrap(out: &'a mut dyn Write, compression: Option<Compression>) -> io::Result<Self> {
+        Output::encode(Sink::Writer(Box::new(out)), compression)
+    }
+
+    fn encode(out: Sink<'a>, compression: Option<Compression>) -> io::Result<Self> {
+        Ok(Output(match compression {
+            None => Encoder::Plain(out),
+            Some(Compression::Gzip) => {
+                Encoder::Gzip(GzEncoder::new(out, flate2::Compression::default()))
+            }
+            #[cfg(feature = "zstd")]
+            Some(Compression::Zstd) => Encoder::Zstd(zstd::Encoder::new(out, 0)?),
+            #[cfg(not(feature = "zstd"))]
+            Some(Compression::Zstd) => {
+                return Err(io::Error::other(
+                    "zstd needs chatter built with the zstd feature",
+                ))
+            }
+        }))
+    }
+
+    /// Write the end of the compressed stream and flush everything, an output that is not finished is cut off.
+    pub fn finish(self) -> io::Result<()> {
+        match self.0 {
+            Encoder::Plain(out) => out.finish(),
+            Encoder::Gzip(encoder) => encoder.finish()?.finish(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.finish()?.finish(),
+        }
+    }
+}
+
+impl Write for Output<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.0 {
+            Encoder::Plain(out) => out.write(buf),
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.0 {
+            Encoder::Plain(out) => out.flush(),
+            Encoder::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
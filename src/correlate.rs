@@ -0,0 +1,128 @@
+//! The bbb-web and FreeSWITCH logs, read next to the akka-apps logs to see when a meeting was created through the API and who was in the audio conference.
+use chrono::{DateTime, Duration, NaiveDateTime};
+use json::JsonValue;
+
+/// Something that happened outside of akka-apps.
+#[derive(Debug)]
+pub enum Event {
+    /// bbb-web created the meeting, the voice bridge is the number of its audio conference.
+    Created {
+        meeting_id: String,
+        time: NaiveDateTime,
+        voice_bridge: Option<String>,
+    },
+    /// Somebody joined the meeting through the API.
+    Joined {
+        meeting_id: String,
+        time: NaiveDateTime,
+        name: String,
+    },
+    /// A member entered a FreeSWITCH conference.
+    AudioJoined {
+        conference: String,
+        member_id: String,
+        name: String,
+        time: NaiveDateTime,
+    },
+    /// A member left a FreeSWITCH conference.
+    AudioLeft {
+        conference: String,
+        member_id: String,
+        time: NaiveDateTime,
+    },
+}
+
+/// The time at the start of a bbb-web log line, e.g. `2021-03-01T10:00:00.123Z` or `2021-03-01 10:00:00,123`.
+///
+/// Times with an offset are converted to UTC like the timestamps of akka-apps, times without one are taken as UTC.
+fn line_time(line: &str) -> Option<NaiveDateTime> {
+    let mut words = line.split_whitespace();
+    let first = words.next()?;
+    if let Ok(time) = DateTime::parse_from_rfc3339(first) {
+        return Some(time.naive_utc());
+    }
+    let with_second = format!("{} {}", first, words.next().unwrap_or_default()).replace(',', ".");
+    [first.replace(',', "."), with_second].iter().find_map(|s| {
+        ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+    })
+}
+
+fn text(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Null => None,
+        JsonValue::Short(_) | JsonValue::String(_) => value.as_str().map(str::to_string),
+        value => Some(value.to_string()),
+    }
+    .filter(|s| !s.is_empty())
+}
+
+/// The analytics data bbb-web logs for the API calls, e.g. `--analytics-- data={"meetingId": …, "logCode": "create_meeting", …}`.
+fn web_event(line: &str, data: &JsonValue) -> Option<Event> {
+    let meeting_id = text(&data["meetingId"]).or_else(|| text(&data["meetingid"]))?;
+    let time = line_time(line)?;
+    match data["logCode"].as_str()? {
+        "create_meeting" | "create_api" => Some(Event::Created {
+            meeting_id,
+            time,
+            voice_bridge: text(&data["voiceConf"]).or_else(|| text(&data["voiceBridge"])),
+        }),
+        "join_api" => Some(Event::Joined {
+            meeting_id,
+            time,
+            name: text(&data["name"])
+                .or_else(|| text(&data["fullname"]))
+                .unwrap_or_default(),
+        }),
+        _ => None,
+    }
+}
+
+/// A conference event of FreeSWITCH as an ESL client logs it in the JSON format.
+fn esl_event(data: &JsonValue) -> Option<Event> {
+    if data["Event-Subclass"] != "conference::maintenance" {
+        return None;
+    }
+    let conference = text(&data["Conference-Name"])?;
+    let member_id = text(&data["Member-ID"])?;
+    // the timestamp is in microseconds since epoch
+    let micros: i64 = text(&data["Event-Date-Timestamp"])?.parse().ok()?;
+    let time = NaiveDateTime::from_timestamp(0, 0) + Duration::microseconds(micros);
+    match data["Action"].as_str()? {
+        "add-member" => {
+            // BigBlueButton calls in as `<userId>_<n>-bbbID-<name>`
+            let caller = text(&data["Caller-Caller-ID-Name"]).unwrap_or_default();
+            let name = match caller.split_once("-bbbID-") {
+                Some((_, name)) => name.to_string(),
+                None => caller,
+            };
+            Some(Event::AudioJoined {
+                conference,
+                member_id,
+                name,
+                time,
+            })
+        }
+        "del-member" => Some(Event::AudioLeft {
+            conference,
+            member_id,
+            time,
+        }),
+        _ => None,
+    }
+}
+
+/// Check if the parsed JSON of a line comes from bbb-web or FreeSWITCH instead of akka-apps.
+pub fn is_external(data: &JsonValue) -> bool {
+    data.has_key("logCode") || data.has_key("Event-Name")
+}
+
+/// The event of a bbb-web or FreeSWITCH log line, `None` if it is not one the correlation needs.
+pub fn parse(line: &str, data: &JsonValue) -> Option<Event> {
+    if data.has_key("logCode") {
+        web_event(line, data)
+    } else {
+        esl_event(data)
+    }
+}
@@ -0,0 +1,361 @@
+//! Delivering the posts to the sinks and webhooks: limited in rate and concurrency, retried with an exponential backoff and kept in a dead letter file if they still fail, so a hiccup of the receiver does not lose anything.
+use std::{
+    collections::VecDeque,
+    fs::OpenOptions,
+    io::{self, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use json::object;
+
+use crate::cli::Options;
+
+/// The wait before the first retry, it doubles with every further one.
+const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest wait between two tries unless the receiver asks for more.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// One request to a sink.
+pub struct Post {
+    /// What is posted, for the messages, e.g. `the chat of MEETING`.
+    pub what: String,
+    pub url: String,
+    /// The headers are not written to the dead letter file as they contain the credentials.
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+/// Why a try failed.
+enum Failure {
+    /// Trying again later can help, maybe after the time the receiver asked for.
+    Temporary(String, Option<Duration>),
+    /// The receiver does not accept the post.
+    Permanent(String),
+}
+
+/// Try to post once.
+fn try_post(post: &Post) -> Result<(), Failure> {
+    let mut request = ureq::post(&post.url);
+    for (name, value) in &post.headers {
+        request = request.set(name, value);
+    }
+    match request.send_string(&post.body) {
+        Ok(_) => Ok(()),
+        // too many requests or a problem of the server
+        Err(ureq::Error::Status(status, response)) if status == 429 || status >= 500 => {
+            let retry_after = response
+                .header("Retry-After")
+                .and_then(|s| s.trim().parse().ok())
+                .map(Duration::from_secs);
+            Err(Failure::Temporary(
+                format!("{}: status code {}", post.url, status),
+                retry_after,
+            ))
+        }
+        Err(e @ ureq::Error::Status(..)) => Err(Failure::Permanent(e.to_string())),
+        Err(e) => Err(Failure::Temporary(e.to_string(), None)),
+    }
+}
+
+/// How the posts are delivered.
+pub struct Delivery {
+    /// How many posts are sent at the same time, with more than one the order is not kept.
+    pub max_inflight: usize,
+    /// The time between the start of two tries.
+    pub interval: Option<Duration>,
+    /// How often a post is tried again after it failed temporarily.
+    pub max_retries: u32,
+    /// Where the posts that could not be delivered are appended to, without one the delivery stops at the first.
+    pub dead_letter: Option<PathBuf>,
+}
+
+impl Delivery {
+    pub fn from_options(options: &Options) -> Delivery {
+        Delivery {
+            max_inflight: options.max_inflight,
+            interval: options
+                .rate_limit
+                .map(|per_second| Duration::from_secs_f64(1.0 / per_second)),
+            max_retries: options.max_retries,
+            dead_letter: options.dead_letter.clone(),
+        }
+    }
+
+    /// Wait until the rate limit allows the next try.
+    fn wait_turn(&self, next: &Mutex<Instant>) {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        let now = Instant::now();
+        let at = {
+            let mut next = next
+                .lock()
+                .expect("no thread panics while holding the lock");
+            let at = (*next).max(now);
+            *next = at + interval;
+            at
+        };
+        thread::sleep(at - now);
+    }
+
+    /// Try the post until it is delivered, it fails permanently or there are no retries left.
+    fn deliver(&self, post: &Post, next: &Mutex<Instant>) -> Result<(), String> {
+        let mut backoff = FIRST_BACKOFF;
+        let mut retries = 0;
+        loop {
+            self.wait_turn(next);
+            match try_post(post) {
+                Ok(()) => return Ok(()),
+                Err(Failure::Permanent(e)) => return Err(e),
+                Err(Failure::Temporary(e, _)) if retries == self.max_retries => return Err(e),
+                Err(Failure::Temporary(e, retry_after)) => {
+                    retries += 1;
+                    let wait = retry_after.unwrap_or(backoff);
+                    eprintln!(
+                        "posting {} failed ({}), retry {} of {} in {} s",
+                        post.what,
+                        e,
+                        retries,
+                        self.max_retries,
+                        wait.as_secs_f64()
+                    );
+                    thread::sleep(wait);
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    }
+
+    /// Append the posts that could not be delivered to the dead letter file, one JSON object per line.
+    fn write_dead_letters(&self, failed: &[(Post, String)]) -> io::Result<()> {
+        let path = match &self.dead_letter {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        for (post, error) in failed {
+            let letter = object! {
+                what: post.what.as_str(),
+                url: post.url.as_str(),
+                body: post.body.as_str(),
+                error: error.as_str(),
+            };
+            writeln!(file, "{}", letter.dump())?;
+        }
+        eprintln!(
+            "{} posts could not be delivered, they were written to {}",
+            failed.len(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// Send the posts, in their order as long as only one is in flight.
+    ///
+    /// Returns how many were delivered.
+    pub fn send(&self, posts: Vec<Post>) -> io::Result<usize> {
+        let queue = Mutex::new(posts.into_iter().collect::<VecDeque<_>>());
+        let next = Mutex::new(Instant::now());
+        let delivered = AtomicUsize::new(0);
+        let failed = Mutex::new(Vec::new());
+        let stop = AtomicBool::new(false);
+        thread::scope(|scope| {
+            for _ in 0..self.max_inflight {
+                scope.spawn(|| {
+                    while !stop.load(Ordering::Relaxed) {
+                        let post = match queue.lock().expect("no thread panics").pop_front() {
+                            Some(post) => post,
+                            None => return,
+                        };
+                        match self.deliver(&post, &next) {
+                            Ok(()) => {
+                                delivered.fetch_add(1, Ordering::Relaxed);
+                            }
+                            Err(e) => {
+                                // without a dead letter file nothing more is sent so the receiver can be fixed and the run repeated
+                                if self.dead_letter.is_none() {
+                                    stop.store(true, Ordering::Relaxed);
+                                }
+                                failed.lock().expect("no thread panics").push((post, e));
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        let failed = failed.into_inner().expect("no thread panicked");
+        match (failed.first(), &self.dead_letter) {
+            (Some((post, error)), None) => Err(io::Error::other(format!(
+                "could not post {}: {}",
+                post.what, error
+            ))),
+            _ => {
+                self.write_dead_letters(&failed)?;
+                Ok(delivered.into_inner())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{BufRead, BufReader, Read},
+        net::TcpListener,
+        sync::Arc,
+    };
+
+    use json::JsonValue;
+
+    use super::*;
+
+    /// A receiver that answers the posts with these status codes, the last one for all the posts after. Returns its url and the bodies it got.
+    fn receiver(statuses: &[u16]) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let (received, statuses) = (bodies.clone(), statuses.to_vec());
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().enumerate() {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                received
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8(body).unwrap());
+                let status = statuses[i.min(statuses.len() - 1)];
+                // the retries come right away
+                let response = format!(
+                    "HTTP/1.1 {} Status\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, bodies)
+    }
+
+    fn posts(url: &str, bodies: &[&str]) -> Vec<Post> {
+        bodies
+            .iter()
+            .map(|body| Post {
+                what: format!("the chat {}", body),
+                url: url.to_string(),
+                headers: vec![("Authorization", "Bearer secret".to_string())],
+                body: body.to_string(),
+            })
+            .collect()
+    }
+
+    fn delivery(max_retries: u32, dead_letter: Option<PathBuf>) -> Delivery {
+        Delivery {
+            max_inflight: 1,
+            interval: None,
+            max_retries,
+            dead_letter,
+        }
+    }
+
+    fn dead_letter(test: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "chatter-dead-letter-{}-{}.ndjson",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn a_temporary_failure_is_retried() {
+        let (url, bodies) = receiver(&[503, 429, 200]);
+        let delivered = delivery(5, None).send(posts(&url, &["a", "b"])).unwrap();
+        assert_eq!(delivered, 2);
+        assert_eq!(*bodies.lock().unwrap(), ["a", "a", "a", "b"]);
+    }
+
+    #[test]
+    fn the_rate_limit_spaces_the_posts() {
+        let (url, bodies) = receiver(&[200]);
+        let limited = Delivery {
+            interval: Some(Duration::from_millis(50)),
+            ..delivery(0, None)
+        };
+        let start = Instant::now();
+        assert_eq!(limited.send(posts(&url, &["a", "b", "c"])).unwrap(), 3);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(bodies.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn the_posts_that_still_fail_are_dead_letters() {
+        let (url, bodies) = receiver(&[503]);
+        let path = dead_letter("retries");
+        let delivered = delivery(2, Some(path.clone()))
+            .send(posts(&url, &["a", "b"]))
+            .unwrap();
+        assert_eq!(delivered, 0);
+        // every post is tried three times, the others are sent nevertheless
+        assert_eq!(bodies.lock().unwrap().len(), 6);
+        let letters = fs::read_to_string(&path).unwrap();
+        let letters: Vec<JsonValue> = letters.lines().map(|l| json::parse(l).unwrap()).collect();
+        assert_eq!(letters.len(), 2);
+        assert_eq!(letters[0]["what"], "the chat a");
+        assert_eq!(letters[0]["url"], url.as_str());
+        assert_eq!(letters[0]["body"], "a");
+        assert!(letters[0]["error"].as_str().unwrap().contains("503"));
+        // the credentials are not written
+        assert!(!fs::read_to_string(&path).unwrap().contains("secret"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_permanent_failure_is_not_retried() {
+        let (url, bodies) = receiver(&[400, 200]);
+        let path = dead_letter("permanent");
+        let delivered = delivery(5, Some(path.clone()))
+            .send(posts(&url, &["a", "b"]))
+            .unwrap();
+        assert_eq!(delivered, 1);
+        assert_eq!(*bodies.lock().unwrap(), ["a", "b"]);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn without_a_dead_letter_file_the_first_failure_stops() {
+        let (url, bodies) = receiver(&[500]);
+        let error = delivery(0, None)
+            .send(posts(&url, &["a", "b"]))
+            .unwrap_err();
+        assert!(
+            error.to_string().starts_with("could not post the chat a"),
+            "{}",
+            error
+        );
+        assert_eq!(*bodies.lock().unwrap(), ["a"]);
+    }
+}
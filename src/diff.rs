@@ -0,0 +1,211 @@
+//! Comparing two JSON exports of the same meetings.
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use json::JsonValue;
+
+/// Identifies a message in both exports.
+type Key = (String, String, String, String);
+
+/// The parts of a message that are compared.
+#[derive(PartialEq)]
+struct Entry {
+    author: String,
+    ext_user_id: String,
+    message: String,
+}
+
+fn text(value: &JsonValue) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+fn entry(msg: &JsonValue) -> Entry {
+    Entry {
+        author: text(&msg["author"]),
+        ext_user_id: text(&msg["ext_user_id"]),
+        message: text(&msg["message"]),
+    }
+}
+
+fn insert(
+    messages: &mut BTreeMap<Key, Vec<Entry>>,
+    meeting_id: &str,
+    chat_id: &str,
+    msg: &JsonValue,
+) {
+    let key = (
+        meeting_id.to_string(),
+        chat_id.to_string(),
+        text(&msg["time"]),
+        text(&msg["author_id"]),
+    );
+    messages.entry(key).or_default().push(entry(msg));
+}
+
+/// Read the messages of a `json` or `ndjson` export.
+fn load(path: &Path) -> io::Result<BTreeMap<Key, Vec<Entry>>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let invalid = |e: json::Error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), e),
+        )
+    };
+    let mut messages = BTreeMap::new();
+    if content.trim_start().starts_with('[') {
+        let doc = json::parse(&content).map_err(invalid)?;
+        for meeting in doc.members() {
+            let meeting_id = text(&meeting["meeting_id"]);
+            for chat in meeting["chats"].members() {
+                let chat_id = text(&chat["chat_id"]);
+                for msg in chat["messages"].members() {
+                    insert(&mut messages, &meeting_id, &chat_id, msg);
+                }
+            }
+        }
+    } else {
+        for line in content.lines().filter(|l| !l.trim().is_empty()) {
+            let msg = json::parse(line).map_err(invalid)?;
+            insert(
+                &mut messages,
+                &text(&msg["meeting_id"]),
+                &text(&msg["chat_id"]),
+                &msg,
+            );
+        }
+    }
+    Ok(messages)
+}
+
+fn write_entry<W: Write>(out: &mut W, sign: char, key: &Key, entry: &Entry) -> io::Result<()> {
+    let (meeting_id, chat_id, time, _) = key;
+    writeln!(
+        out,
+        "{} {} {} {} {}: {}",
+        sign,
+        time,
+        meeting_id,
+        chat_id,
+        entry.author,
+        entry.message.replace('\n', "\\n")
+    )
+}
+
+/// Print the messages that were added, removed or changed from the old to the new export.
+///
+/// Messages are matched by their meeting, chat, time and author id. Returns if there are any differences.
+pub fn run<W: Write>(out: &mut W, old: &Path, new: &Path) -> io::Result<bool> {
+    let old = load(old)?;
+    let mut new = load(new)?;
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    for (key, old_entries) in old {
+        let new_entries = new.remove(&key).unwrap_or_default();
+        let mut new_entries = new_entries.into_iter();
+        for old_entry in old_entries {
+            match new_entries.next() {
+                Some(new_entry) if new_entry == old_entry => {}
+                Some(new_entry) => {
+                    changed += 1;
+                    write_entry(out, '-', &key, &old_entry)?;
+                    write_entry(out, '+', &key, &new_entry)?;
+                }
+                None => {
+                    removed += 1;
+                    write_entry(out, '-', &key, &old_entry)?;
+                }
+            }
+        }
+        for new_entry in new_entries {
+            added += 1;
+            write_entry(out, '+', &key, &new_entry)?;
+        }
+    }
+    for (key, new_entries) in new {
+        for new_entry in new_entries {
+            added += 1;
+            write_entry(out, '+', &key, &new_entry)?;
+        }
+    }
+    eprintln!("{} added, {} removed, {} changed", added, removed, changed);
+    Ok(added + removed + changed > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const HELLO: &str = r#"{"meeting_id": "m", "chat_id": "c", "time": "2021-03-01T10:00:00.000", "author_id": "w_1", "author": "Alice", "message": "hello"}"#;
+
+    fn export(test: &str, name: &str, lines: &[&str]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "chatter-diff-{}-{}-{}",
+            test,
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    /// Whether the exports differ and what is printed.
+    fn diff(test: &str, old: &[&str], new: &[&str]) -> (bool, String) {
+        let (old, new) = (export(test, "old", old), export(test, "new", new));
+        let mut out = Vec::new();
+        let differ = run(&mut out, &old, &new).unwrap();
+        fs::remove_file(old).unwrap();
+        fs::remove_file(new).unwrap();
+        (differ, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn the_same_messages_do_not_differ() {
+        assert_eq!(diff("same", &[HELLO], &[HELLO]), (false, String::new()));
+        // the ndjson lines of a json export
+        let json = format!(
+            r#"[{{"meeting_id": "m", "chats": [{{"chat_id": "c", "messages": [{}]}}]}}]"#,
+            HELLO
+        );
+        assert!(!diff("json", &[&json], &[HELLO]).0);
+    }
+
+    #[test]
+    fn added_removed_and_changed_messages_differ() {
+        let bye = HELLO
+            .replace("hello", "bye")
+            .replace("10:00:00", "10:01:00");
+        let (differ, out) = diff("added", &[HELLO], &[HELLO, &bye]);
+        assert!(differ);
+        assert_eq!(out, "+ 2021-03-01T10:01:00.000 m c Alice: bye\n");
+        assert_eq!(
+            diff("removed", &[HELLO, &bye], &[HELLO]).1,
+            "- 2021-03-01T10:01:00.000 m c Alice: bye\n"
+        );
+        let (differ, out) = diff("changed", &[HELLO], &[&HELLO.replace("hello", "hallo")]);
+        assert!(differ);
+        assert_eq!(
+            out,
+            "- 2021-03-01T10:00:00.000 m c Alice: hello\n+ 2021-03-01T10:00:00.000 m c Alice: hallo\n"
+        );
+    }
+
+    #[test]
+    fn unreadable_exports_are_errors() {
+        let old = export("errors", "old", &[HELLO]);
+        let missing = std::env::temp_dir().join("chatter-diff-missing.ndjson");
+        assert!(run(&mut Vec::new(), &old, &missing).is_err());
+        let broken = export("errors", "broken", &["{not json"]);
+        assert_eq!(
+            run(&mut Vec::new(), &old, &broken).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        fs::remove_file(old).unwrap();
+        fs::remove_file(broken).unwrap();
+    }
+}
@@ -0,0 +1,411 @@
+//! The document formats: Markdown and HTML transcripts.
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use crate::{
+    header::{self, HeaderField},
+    model::{lock_text, segment_start, ChatEntry, Meeting, Message, TimeDisplay},
+    recording::Recordings,
+};
+
+/// The time of a message, a link into the recording if there is one.
+fn markdown_time(
+    meeting: &Meeting,
+    msg: &Message,
+    times: TimeDisplay,
+    recordings: Option<&Recordings>,
+) -> String {
+    let time = times.format(meeting, msg.time, "%H:%M");
+    match recordings.and_then(|r| r.link(meeting, msg.time)) {
+        Some(link) => format!("[{}]({})", time, link),
+        None => time,
+    }
+}
+
+/// Write the transcripts as Markdown with one heading per meeting and chat.
+pub fn write_markdown<'a, W: Write>(
+    out: &mut W,
+    meetings: impl Iterator<Item = &'a Meeting>,
+    times: TimeDisplay,
+    header: Option<&[HeaderField]>,
+    recordings: Option<&Recordings>,
+) -> io::Result<()> {
+    for meeting in meetings {
+        if let Some(fields) = header {
+            writeln!(out, "# {} ({})\n", meeting.meeting_id, meeting.handle())?;
+            let values = header::values(meeting, fields, recordings);
+            for (label, value) in &values {
+                writeln!(out, "- **{}:** {}", label, value)?;
+            }
+            if !values.is_empty() {
+                writeln!(out)?;
+            }
+        } else {
+            write!(
+                out,
+                "# {} - {} ({})",
+                meeting.time.format("%d.%m.%Y %H:%M"),
+                meeting.meeting_id,
+                meeting.handle()
+            )?;
+            match &meeting.name {
+                Some(name) => writeln!(out, " - {}\n", name)?,
+                None => writeln!(out, "\n")?,
+            }
+        }
+        for (label, lines) in meeting.welcome_messages() {
+            writeln!(out, "> **{}:** {}\n", label, lines.join("  \n> "))?;
+        }
+        for chat in meeting.sorted_chats() {
+            writeln!(out, "## {}\n", chat.chat_id)?;
+            let mut segment = None;
+            for entry in meeting.entries(chat) {
+                let msg = match entry {
+                    ChatEntry::Message(msg) => msg,
+                    ChatEntry::Lock(time, locked) => {
+                        writeln!(
+                            out,
+                            "* {} *{}*",
+                            times.format(meeting, time, "%H:%M"),
+                            lock_text(locked)
+                        )?;
+                        continue;
+                    }
+                    ChatEntry::Clear(clear) => {
+                        writeln!(
+                            out,
+                            "* {} *{}*",
+                            times.format(meeting, clear.time, "%H:%M"),
+                            clear.text()
+                        )?;
+                        continue;
+                    }
+                };
+                if let Some(number) = segment_start(&mut segment, msg) {
+                    // the list is interrupted by the heading, the first segment follows the chat heading directly
+                    if number > 1 {
+                        writeln!(out)?;
+                    }
+                    writeln!(out, "### Segment {}\n", number)?;
+                }
+                // a hard line break keeps the continuation lines in the list item
+                writeln!(
+                    out,
+                    "* {} **{}**: {}",
+                    markdown_time(meeting, msg, times, recordings),
+                    msg.display_author(),
+                    msg.lines().join("  \n  ")
+                )?;
+            }
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Escape the characters that have a meaning in HTML.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The colors of the HTML transcript.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Theme {
+    Light,
+    Dark,
+    /// Light or dark as the system of the reader is set.
+    Auto,
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            "auto" => Ok(Theme::Auto),
+            _ => Err(format!(
+                "--html-theme: unknown theme {}, use dark, light or auto",
+                s
+            )),
+        }
+    }
+}
+
+/// How the HTML transcript looks, everything is embedded in the page.
+#[derive(Clone, Debug)]
+pub struct HtmlStyle {
+    pub theme: Theme,
+    /// A stylesheet added after the theme, e.g. with the colors and fonts of the institution.
+    pub css: Option<String>,
+}
+
+impl Default for HtmlStyle {
+    fn default() -> Self {
+        HtmlStyle {
+            theme: Theme::Light,
+            css: None,
+        }
+    }
+}
+
+const LIGHT: &str = "--background: #fff; --text: #222; --muted: #666; --border: #ccc; --link: #0645ad; --mark: #f3f3f3;";
+const DARK: &str = "--background: #1e1e1e; --text: #ddd; --muted: #999; --border: #444; --link: #8ab4f8; --mark: #2a2a2a;";
+
+/// The style of the HTML page in the colors of the theme, the rows hidden by the filter get the `hidden` attribute.
+const STYLE: &str =
+    "body { background: var(--background); color: var(--text); font-family: sans-serif; }
+a { color: var(--link); }
+table.chat { border-collapse: collapse; }
+table.chat td, table.chat th { padding: 0.15em 0.5em; vertical-align: top; }
+td.time { color: var(--muted); white-space: nowrap; }
+td.author { font-weight: bold; }
+tr.lock, tr.clear, tr.segment { background: var(--mark); color: var(--muted); }
+p.welcome { border-left: 3px solid var(--border); padding-left: 0.5em; }
+dl.header { display: grid; grid-template-columns: max-content auto; gap: 0.1em 1em; }
+dl.header dt { color: var(--muted); }
+dl.header dd { margin: 0; }
+form.filter { margin: 1em 0; padding: 0.5em; border: 1px solid var(--border); }
+form.filter fieldset { border: none; margin: 0; padding: 0.25em 0; }
+form.filter label { margin-right: 1em; white-space: nowrap; }
+[hidden] { display: none !important; }";
+
+/// The `<style>` elements of the head, a custom stylesheet comes last so it wins.
+fn write_style<W: Write>(out: &mut W, style: &HtmlStyle) -> io::Result<()> {
+    match style.theme {
+        Theme::Light => writeln!(out, "<style>\n:root {{ color-scheme: light; {} }}", LIGHT)?,
+        Theme::Dark => writeln!(out, "<style>\n:root {{ color-scheme: dark; {} }}", DARK)?,
+        Theme::Auto => writeln!(
+            out,
+            "<style>\n:root {{ color-scheme: light dark; {} }}\n@media (prefers-color-scheme: dark) {{ :root {{ {} }} }}",
+            LIGHT, DARK
+        )?,
+    }
+    writeln!(out, "{}\n</style>", STYLE)?;
+    if let Some(css) = &style.css {
+        writeln!(out, "<style>\n{}\n</style>", css.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Show the filter of every meeting and hide the messages that do not match its search text, authors and time range.
+///
+/// Without JavaScript the forms stay hidden and the page shows every message.
+const SCRIPT: &str = r#"for (const section of document.querySelectorAll("section.meeting")) {
+  const form = section.querySelector("form.filter");
+  if (!form) continue;
+  const rows = Array.from(section.querySelectorAll("tr[data-author]"));
+  const byTime = rows.slice().sort((a, b) => a.dataset.minute - b.dataset.minute);
+  const search = form.querySelector("input[type=search]");
+  const from = form.querySelector("input[name=from]");
+  const to = form.querySelector("input[name=to]");
+  const range = form.querySelector("output[name=range]");
+  const count = form.querySelector("output[name=count]");
+  const update = () => {
+    const text = search.value.trim().toLowerCase();
+    const authors = new Set(Array.from(form.querySelectorAll("input[type=checkbox]:checked"), box => box.value));
+    const start = Math.min(+from.value, +to.value);
+    const end = Math.max(+from.value, +to.value);
+    const inRange = byTime.filter(row => +row.dataset.minute >= start && +row.dataset.minute <= end);
+    range.value = inRange.length ? inRange[0].dataset.time + " - " + inRange[inRange.length - 1].dataset.time : "";
+    let shown = 0;
+    for (const row of rows) {
+      const minute = +row.dataset.minute;
+      const show = authors.has(row.dataset.author) && minute >= start && minute <= end
+        && (!text || row.querySelector("td.message").textContent.toLowerCase().includes(text));
+      row.hidden = !show;
+      if (show) shown++;
+    }
+    count.value = shown + " of " + rows.length + " messages";
+  };
+  form.addEventListener("input", update);
+  form.addEventListener("submit", event => event.preventDefault());
+  form.hidden = false;
+  update();
+}"#;
+
+/// The search field, one checkbox per author and the time range of a meeting, used by [`SCRIPT`].
+fn write_filter<W: Write>(out: &mut W, meeting: &Meeting) -> io::Result<()> {
+    let messages = || meeting.chats.values().flat_map(|chat| &chat.messages);
+    let minutes = messages().map(|msg| (msg.time - meeting.time).num_minutes());
+    let (first, last) = match (minutes.clone().min(), minutes.max()) {
+        (Some(first), Some(last)) => (first, last),
+        // nothing to filter
+        _ => return Ok(()),
+    };
+    let authors: BTreeSet<String> = messages().map(Message::display_author).collect();
+    writeln!(
+        out,
+        "<form class=\"filter\" hidden>\n<fieldset><label>Search <input type=\"search\"></label> <output name=\"count\"></output></fieldset>\n<fieldset>"
+    )?;
+    for author in authors {
+        writeln!(
+            out,
+            "<label><input type=\"checkbox\" value=\"{0}\" checked> {0}</label>",
+            escape_html(&author)
+        )?;
+    }
+    writeln!(
+        out,
+        "</fieldset>\n<fieldset><label>From <input type=\"range\" name=\"from\" min=\"{0}\" max=\"{1}\" value=\"{0}\"></label><label>To <input type=\"range\" name=\"to\" min=\"{0}\" max=\"{1}\" value=\"{1}\"></label> <output name=\"range\"></output></fieldset>\n</form>",
+        first, last
+    )
+}
+
+/// Write the section of one meeting, with the [filter](SCRIPT) of the HTML page or without for the EPUB. It is valid XHTML as well.
+pub fn write_meeting<W: Write>(
+    out: &mut W,
+    meeting: &Meeting,
+    times: TimeDisplay,
+    header: Option<&[HeaderField]>,
+    recordings: Option<&Recordings>,
+    filter: bool,
+) -> io::Result<()> {
+    if let Some(fields) = header {
+        writeln!(
+            out,
+            "<section class=\"meeting\">\n<h1>{} ({})</h1>",
+            escape_html(&meeting.meeting_id),
+            meeting.handle()
+        )?;
+        let values = header::values(meeting, fields, recordings);
+        if !values.is_empty() {
+            writeln!(out, "<dl class=\"header\">")?;
+            for (label, value) in values {
+                let value = if label == "Recording" {
+                    format!("<a href=\"{0}\">{0}</a>", escape_html(&value))
+                } else {
+                    escape_html(&value)
+                };
+                writeln!(out, "<dt>{}</dt><dd>{}</dd>", label, value)?;
+            }
+            writeln!(out, "</dl>")?;
+        }
+    } else {
+        let name = meeting
+            .name
+            .as_ref()
+            .map(|name| format!(" - {}", escape_html(name)))
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "<section class=\"meeting\">\n<h1>{} - {} ({}){}</h1>",
+            meeting.time.format("%d.%m.%Y %H:%M"),
+            escape_html(&meeting.meeting_id),
+            meeting.handle(),
+            name
+        )?;
+    }
+    for (label, lines) in meeting.welcome_messages() {
+        writeln!(
+            out,
+            "<p class=\"welcome\"><strong>{}:</strong> {}</p>",
+            label,
+            lines
+                .iter()
+                .map(|line| escape_html(line))
+                .collect::<Vec<_>>()
+                .join("<br/>")
+        )?;
+    }
+    if filter {
+        write_filter(out, meeting)?;
+    }
+    for chat in meeting.sorted_chats() {
+        writeln!(
+            out,
+            "<h2>{}</h2>\n<table class=\"chat\">",
+            escape_html(&chat.chat_id)
+        )?;
+        let mut segment = None;
+        for entry in meeting.entries(chat) {
+            let msg = match entry {
+                ChatEntry::Message(msg) => msg,
+                ChatEntry::Lock(time, locked) => {
+                    writeln!(
+                        out,
+                        "<tr class=\"lock\"><td class=\"time\">{}</td><td colspan=\"2\">{}</td></tr>",
+                        times.format(meeting, time, "%H:%M"),
+                        lock_text(locked)
+                    )?;
+                    continue;
+                }
+                ChatEntry::Clear(clear) => {
+                    writeln!(
+                        out,
+                        "<tr class=\"clear\"><td class=\"time\">{}</td><td colspan=\"2\">{}</td></tr>",
+                        times.format(meeting, clear.time, "%H:%M"),
+                        escape_html(&clear.text())
+                    )?;
+                    continue;
+                }
+            };
+            if let Some(number) = segment_start(&mut segment, msg) {
+                writeln!(
+                    out,
+                    "<tr class=\"segment\"><th colspan=\"3\">Segment {}</th></tr>",
+                    number
+                )?;
+            }
+            let shown = times.format(meeting, msg.time, "%H:%M");
+            let time = match recordings.and_then(|r| r.link(meeting, msg.time)) {
+                Some(link) => format!("<a href=\"{}\">{}</a>", escape_html(&link), shown),
+                None => shown.clone(),
+            };
+            let author = escape_html(&msg.display_author());
+            writeln!(
+                out,
+                "<tr data-author=\"{}\" data-minute=\"{}\" data-time=\"{}\"><td class=\"time\">{}</td><td class=\"author\">{}</td><td class=\"message\">{}</td></tr>",
+                author,
+                (msg.time - meeting.time).num_minutes(),
+                shown,
+                time,
+                author,
+                msg.lines()
+                    .iter()
+                    .map(|line| escape_html(line))
+                    .collect::<Vec<_>>()
+                    .join("<br/>")
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+    writeln!(out, "</section>")?;
+    Ok(())
+}
+
+/// Write the transcripts as one self contained HTML page.
+pub fn write_html<'a, W: Write>(
+    out: &mut W,
+    meetings: impl Iterator<Item = &'a Meeting>,
+    times: TimeDisplay,
+    header: Option<&[HeaderField]>,
+    recordings: Option<&Recordings>,
+    style: &HtmlStyle,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Chats</title>"
+    )?;
+    write_style(out, style)?;
+    writeln!(out, "</head>\n<body>")?;
+    for meeting in meetings {
+        write_meeting(out, meeting, times, header, recordings, true)?;
+    }
+    writeln!(out, "<script>\n{}\n</script>\n</body>\n</html>", SCRIPT)
+}
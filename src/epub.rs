@@ -0,0 +1,154 @@
+//! The EPUB export: the transcripts of a term as one book with a chapter per meeting, to read them on an e-reader.
+use std::io::{self, Write};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    checksum::hex,
+    document::{escape_html, write_meeting},
+    header::HeaderField,
+    model::{Meeting, TimeDisplay},
+    recording::Recordings,
+    split::{entry_options, package},
+};
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles>
+<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+</rootfiles>
+</container>
+"#;
+
+/// Only the layout of the tables, the fonts and colors are left to the reader.
+const STYLE: &str = "table.chat { border-collapse: collapse; }
+table.chat td, table.chat th { padding: 0.1em 0.4em; vertical-align: top; text-align: left; }
+td.time { white-space: nowrap; }
+td.author { font-weight: bold; }
+tr.lock, tr.clear, tr.segment { font-style: italic; }
+p.welcome { border-left: 2px solid; padding-left: 0.5em; }
+dl.header dt { font-weight: bold; }
+";
+
+/// The start of every XHTML document of the book.
+fn write_head<W: Write>(out: &mut W, title: &str, style: bool) -> io::Result<()> {
+    writeln!(
+        out,
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">\n<head>\n<meta charset=\"utf-8\"/>\n<title>{}</title>",
+        escape_html(title)
+    )?;
+    if style {
+        writeln!(
+            out,
+            "<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>"
+        )?;
+    }
+    writeln!(out, "</head>\n<body>")
+}
+
+/// The name of a meeting in the table of contents.
+fn chapter_title(meeting: &Meeting) -> String {
+    format!(
+        "{} - {}",
+        meeting.time.format("%d.%m.%Y %H:%M"),
+        meeting.name.clone().unwrap_or_else(|| meeting.handle())
+    )
+}
+
+/// The title of the book: the name of a single meeting or the days of the first and the last one.
+fn title(meetings: &[&Meeting]) -> String {
+    match meetings {
+        [] => "Chats".to_string(),
+        [meeting] => chapter_title(meeting),
+        [first, .., last] => format!(
+            "Chats {} - {}",
+            first.time.format("%d.%m.%Y"),
+            last.time.format("%d.%m.%Y")
+        ),
+    }
+}
+
+/// The package document with the metadata, the files and their order.
+fn write_package<W: Write>(out: &mut W, meetings: &[&Meeting]) -> io::Result<()> {
+    // the same meetings are the same book, so a reader replaces it instead of adding a copy
+    let mut ids = Sha256::new();
+    for meeting in meetings {
+        ids.update(meeting.meeting_id.as_bytes());
+        ids.update(b"\n");
+    }
+    let modified = meetings
+        .iter()
+        .flat_map(|m| m.chats.values().flat_map(|c| &c.messages))
+        .map(|msg| msg.time)
+        .chain(meetings.iter().map(|m| m.time))
+        .max();
+    writeln!(
+        out,
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"id\">\n<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n<dc:identifier id=\"id\">urn:chatter:{}</dc:identifier>\n<dc:title>{}</dc:title>\n<dc:language>und</dc:language>\n<meta property=\"dcterms:modified\">{}</meta>\n</metadata>\n<manifest>\n<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n<item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>",
+        &hex(&ids.finalize())[..32],
+        escape_html(&title(meetings)),
+        modified
+            .map(|time| time.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+            .unwrap_or_else(|| "1970-01-01T00:00:00Z".to_string())
+    )?;
+    for number in 1..=meetings.len() {
+        writeln!(
+            out,
+            "<item id=\"meeting-{0}\" href=\"meeting-{0}.xhtml\" media-type=\"application/xhtml+xml\"/>",
+            number
+        )?;
+    }
+    // the table of contents is the first page, so the book has one even without meetings
+    writeln!(out, "</manifest>\n<spine>\n<itemref idref=\"nav\"/>")?;
+    for number in 1..=meetings.len() {
+        writeln!(out, "<itemref idref=\"meeting-{}\"/>", number)?;
+    }
+    writeln!(out, "</spine>\n</package>")
+}
+
+/// The table of contents with a link to every meeting.
+fn write_nav<W: Write>(out: &mut W, meetings: &[&Meeting]) -> io::Result<()> {
+    let title = title(meetings);
+    write_head(out, &title, false)?;
+    writeln!(
+        out,
+        "<nav epub:type=\"toc\" id=\"toc\">\n<h1>{}</h1>\n<ol>",
+        escape_html(&title)
+    )?;
+    for (i, meeting) in meetings.iter().enumerate() {
+        writeln!(
+            out,
+            "<li><a href=\"meeting-{}.xhtml\">{}</a></li>",
+            i + 1,
+            escape_html(&chapter_title(meeting))
+        )?;
+    }
+    writeln!(out, "</ol>\n</nav>\n</body>\n</html>")
+}
+
+/// Write the transcripts as an EPUB 3 book with one chapter per meeting, in the order they are given.
+pub fn write<'a, W: Write>(
+    out: &mut W,
+    meetings: impl Iterator<Item = &'a Meeting>,
+    times: TimeDisplay,
+    header: Option<&[HeaderField]>,
+    recordings: Option<&Recordings>,
+) -> io::Result<()> {
+    let meetings: Vec<&Meeting> = meetings.collect();
+    let mut zip = package("application/epub+zip")?;
+    zip.start_file("META-INF/container.xml", entry_options())?;
+    zip.write_all(CONTAINER.as_bytes())?;
+    zip.start_file("OEBPS/content.opf", entry_options())?;
+    write_package(&mut zip, &meetings)?;
+    zip.start_file("OEBPS/nav.xhtml", entry_options())?;
+    write_nav(&mut zip, &meetings)?;
+    zip.start_file("OEBPS/style.css", entry_options())?;
+    zip.write_all(STYLE.as_bytes())?;
+    for (i, meeting) in meetings.iter().enumerate() {
+        zip.start_file(format!("OEBPS/meeting-{}.xhtml", i + 1), entry_options())?;
+        write_head(&mut zip, &chapter_title(meeting), true)?;
+        write_meeting(&mut zip, meeting, times, header, recordings, false)?;
+        writeln!(zip, "</body>\n</html>")?;
+    }
+    out.write_all(&zip.finish()?.into_inner())
+}
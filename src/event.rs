@@ -0,0 +1,600 @@
+//! The log lines as typed events, read lazily so embedders can do their own aggregation.
+use std::{
+    fmt,
+    io::{self, BufRead, Lines},
+    str::FromStr,
+};
+
+use chrono::{Duration, NaiveDateTime};
+use json::JsonValue;
+
+use crate::{correlate, fields::FieldMap};
+
+/// Something that happened in a meeting, read from one log line.
+#[derive(Debug)]
+pub enum Event {
+    MeetingCreated {
+        meeting_id: String,
+        time: NaiveDateTime,
+        /// The meeting id the room was created with, the same for every session of a room.
+        external_id: Option<String>,
+        /// The welcome message of the chat.
+        welcome: Option<String>,
+        /// The welcome message for the moderators.
+        moderator_message: Option<String>,
+        /// The `meta_` parameters of the create call without the prefix, e.g. `bbb-context-id`.
+        metadata: Vec<(String, String)>,
+    },
+    UserJoined {
+        meeting_id: String,
+        time: NaiveDateTime,
+        /// The internal user id.
+        user_id: String,
+        name: String,
+        /// The external user id, e.g. the Moodle user id.
+        ext_id: Option<String>,
+        /// `MODERATOR` or `VIEWER`.
+        role: Option<String>,
+        /// A guest, i.e. not authenticated by the system the meeting was joined through.
+        guest: bool,
+    },
+    UserLeft {
+        meeting_id: String,
+        time: NaiveDateTime,
+        user_id: String,
+    },
+    /// The recording was started (`true`) or stopped (`false`).
+    RecordingChanged {
+        meeting_id: String,
+        time: NaiveDateTime,
+        recording: Option<bool>,
+    },
+    /// The lock settings for the viewers changed, `public_chat_locked` is missing if the event does not tell.
+    LockSettingsChanged {
+        meeting_id: String,
+        time: NaiveDateTime,
+        public_chat_locked: Option<bool>,
+    },
+    PollStarted {
+        meeting_id: String,
+        time: NaiveDateTime,
+        /// The question or the id of the poll if the question is not logged.
+        question: String,
+    },
+    /// A moderator cleared the history of the public chat.
+    PublicChatCleared {
+        meeting_id: String,
+        time: NaiveDateTime,
+        /// The internal user id of the moderator.
+        user_id: String,
+    },
+    /// A chat message, every event that is not known otherwise is taken as one.
+    Message {
+        meeting_id: String,
+        chat_id: String,
+        time: NaiveDateTime,
+        author: String,
+        /// The internal user id of the author.
+        author_id: String,
+        /// The role of the author if the event tells it, `MODERATOR` or `VIEWER`.
+        role: Option<String>,
+        text: String,
+        /// The id BigBlueButton gave the message if the event has one.
+        id: Option<String>,
+    },
+    /// A line of the bbb-web or FreeSWITCH log.
+    External(correlate::Event),
+    /// A line that could not be read, without an error if there is no JSON in it at all.
+    Invalid { line: String, error: Option<String> },
+}
+
+impl Event {
+    /// The meeting and the time of the events of akka-apps.
+    pub fn meeting(&self) -> Option<(&str, NaiveDateTime)> {
+        match self {
+            Event::MeetingCreated {
+                meeting_id, time, ..
+            }
+            | Event::UserJoined {
+                meeting_id, time, ..
+            }
+            | Event::UserLeft {
+                meeting_id, time, ..
+            }
+            | Event::RecordingChanged {
+                meeting_id, time, ..
+            }
+            | Event::LockSettingsChanged {
+                meeting_id, time, ..
+            }
+            | Event::PollStarted {
+                meeting_id, time, ..
+            }
+            | Event::PublicChatCleared {
+                meeting_id, time, ..
+            }
+            | Event::Message {
+                meeting_id, time, ..
+            } => Some((meeting_id, *time)),
+            Event::External(_) | Event::Invalid { .. } => None,
+        }
+    }
+
+    /// The type of the event, `None` for the lines that could not be read.
+    pub fn event_type(&self) -> Option<EventType> {
+        Some(match self {
+            Event::MeetingCreated { .. } => EventType::Meetings,
+            Event::UserJoined { .. } | Event::UserLeft { .. } => EventType::Joins,
+            Event::RecordingChanged { .. } => EventType::Recording,
+            Event::LockSettingsChanged { .. } => EventType::Locks,
+            Event::PollStarted { .. } => EventType::Polls,
+            Event::Message { .. } | Event::PublicChatCleared { .. } => EventType::Chat,
+            Event::External(correlate::Event::Created { .. } | correlate::Event::Joined { .. }) => {
+                EventType::Web
+            }
+            Event::External(
+                correlate::Event::AudioJoined { .. } | correlate::Event::AudioLeft { .. },
+            ) => EventType::Audio,
+            Event::Invalid { .. } => return None,
+        })
+    }
+}
+
+/// What an event is about, to read only some of them with `--events` and `--exclude-events`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EventType {
+    /// The creation of the meetings.
+    Meetings,
+    /// The participants joining and leaving.
+    Joins,
+    Recording,
+    /// The lock settings.
+    Locks,
+    Polls,
+    Chat,
+    /// The lines of the bbb-web log.
+    Web,
+    /// The lines of the FreeSWITCH log.
+    Audio,
+}
+
+const EVENT_TYPES: [(&str, EventType); 8] = [
+    ("meetings", EventType::Meetings),
+    ("joins", EventType::Joins),
+    ("recording", EventType::Recording),
+    ("locks", EventType::Locks),
+    ("polls", EventType::Polls),
+    ("chat", EventType::Chat),
+    ("web", EventType::Web),
+    ("audio", EventType::Audio),
+];
+
+impl fmt::Display for EventType {
+    /// The name in `--events`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = EVENT_TYPES
+            .iter()
+            .find(|(_, t)| t == self)
+            .map(|(name, _)| *name)
+            .unwrap_or_default();
+        f.write_str(name)
+    }
+}
+
+/// The types of the events that are read, all of them by default.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventTypes(Vec<EventType>);
+
+impl Default for EventTypes {
+    fn default() -> Self {
+        EventTypes(EVENT_TYPES.iter().map(|(_, t)| *t).collect())
+    }
+}
+
+impl FromStr for EventTypes {
+    type Err = String;
+
+    /// Parse a comma separated list like `chat,polls,joins`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut types = Vec::new();
+        for item in s.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            let event_type = EVENT_TYPES
+                .iter()
+                .find(|(name, _)| *name == item)
+                .map(|(_, t)| *t)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = EVENT_TYPES.iter().map(|(name, _)| *name).collect();
+                    format!(
+                        "unknown event type {}, chatter reads only {}",
+                        item,
+                        names.join(", ")
+                    )
+                })?;
+            if !types.contains(&event_type) {
+                types.push(event_type);
+            }
+        }
+        Ok(EventTypes(types))
+    }
+}
+
+impl EventTypes {
+    pub fn contains(&self, event_type: EventType) -> bool {
+        self.0.contains(&event_type)
+    }
+
+    /// Stop reading the given types.
+    pub fn exclude(&mut self, other: &EventTypes) {
+        self.0.retain(|t| !other.contains(*t));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A text of the event, `None` if it is missing or empty.
+fn non_empty(value: &JsonValue) -> Option<String> {
+    value
+        .as_str()
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+/// How the body and the header of an event are read, given its meeting and time.
+type Read = fn(String, NaiveDateTime, &JsonValue, &JsonValue) -> Event;
+
+/// Who cleared the public chat, the same for the message of the client and the event akka-apps sends on.
+fn public_chat_cleared(
+    meeting_id: String,
+    time: NaiveDateTime,
+    _: &JsonValue,
+    header: &JsonValue,
+) -> Event {
+    Event::PublicChatCleared {
+        meeting_id,
+        time,
+        user_id: header["userId"].to_string(),
+    }
+}
+
+/// The events akka-apps writes into its log with their type and how they are read. The Redis channels carry many more (e.g. who is talking) that must not be taken as chat messages.
+const DISPATCH: [(&str, EventType, Read); 8] = [
+    (
+        "MeetingCreatedEvtMsg",
+        EventType::Meetings,
+        |meeting_id, time, body, _| Event::MeetingCreated {
+            meeting_id,
+            time,
+            external_id: non_empty(&body["props"]["meetingProp"]["extId"]),
+            welcome: non_empty(&body["props"]["welcomeProp"]["welcomeMsg"]),
+            moderator_message: non_empty(&body["props"]["welcomeProp"]["modOnlyMessage"]),
+            metadata: body["props"]["metadataProp"]["metadata"]
+                .entries()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        },
+    ),
+    (
+        "UserJoinedMeetingEvtMsg",
+        EventType::Joins,
+        |meeting_id, time, body, _| Event::UserJoined {
+            meeting_id,
+            time,
+            user_id: body["intId"].to_string(),
+            name: body["name"].to_string(),
+            ext_id: non_empty(&body["extId"]),
+            role: body["role"].as_str().map(str::to_string),
+            guest: body["guest"].as_bool().unwrap_or(false),
+        },
+    ),
+    (
+        "UserLeftMeetingEvtMsg",
+        EventType::Joins,
+        |meeting_id, time, body, _| Event::UserLeft {
+            meeting_id,
+            time,
+            user_id: body["intId"].to_string(),
+        },
+    ),
+    (
+        "RecordingStatusChangedEvtMsg",
+        EventType::Recording,
+        |meeting_id, time, body, _| Event::RecordingChanged {
+            meeting_id,
+            time,
+            recording: body["recording"].as_bool(),
+        },
+    ),
+    (
+        "LockSettingsInMeetingChangedEvtMsg",
+        EventType::Locks,
+        |meeting_id, time, body, _| Event::LockSettingsChanged {
+            meeting_id,
+            time,
+            public_chat_locked: body["disablePubChat"].as_bool(),
+        },
+    ),
+    (
+        "PollStartedEvtMsg",
+        EventType::Polls,
+        |meeting_id, time, body, _| Event::PollStarted {
+            meeting_id,
+            time,
+            question: body["question"]
+                .as_str()
+                .filter(|q| !q.is_empty())
+                .or_else(|| body["pollId"].as_str())
+                .unwrap_or_default()
+                .to_string(),
+        },
+    ),
+    (
+        "ClearPublicChatHistoryPubMsg",
+        EventType::Chat,
+        public_chat_cleared,
+    ),
+    (
+        "ClearPublicChatHistoryEvtMsg",
+        EventType::Chat,
+        public_chat_cleared,
+    ),
+];
+
+/// The chat message events of the BigBlueButton versions without a field map.
+const CHAT_MESSAGE: &str = "GroupChatMessageBroadcastEvtMsg";
+
+/// Whether the events of this name are read: the chat messages and the events of [`DISPATCH`].
+pub fn is_read(fields: &FieldMap, name: &str) -> bool {
+    name == CHAT_MESSAGE || DISPATCH.iter().any(|(n, ..)| *n == name) || fields.has_event(name)
+}
+
+/// Whether the JSON of an event from somewhere else than the log, e.g. Redis or Kafka, is read, see [`is_read`].
+pub fn is_read_payload(fields: &FieldMap, payload: &str) -> bool {
+    json::parse(payload)
+        .ok()
+        .and_then(|data| {
+            data["envelope"]["name"]
+                .as_str()
+                .map(|name| is_read(fields, name))
+        })
+        .unwrap_or(false)
+}
+
+/// Whether a selected id is not in the event, the missing JSON values are read as `null`.
+pub fn is_missing(id: &str) -> bool {
+    id.is_empty() || id == "null"
+}
+
+/// Parse one log line, `None` if it is a line of bbb-web or FreeSWITCH or an event that tells nothing about the meetings, e.g. who is talking or typing.
+pub fn parse(fields: &FieldMap, line: &str) -> Option<Event> {
+    let invalid = |error: Option<String>| {
+        Some(Event::Invalid {
+            line: line.to_string(),
+            error,
+        })
+    };
+    // skip the first part (date and processinformation and go to the first brace which is the starting of the json log)
+    let start_pos = match line.find('{') {
+        Some(start_pos) => start_pos,
+        None => return invalid(None),
+    };
+    let data = match json::parse(&line[start_pos..]) {
+        Ok(data) => data,
+        Err(e) => return invalid(Some(e.to_string())),
+    };
+    if correlate::is_external(&data) {
+        return correlate::parse(line, &data).map(Event::External);
+    }
+    let event = data["envelope"]["name"].as_str().unwrap_or_default();
+    if !is_read(fields, event) {
+        return None;
+    }
+    let fields = fields.fields(event);
+    // First get the time of the message. The timestamp is in milliseconds since epoch.
+    let timestamp = fields.timestamp.select(&data);
+    let secs = match timestamp
+        .as_i64()
+        .or_else(|| timestamp.as_str().and_then(|t| t.parse().ok()))
+    {
+        Some(secs) => secs,
+        None => return invalid(Some(format!("{} is not a number", timestamp))),
+    };
+    let time = NaiveDateTime::from_timestamp(0, 0) + Duration::milliseconds(secs);
+    let meeting_id = fields.meeting_id.select(&data).to_string();
+
+    let body = &data["core"]["body"];
+    // the join and leave events carry the presence and the external user id of a participant, the recording events tell which parts of the meeting are in the recording, the poll events when the polls were started and the clear events when the public chat was emptied. The others are chat messages.
+    if let Some((_, _, read)) = DISPATCH.iter().find(|(name, ..)| *name == event) {
+        return Some(read(meeting_id, time, body, &data["core"]["header"]));
+    }
+    Some(Event::Message {
+        meeting_id,
+        chat_id: fields.chat_id.select(&data).to_string(),
+        time,
+        author: fields.author.select(&data).to_string(),
+        author_id: fields.author_id.select(&data).to_string(),
+        role: fields
+            .role
+            .select(&data)
+            .as_str()
+            .filter(|r| !r.is_empty())
+            .map(str::to_string),
+        text: fields.text.select(&data).to_string(),
+        id: Some(fields.id.select(&data))
+            .filter(|id| !id.is_null())
+            .map(JsonValue::to_string),
+    })
+}
+
+/// The events of a log, read line by line as they are needed.
+///
+/// ```ignore
+/// let stream = EventStream::new(BufReader::new(File::open("bbb-apps-akka.log")?));
+/// let messages = stream.filter(|e| matches!(e, Event::Message { .. })).count();
+/// ```
+pub struct EventStream<R> {
+    lines: Lines<R>,
+    fields: FieldMap,
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> EventStream<R> {
+    /// Read the events of the default BigBlueButton log format.
+    pub fn new(reader: R) -> Self {
+        Self::with_fields(reader, FieldMap::default())
+    }
+
+    /// Read the events of another BigBlueButton version, see [`FieldMap`].
+    pub fn with_fields(reader: R, fields: FieldMap) -> Self {
+        EventStream {
+            lines: reader.lines(),
+            fields,
+            error: None,
+        }
+    }
+
+    /// The error that ended the stream early, if reading failed.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+impl<R: BufRead> Iterator for EventStream<R> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        loop {
+            match self.lines.next()? {
+                Ok(line) => {
+                    if let Some(event) = parse(&self.fields, &line) {
+                        return Some(event);
+                    }
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// The events of a log read from an async reader, e.g. a TCP syslog connection.
+///
+/// ```ignore
+/// let mut events = parse_async(BufReader::new(socket));
+/// while let Some(event) = events.next().await? {
+///     meetings.event(event);
+/// }
+/// ```
+#[cfg(feature = "tokio")]
+pub struct AsyncEventStream<R> {
+    lines: tokio::io::Lines<R>,
+    fields: FieldMap,
+}
+
+/// Read the events of the default BigBlueButton log format from an async reader.
+#[cfg(feature = "tokio")]
+pub fn parse_async<R: tokio::io::AsyncBufRead + Unpin>(reader: R) -> AsyncEventStream<R> {
+    AsyncEventStream::with_fields(reader, FieldMap::default())
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncBufRead + Unpin> AsyncEventStream<R> {
+    /// Read the events of another BigBlueButton version, see [`FieldMap`].
+    pub fn with_fields(reader: R, fields: FieldMap) -> Self {
+        use tokio::io::AsyncBufReadExt;
+        AsyncEventStream {
+            lines: reader.lines(),
+            fields,
+        }
+    }
+
+    /// The next event, `None` at the end of the input.
+    pub async fn next(&mut self) -> io::Result<Option<Event>> {
+        while let Some(line) = self.lines.next_line().await? {
+            if let Some(event) = parse(&self.fields, &line) {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(name: &str, body: &str) -> String {
+        format!(
+            r#"2021-03-01T10:00:00.000Z INFO  o.b.c.a.RedisRecorderActor - {{"envelope": {{"name": "{}", "routing": {{"meetingId": "m"}}, "timestamp": 1614592800000}}, "core": {{"body": {}}}}}"#,
+            name, body
+        )
+    }
+
+    #[test]
+    fn chat_messages_are_read() {
+        let line = line(
+            CHAT_MESSAGE,
+            r#"{"chatId": "MAIN-PUBLIC-GROUP-CHAT", "msg": {"sender": {"id": "w_1", "name": "Eve"}, "message": "hi"}}"#,
+        );
+        match parse(&FieldMap::default(), &line) {
+            Some(Event::Message { author, text, .. }) => {
+                assert_eq!((author, text), ("Eve".to_string(), "hi".to_string()))
+            }
+            other => panic!("not a message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn other_events_are_skipped() {
+        for name in [
+            "UserTypingPubMsg",
+            "UserTalkingVoiceEvtMsg",
+            "PresentationPageChangedEvtMsg",
+        ] {
+            let line = line(
+                name,
+                r#"{"chatId": "MAIN-PUBLIC-GROUP-CHAT", "userId": "w_1"}"#,
+            );
+            assert!(parse(&FieldMap::default(), &line).is_none(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn payloads_of_other_events_are_not_read() {
+        let fields = FieldMap::default();
+        assert!(is_read_payload(
+            &fields,
+            r#"{"envelope": {"name": "GroupChatMessageBroadcastEvtMsg"}}"#
+        ));
+        assert!(is_read_payload(
+            &fields,
+            r#"{"envelope": {"name": "UserJoinedMeetingEvtMsg"}}"#
+        ));
+        assert!(!is_read_payload(
+            &fields,
+            r#"{"envelope": {"name": "UserTalkingVoiceEvtMsg"}}"#
+        ));
+        assert!(!is_read_payload(&fields, "not json"));
+    }
+
+    #[test]
+    fn the_events_of_the_field_map_are_chat_messages() {
+        let path = std::env::temp_dir().join(format!("chatter-fields-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[events.SendPublicMessageEvtMsg]\ntext = \"core.body.message.message\"\n",
+        )
+        .unwrap();
+        let fields = FieldMap::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let line = line(
+            "SendPublicMessageEvtMsg",
+            r#"{"chatId": "MAIN-PUBLIC-GROUP-CHAT", "message": {"message": "hi"}}"#,
+        );
+        assert!(matches!(parse(&fields, &line), Some(Event::Message { text, .. }) if text == "hi"));
+        assert!(parse(&FieldMap::default(), &line).is_none());
+    }
+}
@@ -0,0 +1,235 @@
+//! How one log line is read, step by step, to find out why the messages of a server do not show up.
+use std::io::{self, Write};
+
+use crate::{
+    cli::Options,
+    correlate,
+    event::{self, Event},
+    fields::FieldMap,
+    model::Message,
+    tenant::Tenants,
+};
+
+/// Print every step of reading the line. Returns whether a chat message of the line is kept.
+pub fn run<W: Write>(
+    out: &mut W,
+    options: &Options,
+    fields: &FieldMap,
+    tenants: &Tenants,
+    line: &str,
+) -> io::Result<bool> {
+    let start = match line.find('{') {
+        Some(start) => start,
+        None => {
+            writeln!(out, "prefix:     {}", line.trim())?;
+            writeln!(
+                out,
+                "skipped:    there is no JSON in the line, it has to start with a {{"
+            )?;
+            return Ok(false);
+        }
+    };
+    let prefix = &line[..start];
+    writeln!(out, "prefix:     {}", prefix.trim())?;
+    let tenant = tenants.find(prefix);
+    match (&options.tenant_map, &tenant) {
+        (None, _) => {}
+        (Some(_), Some(tenant)) => writeln!(out, "tenant:     {}", tenant)?,
+        (Some(_), None) => writeln!(out, "tenant:     no host of the --tenant-map in the prefix")?,
+    }
+    let data = match json::parse(&line[start..]) {
+        Ok(data) => data,
+        Err(e) => {
+            writeln!(out, "skipped:    the JSON can not be parsed: {}", e)?;
+            return Ok(false);
+        }
+    };
+    if correlate::is_external(&data) {
+        let log = if data.has_key("logCode") {
+            "bbb-web"
+        } else {
+            "FreeSWITCH"
+        };
+        writeln!(out, "log:        {}", log)?;
+        return match correlate::parse(line, &data) {
+            Some(event) => {
+                writeln!(out, "event:      {:?}", event)?;
+                let event_type = Event::External(event).event_type();
+                if let Some(event_type) = event_type.filter(|t| !options.events.contains(*t)) {
+                    writeln!(
+                        out,
+                        "skipped:    --events or --exclude-events, the event is of the type {}",
+                        event_type
+                    )?;
+                    return Ok(false);
+                }
+                writeln!(
+                    out,
+                    "used:       to correlate the meetings with the API joins and the audio conference, there is no chat message in it"
+                )?;
+                Ok(false)
+            }
+            None => {
+                writeln!(
+                    out,
+                    "skipped:    not an event the correlation needs (created, joins and the audio conference)"
+                )?;
+                Ok(false)
+            }
+        };
+    }
+
+    let name = data["envelope"]["name"].as_str().unwrap_or_default();
+    let selectors = fields.fields(name);
+    writeln!(
+        out,
+        "envelope:   {}",
+        if name.is_empty() { "(no name)" } else { name }
+    )?;
+    writeln!(
+        out,
+        "fields:     {}",
+        if fields.has_event(name) {
+            "the table of this event in the --field-map"
+        } else {
+            "the default ones"
+        }
+    )?;
+    writeln!(
+        out,
+        "timestamp:  {} = {}",
+        selectors.timestamp,
+        selectors.timestamp.select(&data)
+    )?;
+    writeln!(
+        out,
+        "meeting:    {} = {}",
+        selectors.meeting_id,
+        selectors.meeting_id.select(&data)
+    )?;
+
+    let event = match event::parse(fields, line) {
+        Some(event) => event,
+        None => {
+            writeln!(out, "skipped:    the line is no event")?;
+            return Ok(false);
+        }
+    };
+    if let Some(event_type) = event.event_type() {
+        if !options.events.contains(event_type) {
+            writeln!(
+                out,
+                "skipped:    --events or --exclude-events, the event is of the type {}",
+                event_type
+            )?;
+            return Ok(false);
+        }
+    }
+    let (meeting_id, chat_id, msg) = match event {
+        Event::Invalid { error, .. } => {
+            writeln!(out, "skipped:    {}", error.unwrap_or_default())?;
+            return Ok(false);
+        }
+        Event::Message {
+            meeting_id,
+            chat_id,
+            time,
+            author,
+            author_id,
+            role,
+            text,
+            id,
+        } => {
+            writeln!(out, "time:       {}", time.format("%Y-%m-%d %H:%M:%S%.3f"))?;
+            writeln!(
+                out,
+                "kind:       chat message, every event that is not known otherwise"
+            )?;
+            writeln!(out, "chat:       {} = {}", selectors.chat_id, chat_id)?;
+            writeln!(out, "author:     {} = {}", selectors.author, author)?;
+            writeln!(out, "author id:  {} = {}", selectors.author_id, author_id)?;
+            writeln!(
+                out,
+                "role:       {} = {}",
+                selectors.role,
+                role.as_deref()
+                    .unwrap_or("(from the join event of the author)")
+            )?;
+            writeln!(out, "text:       {} = {}", selectors.text, text)?;
+            let id = match id {
+                Some(id) => {
+                    writeln!(out, "id:         {} = {}", selectors.id, id)?;
+                    id
+                }
+                None => {
+                    let id = Message::derive_id(&meeting_id, &chat_id, &author_id, time, &text);
+                    writeln!(
+                        out,
+                        "id:         {} (hash, {} is not there)",
+                        id, selectors.id
+                    )?;
+                    id
+                }
+            };
+            let msg = Message {
+                id,
+                author,
+                author_id,
+                ext_id: None,
+                role,
+                guest: false,
+                message: text,
+                time,
+                repeated: None,
+                language: None,
+                score: None,
+                raw: None,
+                withheld: None,
+                mentions: Vec::new(),
+                segment: None,
+                oversized: None,
+            };
+            (meeting_id, chat_id, msg)
+        }
+        event => {
+            writeln!(out, "kind:       {:?}", event)?;
+            writeln!(
+                out,
+                "used:       for the meeting and its participants, there is no chat message in it"
+            )?;
+            return Ok(false);
+        }
+    };
+
+    if event::is_missing(&meeting_id) || event::is_missing(&chat_id) {
+        writeln!(
+            out,
+            "skipped:    there is no meeting or chat id, e.g. a --field-map is needed for this version, --unattributed lists these messages"
+        )?;
+        return Ok(false);
+    }
+    if !options.tenants.is_empty() && !tenant.as_ref().is_some_and(|t| options.tenants.contains(t))
+    {
+        writeln!(
+            out,
+            "skipped:    --tenant, unless the path of the log file names one of its servers"
+        )?;
+        return Ok(false);
+    }
+    if let Some(option) = options.dropped_by(&meeting_id, &chat_id, &msg) {
+        writeln!(out, "skipped:    {}", option)?;
+        return Ok(false);
+    }
+    writeln!(out, "kept:       the message passes the filters")?;
+    if options.since.is_some() || options.until.is_some() {
+        writeln!(
+            out,
+            "note:       --since and --until need the start of the meeting, which is not in this line"
+        )?;
+    }
+    writeln!(
+        out,
+        "note:       the external user id and whether the author is a guest come from their join event and count as unknown here, the same text right after is dropped as a duplicate"
+    )?;
+    Ok(true)
+}
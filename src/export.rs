@@ -0,0 +1,313 @@
+//! The machine readable output formats.
+use std::io::{self, Write};
+
+use chrono::NaiveDateTime;
+use json::{object, JsonValue};
+
+use crate::{
+    collation::Collation,
+    collect::Unattributed,
+    language,
+    model::{Chat, Meeting, Message},
+    schema::SCHEMA_VERSION,
+};
+
+/// The timestamps in the machine readable formats are always printed with full precision.
+pub fn timestamp(time: &NaiveDateTime) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S%.3f").to_string()
+}
+
+fn message_json(msg: &Message) -> JsonValue {
+    let mut json = object! {
+        id: msg.id.clone(),
+        time: timestamp(&msg.time),
+        author: msg.author.clone(),
+        author_id: msg.author_id.clone(),
+        ext_user_id: msg.ext_id.clone(),
+        role: msg.role.as_deref().map(str::to_lowercase),
+        guest: msg.guest,
+        message: msg.message.clone(),
+        words: msg.words(),
+        characters: msg.characters(),
+    };
+    if let Some(score) = msg.score {
+        json["score"] = score.0.into();
+    }
+    if let Some(language) = msg.language {
+        json["language"] = language.into();
+    }
+    if let Some(raw) = &msg.raw {
+        json["raw"] = raw.clone().into();
+    }
+    if msg.withheld.is_some() {
+        json["withheld"] = true.into();
+    }
+    if let Some(segment) = msg.segment {
+        json["segment"] = segment.into();
+    }
+    if let Some(bytes) = msg.oversized {
+        json["oversized_bytes"] = bytes.into();
+    }
+    if !msg.mentions.is_empty() {
+        json["mentions"] = msg
+            .mentions
+            .iter()
+            .map(|m| {
+                object! {
+                    user_id: m.user_id.clone(),
+                    name: m.name.clone(),
+                    ext_user_id: m.ext_id.clone(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .into();
+    }
+    json
+}
+
+fn chat_json(chat: &Chat) -> JsonValue {
+    object! {
+        chat_id: chat.chat_id.clone(),
+        messages: chat.messages.iter().map(message_json).collect::<Vec<_>>(),
+    }
+}
+
+fn meeting_json(meeting: &Meeting) -> JsonValue {
+    let mut json = object! {
+        schema_version: SCHEMA_VERSION,
+        meeting_id: meeting.meeting_id.clone(),
+        handle: meeting.handle(),
+        time: timestamp(&meeting.time),
+        chats: meeting.sorted_chats().into_iter().map(chat_json).collect::<Vec<_>>(),
+    };
+    // only known with the BigBlueButton API
+    if let Some(name) = &meeting.name {
+        json["name"] = name.clone().into();
+    }
+    if !meeting.metadata.is_empty() {
+        let mut metadata = JsonValue::new_object();
+        for (key, value) in &meeting.metadata {
+            metadata[key.as_str()] = value.clone().into();
+        }
+        json["metadata"] = metadata;
+    }
+    if let Some(url) = &meeting.playback_url {
+        json["playback_url"] = url.clone().into();
+    }
+    if let Some(tenant) = &meeting.tenant {
+        json["tenant"] = tenant.clone().into();
+    }
+    if let Some(welcome) = &meeting.welcome {
+        json["welcome"] = welcome.clone().into();
+    }
+    if let Some(message) = &meeting.moderator_message {
+        json["moderator_message"] = message.clone().into();
+    }
+    let languages = language::shares(meeting);
+    if !languages.is_empty() {
+        let mut shares = JsonValue::new_object();
+        for (language, share) in languages {
+            shares[language] = share.into();
+        }
+        json["languages"] = shares;
+    }
+    if !meeting.public_chat_locks.is_empty() {
+        json["public_chat_locks"] = meeting
+            .public_chat_locks
+            .iter()
+            .map(|(time, locked)| object! { time: timestamp(time), locked: *locked })
+            .collect::<Vec<_>>()
+            .into();
+    }
+    if !meeting.public_chat_clears.is_empty() {
+        json["public_chat_clears"] = meeting
+            .public_chat_clears
+            .iter()
+            .map(|clear| {
+                object! {
+                    time: timestamp(&clear.time),
+                    user_id: clear.user_id.as_str(),
+                    name: clear.name.as_str(),
+                    ext_user_id: clear.ext_id.as_deref(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .into();
+    }
+    json
+}
+
+/// Write all meetings as one pretty printed JSON array, with a [provenance](crate::provenance) in every meeting if there is one.
+pub fn write_json<'a, W: Write>(
+    out: &mut W,
+    meetings: impl Iterator<Item = &'a Meeting>,
+    provenance: Option<&JsonValue>,
+) -> io::Result<()> {
+    let doc = JsonValue::Array(
+        meetings
+            .map(|meeting| {
+                let mut doc = meeting_json(meeting);
+                if let Some(provenance) = provenance {
+                    doc["provenance"] = provenance.clone();
+                }
+                doc
+            })
+            .collect(),
+    );
+    doc.write_pretty(out, 2)?;
+    writeln!(out)
+}
+
+/// Write a single message as one line of JSON carrying the meeting and chat it belongs to.
+pub fn write_ndjson_line<W: Write>(
+    out: &mut W,
+    meeting: &Meeting,
+    chat: &Chat,
+    msg: &Message,
+) -> io::Result<()> {
+    let mut line = message_json(msg);
+    line["meeting_id"] = meeting.meeting_id.clone().into();
+    line["chat_id"] = chat.chat_id.clone().into();
+    line["schema_version"] = SCHEMA_VERSION.into();
+    line.write(out)?;
+    writeln!(out)
+}
+
+/// Write one JSON object per message and line. Every object carries the meeting and chat it belongs to.
+pub fn write_ndjson<'a, W: Write>(
+    out: &mut W,
+    meetings: impl Iterator<Item = &'a Meeting>,
+) -> io::Result<()> {
+    for meeting in meetings {
+        for chat in meeting.sorted_chats() {
+            for msg in &chat.messages {
+                write_ndjson_line(out, meeting, chat, msg)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Quote a CSV field if it contains a separator, a quote or a line break.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub const CSV_HEADER: &str =
+    "meeting_id,chat_id,time,author,author_id,ext_user_id,message,words,characters,id,role,guest";
+
+pub const UNATTRIBUTED_HEADER: &str =
+    "file,timestamp,time,meeting_id,chat_id,author,author_id,message";
+
+pub const PARTICIPANTS_HEADER: &str =
+    "meeting_id,name,ext_user_id,role,guest,joined,left,duration_seconds";
+
+/// Write an attendance sheet: one CSV row per participant and time they were in the meeting.
+///
+/// The leave time and the duration are empty if the leave event was not seen.
+pub fn write_participants<'a, W: Write>(
+    out: &mut W,
+    meetings: impl Iterator<Item = &'a Meeting>,
+    collation: &Collation,
+) -> io::Result<()> {
+    writeln!(out, "{}", PARTICIPANTS_HEADER)?;
+    for meeting in meetings {
+        let mut users = meeting.sorted_users();
+        // stable, the users with the same name stay in the order of their ids
+        users.sort_by(|a, b| collation.compare(&a.name, &b.name));
+        for user in users {
+            let role = user.role.as_deref().unwrap_or_default().to_lowercase();
+            for (joined, left) in &user.sessions {
+                let row = [
+                    meeting.meeting_id.as_str(),
+                    user.name.as_str(),
+                    user.ext_id.as_deref().unwrap_or_default(),
+                    &role,
+                    if user.guest { "true" } else { "false" },
+                    &timestamp(joined),
+                    &left.as_ref().map(timestamp).unwrap_or_default(),
+                    &left
+                        .map(|left| (left - *joined).num_seconds().to_string())
+                        .unwrap_or_default(),
+                ];
+                let row: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+                writeln!(out, "{}", row.join(","))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write a single message as one CSV row.
+pub fn write_csv_row<W: Write>(
+    out: &mut W,
+    meeting: &Meeting,
+    chat: &Chat,
+    msg: &Message,
+) -> io::Result<()> {
+    let row = [
+        meeting.meeting_id.as_str(),
+        chat.chat_id.as_str(),
+        &timestamp(&msg.time),
+        msg.author.as_str(),
+        msg.author_id.as_str(),
+        msg.ext_id.as_deref().unwrap_or_default(),
+        msg.message.as_str(),
+        &msg.words().to_string(),
+        &msg.characters().to_string(),
+        msg.id.as_str(),
+        &msg.role.as_deref().unwrap_or_default().to_lowercase(),
+        if msg.guest { "true" } else { "false" },
+    ];
+    let row: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+    writeln!(out, "{}", row.join(","))
+}
+
+/// Write one row per message with a header line.
+pub fn write_csv<'a, W: Write>(
+    out: &mut W,
+    meetings: impl Iterator<Item = &'a Meeting>,
+) -> io::Result<()> {
+    writeln!(out, "{}", CSV_HEADER)?;
+    write_csv_rows(out, meetings)
+}
+
+/// Write the rows of the messages without the header.
+pub fn write_csv_rows<'a, W: Write>(
+    out: &mut W,
+    meetings: impl Iterator<Item = &'a Meeting>,
+) -> io::Result<()> {
+    for meeting in meetings {
+        for chat in meeting.sorted_chats() {
+            for msg in &chat.messages {
+                write_csv_row(out, meeting, chat, msg)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write the chat messages without a meeting or chat id as CSV, with the file they are in and their timestamp in milliseconds as it is logged.
+pub fn write_unattributed<W: Write>(out: &mut W, messages: &[Unattributed]) -> io::Result<()> {
+    writeln!(out, "{}", UNATTRIBUTED_HEADER)?;
+    for unattributed in messages {
+        let msg = &unattributed.message;
+        let row = [
+            &unattributed.source.display().to_string(),
+            &msg.time.timestamp_millis().to_string(),
+            &timestamp(&msg.time),
+            unattributed.meeting_id.as_deref().unwrap_or_default(),
+            unattributed.chat_id.as_deref().unwrap_or_default(),
+            msg.author.as_str(),
+            msg.author_id.as_str(),
+            msg.message.as_str(),
+        ];
+        let row: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
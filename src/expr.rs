@@ -0,0 +1,456 @@
+//! A small expression language to filter the messages.
+//!
+//! ```text
+//! author != "SYSTEM" and text | contains("http")
+//! not (chat_id == "MAIN-PUBLIC-GROUP-CHAT") or text | length > 200
+//! ```
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::{export::timestamp, model::Message};
+
+/// The value of a (part of an) expression.
+#[derive(Clone, PartialEq, Debug)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl Value {
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0,
+            Value::Text(t) => !t.is_empty(),
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Value::Null => String::new(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::Text(t) => t.clone(),
+        }
+    }
+}
+
+/// The message fields an expression can use.
+#[derive(Clone, Copy, Debug)]
+enum Field {
+    Author,
+    AuthorId,
+    ExtUserId,
+    Text,
+    Time,
+    ChatId,
+    MeetingId,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// The functions that can be applied with `|`.
+#[derive(Clone, Debug)]
+enum Function {
+    Contains(String),
+    StartsWith(String),
+    EndsWith(String),
+    Matches(Regex),
+    Lower,
+    Length,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Literal(Value),
+    Field(Field),
+    Not(Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Compare(Box<Node>, Comparison, Box<Node>),
+    Pipe(Box<Node>, Function),
+}
+
+/// A parsed filter expression.
+#[derive(Clone, Debug)]
+pub struct Expr(Node);
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Ident(String),
+    Text(String),
+    Number(f64),
+    Compare(Comparison),
+    Pipe,
+    Open,
+    Close,
+}
+
+fn tokens(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '|' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Pipe,
+                });
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => text.push('\n'),
+                            Some('t') => text.push('\t'),
+                            Some(c) => text.push(c),
+                            None => return Err("unterminated string".to_string()),
+                        },
+                        Some(c) => text.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let equals = chars.next_if_eq(&'=').is_some();
+                tokens.push(Token::Compare(match (c, equals) {
+                    ('=', true) => Comparison::Eq,
+                    ('!', true) => Comparison::Ne,
+                    ('<', false) => Comparison::Lt,
+                    ('<', true) => Comparison::Le,
+                    ('>', false) => Comparison::Gt,
+                    ('>', true) => Comparison::Ge,
+                    _ => return Err(format!("unexpected {}", c)),
+                }));
+            }
+            c if c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                let number = number
+                    .parse()
+                    .map_err(|_| format!("bad number {}", number))?;
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            c => return Err(format!("unexpected {}", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            Some(t) => Err(format!("expected {:?} but found {:?}", token, t)),
+            None => Err(format!("expected {:?} at the end", token)),
+        }
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(ident)) if ident == word) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut node = self.and()?;
+        while self.keyword("or") {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut node = self.not()?;
+        while self.keyword("and") {
+            node = Node::And(Box::new(node), Box::new(self.not()?));
+        }
+        Ok(node)
+    }
+
+    fn not(&mut self) -> Result<Node, String> {
+        if self.keyword("not") {
+            return Ok(Node::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Node, String> {
+        let left = self.pipe()?;
+        if let Some(&Token::Compare(comparison)) = self.peek() {
+            self.pos += 1;
+            let right = self.pipe()?;
+            return Ok(Node::Compare(Box::new(left), comparison, Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn pipe(&mut self) -> Result<Node, String> {
+        let mut node = self.primary()?;
+        while self.peek() == Some(&Token::Pipe) {
+            self.pos += 1;
+            node = Node::Pipe(Box::new(node), self.function()?);
+        }
+        Ok(node)
+    }
+
+    fn function(&mut self) -> Result<Function, String> {
+        let name = match self.next() {
+            Some(Token::Ident(name)) => name,
+            other => return Err(format!("expected a function but found {:?}", other)),
+        };
+        let mut argument = || -> Result<String, String> {
+            self.expect(Token::Open)?;
+            let argument = match self.next() {
+                Some(Token::Text(text)) => text,
+                other => return Err(format!("{} needs a string but found {:?}", name, other)),
+            };
+            self.expect(Token::Close)?;
+            Ok(argument)
+        };
+        match name.as_str() {
+            "contains" => Ok(Function::Contains(argument()?)),
+            "startswith" => Ok(Function::StartsWith(argument()?)),
+            "endswith" => Ok(Function::EndsWith(argument()?)),
+            "matches" => {
+                let pattern = argument()?;
+                Regex::new(&pattern)
+                    .map(Function::Matches)
+                    .map_err(|e| e.to_string())
+            }
+            "lower" => Ok(Function::Lower),
+            "length" => Ok(Function::Length),
+            _ => Err(format!("unknown function {}", name)),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some(Token::Open) => {
+                let node = self.or()?;
+                self.expect(Token::Close)?;
+                Ok(node)
+            }
+            Some(Token::Text(text)) => Ok(Node::Literal(Value::Text(text))),
+            Some(Token::Number(number)) => Ok(Node::Literal(Value::Number(number))),
+            Some(Token::Ident(ident)) => Ok(match ident.as_str() {
+                "true" => Node::Literal(Value::Bool(true)),
+                "false" => Node::Literal(Value::Bool(false)),
+                "null" => Node::Literal(Value::Null),
+                "author" => Node::Field(Field::Author),
+                "author_id" => Node::Field(Field::AuthorId),
+                "ext_user_id" => Node::Field(Field::ExtUserId),
+                "text" | "message" => Node::Field(Field::Text),
+                "time" => Node::Field(Field::Time),
+                "chat_id" => Node::Field(Field::ChatId),
+                "meeting_id" => Node::Field(Field::MeetingId),
+                _ => return Err(format!("unknown field {}", ident)),
+            }),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of the expression".to_string()),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokens(s)?,
+            pos: 0,
+        };
+        let node = parser.or()?;
+        match parser.peek() {
+            None => Ok(Expr(node)),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+        }
+    }
+}
+
+/// What an expression is evaluated on.
+struct Context<'a> {
+    meeting_id: &'a str,
+    chat_id: &'a str,
+    msg: &'a Message,
+}
+
+fn compare(left: &Value, comparison: Comparison, right: &Value) -> bool {
+    let ordering = match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.partial_cmp(r),
+        (Value::Null, Value::Null) => Some(std::cmp::Ordering::Equal),
+        (Value::Null, _) | (_, Value::Null) => None,
+        (l, r) => Some(l.text().cmp(&r.text())),
+    };
+    match (comparison, ordering) {
+        (Comparison::Ne, None) => true,
+        (_, None) => false,
+        (Comparison::Eq, Some(o)) => o.is_eq(),
+        (Comparison::Ne, Some(o)) => o.is_ne(),
+        (Comparison::Lt, Some(o)) => o.is_lt(),
+        (Comparison::Le, Some(o)) => o.is_le(),
+        (Comparison::Gt, Some(o)) => o.is_gt(),
+        (Comparison::Ge, Some(o)) => o.is_ge(),
+    }
+}
+
+impl Node {
+    fn eval(&self, context: &Context) -> Value {
+        match self {
+            Node::Literal(value) => value.clone(),
+            Node::Field(field) => {
+                let msg = context.msg;
+                match field {
+                    Field::Author => Value::Text(msg.author.clone()),
+                    Field::AuthorId => Value::Text(msg.author_id.clone()),
+                    Field::ExtUserId => msg.ext_id.clone().map_or(Value::Null, Value::Text),
+                    Field::Text => Value::Text(msg.message.clone()),
+                    Field::Time => Value::Text(timestamp(&msg.time)),
+                    Field::ChatId => Value::Text(context.chat_id.to_string()),
+                    Field::MeetingId => Value::Text(context.meeting_id.to_string()),
+                }
+            }
+            Node::Not(node) => Value::Bool(!node.eval(context).truthy()),
+            Node::And(left, right) => {
+                Value::Bool(left.eval(context).truthy() && right.eval(context).truthy())
+            }
+            Node::Or(left, right) => {
+                Value::Bool(left.eval(context).truthy() || right.eval(context).truthy())
+            }
+            Node::Compare(left, comparison, right) => Value::Bool(compare(
+                &left.eval(context),
+                *comparison,
+                &right.eval(context),
+            )),
+            Node::Pipe(node, function) => {
+                let text = node.eval(context).text();
+                match function {
+                    Function::Contains(s) => Value::Bool(text.contains(s.as_str())),
+                    Function::StartsWith(s) => Value::Bool(text.starts_with(s.as_str())),
+                    Function::EndsWith(s) => Value::Bool(text.ends_with(s.as_str())),
+                    Function::Matches(regex) => Value::Bool(regex.is_match(&text)),
+                    Function::Lower => Value::Text(text.to_lowercase()),
+                    Function::Length => Value::Number(text.chars().count() as f64),
+                }
+            }
+        }
+    }
+}
+
+impl Expr {
+    /// Check if a message of the given meeting and chat passes the filter.
+    pub fn matches(&self, meeting_id: &str, chat_id: &str, msg: &Message) -> bool {
+        let context = Context {
+            meeting_id,
+            chat_id,
+            msg,
+        };
+        self.0.eval(&context).truthy()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn message(author: &str, text: &str) -> Message {
+        let time = NaiveDate::from_ymd(2021, 3, 1).and_hms(10, 0, 0);
+        Message::example("w_1", author, text, time)
+    }
+
+    fn matches(expr: &str, msg: &Message) -> bool {
+        expr.parse::<Expr>()
+            .unwrap()
+            .matches("meeting", "MAIN-PUBLIC-GROUP-CHAT", msg)
+    }
+
+    #[test]
+    fn and_binds_stronger_than_or() {
+        let msg = message("Alice", "hello");
+        assert!(matches(r#"true or false and false"#, &msg));
+        assert!(!matches(r#"(true or false) and false"#, &msg));
+        assert!(matches(r#"not false and true"#, &msg));
+    }
+
+    #[test]
+    fn pipes_and_comparisons() {
+        let msg = message("Alice", "see https://example.org");
+        assert!(matches(
+            r#"author != "SYSTEM" and text | contains("http")"#,
+            &msg
+        ));
+        assert!(matches(r#"text | length > 10"#, &msg));
+        assert!(matches(r#"author | lower == "alice""#, &msg));
+        assert!(!matches(r#"ext_user_id == "moodle-1""#, &msg));
+        assert!(matches(r#"ext_user_id == null"#, &msg));
+    }
+
+    #[test]
+    fn escapes_in_strings() {
+        let msg = message("Alice", "say \"hi\"\nnow");
+        assert!(matches(r#"text | contains("\"hi\"\n")"#, &msg));
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        let error = |expr: &str| expr.parse::<Expr>().err().unwrap();
+        assert_eq!(error(r#"text | contains("http)"#), "unterminated string");
+        assert_eq!(error("name == 1"), "unknown field name");
+        assert_eq!(error("text | reverse"), "unknown function reverse");
+        assert_eq!(error("(true"), "expected Close at the end");
+        assert_eq!(error("true false"), r#"unexpected Ident("false")"#);
+        assert_eq!(error("author ="), "unexpected =");
+        assert_eq!(error("author =="), "unexpected end of the expression");
+    }
+}
@@ -0,0 +1,195 @@
+//! Where in the logged JSON the fields of a chat message are found, configurable for other BigBlueButton versions.
+use std::{collections::HashMap, fmt, fs, path::Path, str::FromStr};
+
+use json::JsonValue;
+
+/// One step of a selector.
+#[derive(Clone, Debug)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+/// A JSONPath like selector: keys separated by dots with optional array indices, e.g. `core.body.msgs[0].message`. A leading `$.` is allowed.
+#[derive(Clone, Debug)]
+pub struct Selector(Vec<Step>);
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = s.strip_prefix("$.").unwrap_or(s);
+        let mut steps = Vec::new();
+        for part in path.split('.') {
+            let (key, mut rest) = match part.find('[') {
+                Some(pos) => (&part[..pos], &part[pos..]),
+                None => (part, ""),
+            };
+            if key.is_empty() && rest.is_empty() {
+                return Err(format!("empty key in selector {}", s));
+            }
+            if !key.is_empty() {
+                steps.push(Step::Key(key.to_string()));
+            }
+            while let Some(index) = rest.strip_prefix('[') {
+                let (index, tail) = index
+                    .split_once(']')
+                    .ok_or_else(|| format!("missing ] in selector {}", s))?;
+                let index = index
+                    .parse()
+                    .map_err(|e| format!("bad index in selector {}: {}", s, e))?;
+                steps.push(Step::Index(index));
+                rest = tail;
+            }
+            if !rest.is_empty() {
+                return Err(format!("unexpected {} in selector {}", rest, s));
+            }
+        }
+        Ok(Selector(steps))
+    }
+}
+
+impl fmt::Display for Selector {
+    /// The selector as it is written in a field map.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self.0.iter().enumerate() {
+            match step {
+                Step::Key(key) if i == 0 => write!(f, "{}", key)?,
+                Step::Key(key) => write!(f, ".{}", key)?,
+                Step::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Selector {
+    /// The selected value of the data, `Null` if it is not there.
+    pub fn select<'a>(&self, data: &'a JsonValue) -> &'a JsonValue {
+        self.0.iter().fold(data, |value, step| match step {
+            Step::Key(key) => &value[key.as_str()],
+            Step::Index(index) => &value[*index],
+        })
+    }
+}
+
+/// The selectors of the fields of a chat message event.
+#[derive(Clone, Debug)]
+pub struct Fields {
+    pub meeting_id: Selector,
+    pub timestamp: Selector,
+    pub chat_id: Selector,
+    pub author: Selector,
+    pub author_id: Selector,
+    /// `MODERATOR` or `VIEWER`, not logged by every version.
+    pub role: Selector,
+    pub text: Selector,
+    /// The id of the message, not logged by every version.
+    pub id: Selector,
+}
+
+impl Default for Fields {
+    /// The fields of the `GroupChatMessageBroadcastEvtMsg` of BigBlueButton 2.2 and later.
+    fn default() -> Self {
+        let selector = |s: &str| s.parse().expect("the default selectors are valid");
+        Fields {
+            meeting_id: selector("envelope.routing.meetingId"),
+            timestamp: selector("envelope.timestamp"),
+            chat_id: selector("core.body.chatId"),
+            author: selector("core.body.msg.sender.name"),
+            author_id: selector("core.body.msg.sender.id"),
+            role: selector("core.body.msg.sender.role"),
+            text: selector("core.body.msg.message"),
+            id: selector("core.body.msg.id"),
+        }
+    }
+}
+
+impl Fields {
+    /// Replace the selectors that are given in a table of the field map.
+    fn update(&mut self, table: &toml::Table) -> Result<(), String> {
+        for (key, value) in table {
+            let selector = value
+                .as_str()
+                .ok_or_else(|| format!("{} has to be a string", key))?
+                .parse()?;
+            match key.as_str() {
+                "meeting_id" => self.meeting_id = selector,
+                "timestamp" => self.timestamp = selector,
+                "chat_id" => self.chat_id = selector,
+                "author" => self.author = selector,
+                "author_id" => self.author_id = selector,
+                "role" => self.role = selector,
+                "text" => self.text = selector,
+                "id" => self.id = selector,
+                _ => return Err(format!("unknown field {}", key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The selectors to use for every event name.
+#[derive(Clone, Default, Debug)]
+pub struct FieldMap {
+    default: Fields,
+    events: HashMap<String, Fields>,
+}
+
+impl FieldMap {
+    /// Read a TOML file like
+    ///
+    /// ```toml
+    /// [default]
+    /// meeting_id = "envelope.routing.meetingId"
+    ///
+    /// [events.SendPublicMessageEvtMsg]
+    /// author = "core.body.message.fromUsername"
+    /// text = "core.body.message.message"
+    /// ```
+    ///
+    /// Everything that is not given falls back to the `default` table and then to the BigBlueButton 2.2 fields.
+    pub fn load(path: &Path) -> Result<FieldMap, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let table: toml::Table = content
+            .parse()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut map = FieldMap::default();
+        for (key, value) in &table {
+            let value = value
+                .as_table()
+                .ok_or_else(|| format!("{}: {} has to be a table", path.display(), key))?;
+            match key.as_str() {
+                "default" => map
+                    .default
+                    .update(value)
+                    .map_err(|e| format!("{}: default: {}", path.display(), e))?,
+                "events" => {}
+                _ => return Err(format!("{}: unknown table {}", path.display(), key)),
+            }
+        }
+        if let Some(events) = table.get("events").and_then(|e| e.as_table()) {
+            for (name, value) in events {
+                let value = value.as_table().ok_or_else(|| {
+                    format!("{}: events.{} has to be a table", path.display(), name)
+                })?;
+                let mut fields = map.default.clone();
+                fields
+                    .update(value)
+                    .map_err(|e| format!("{}: events.{}: {}", path.display(), name, e))?;
+                map.events.insert(name.clone(), fields);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Whether the field map has selectors for this event, i.e. it is a chat message of another BigBlueButton version.
+    pub fn has_event(&self, event: &str) -> bool {
+        self.events.contains_key(event)
+    }
+
+    /// The selectors for an event.
+    pub fn fields(&self, event: &str) -> &Fields {
+        self.events.get(event).unwrap_or(&self.default)
+    }
+}
@@ -0,0 +1,481 @@
+//! Follow mode: keep reading the inputs and print every new message as soon as it is read.
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, Metadata},
+    io::{self, BufRead, BufReader, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use notify::{RecursiveMode, Watcher};
+
+use crate::{
+    cli::{Format, Options},
+    collect::Collector,
+    export,
+    fields::FieldMap,
+    input, mention,
+    model::{Chat, ContextLine, Meeting, Message},
+    shutdown::{Reload, Shutdown},
+    tenant::Tenants,
+    transform::Pipeline,
+};
+
+/// Even if the file system notifications are missed every file is checked after this time.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How many rotated files are remembered to continue reading them under their new name.
+const MAX_RETIRED: usize = 16;
+
+/// An identifier of the file behind a path, used to detect that a log was rotated.
+#[cfg(unix)]
+fn file_id(meta: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.ino()
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &Metadata) -> u64 {
+    0
+}
+
+/// A file that is read as it grows.
+struct Tail {
+    path: PathBuf,
+    reader: BufReader<File>,
+    id: u64,
+    /// The position up to which the file has been read.
+    pos: u64,
+    /// A line that has not been completely written yet.
+    partial: Vec<u8>,
+    /// The number of the lines read so far, for the warnings.
+    lines: usize,
+}
+
+impl Tail {
+    fn open(path: &Path) -> io::Result<Tail> {
+        let file = File::open(path)?;
+        let id = file_id(&file.metadata()?);
+        Ok(Tail {
+            path: path.to_path_buf(),
+            reader: BufReader::new(file),
+            id,
+            pos: 0,
+            partial: Vec::new(),
+            lines: 0,
+        })
+    }
+
+    /// Read all the complete lines that were appended since the last call.
+    fn read(&mut self, mut line: impl FnMut(&str)) -> io::Result<()> {
+        // the file was truncated (copytruncate rotation) so start from the beginning
+        if self.reader.get_ref().metadata()?.len() < self.pos {
+            self.reader.seek(SeekFrom::Start(0))?;
+            self.pos = 0;
+            self.partial.clear();
+            self.lines = 0;
+        }
+        loop {
+            let read = self.reader.read_until(b'\n', &mut self.partial)?;
+            if read == 0 {
+                return Ok(());
+            }
+            self.pos += read as u64;
+            if self.partial.ends_with(b"\n") {
+                self.lines += 1;
+                line(&input::decode(&self.partial, &self.path, self.lines));
+                self.partial.clear();
+            }
+        }
+    }
+}
+
+/// All the followed files keyed by their absolute path.
+struct Tails {
+    /// The files given on the command line.
+    files: HashSet<PathBuf>,
+    /// The directory given on the command line.
+    dir: Option<PathBuf>,
+    tails: HashMap<PathBuf, Tail>,
+    /// Files that were moved away recently.
+    retired: Vec<Tail>,
+}
+
+impl Tails {
+    /// Check if a path is one of the inputs - either given directly or because it is in the followed directory.
+    fn follows(&self, path: &Path) -> bool {
+        self.files.contains(path)
+            || self.dir.as_deref() == path.parent() && !path.is_dir() && !input::is_compressed(path)
+    }
+
+    /// The files that are currently there and followed.
+    fn existing(&self) -> io::Result<Vec<PathBuf>> {
+        let mut paths: Vec<PathBuf> = self.files.iter().cloned().collect();
+        paths.extend(self.tails.keys().cloned());
+        if let Some(dir) = &self.dir {
+            paths.extend(input::dir_files(dir)?);
+        }
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+
+    /// Read the rest of a file that is no longer at its path. It is kept for a while as it usually reappears under the rotated name.
+    fn retire(&mut self, mut tail: Tail, line: impl FnMut(&str)) -> io::Result<()> {
+        tail.read(line)?;
+        self.retired.push(tail);
+        if self.retired.len() > MAX_RETIRED {
+            self.retired.remove(0);
+        }
+        Ok(())
+    }
+
+    /// Read what is new in the file at `path`, handling rotated and removed files.
+    fn poll(&mut self, path: &Path, mut line: impl FnMut(&str)) -> io::Result<()> {
+        let meta = match fs::metadata(path) {
+            Ok(meta) if meta.is_file() => meta,
+            _ => {
+                // the file has been moved away - read what has been written before that.
+                if let Some(tail) = self.tails.remove(path) {
+                    self.retire(tail, line)?;
+                }
+                return Ok(());
+            }
+        };
+        let id = file_id(&meta);
+        if let Some(tail) = self.tails.get_mut(path) {
+            if tail.id == id {
+                return tail.read(line);
+            }
+            // a new file was created at the place of the old one, finish the old one first.
+            let tail = self.tails.remove(path).expect("the tail was just there");
+            self.retire(tail, &mut line)?;
+        }
+        // a renamed file continues where it was.
+        let moved = self
+            .tails
+            .iter()
+            .find(|(_, tail)| tail.id == id)
+            .map(|(old, _)| old.clone());
+        let mut tail = if let Some(old) = moved {
+            self.tails.remove(&old).expect("the tail was just there")
+        } else if let Some(i) = self.retired.iter().position(|tail| tail.id == id) {
+            self.retired.remove(i)
+        } else {
+            Tail::open(path)?
+        };
+        tail.read(line)?;
+        self.tails.insert(path.to_path_buf(), tail);
+        Ok(())
+    }
+}
+
+fn print<W: Write>(
+    out: &mut W,
+    options: &Options,
+    meeting: &Meeting,
+    chat: &Chat,
+    msg: &Message,
+) -> io::Result<()> {
+    match options.format {
+        Format::Ndjson => export::write_ndjson_line(out, meeting, chat, msg)?,
+        Format::Csv => export::write_csv_row(out, meeting, chat, msg)?,
+        _ => writeln!(
+            out,
+            "{}",
+            ContextLine(meeting, chat, msg, options.time_display)
+        )?,
+    }
+    out.flush()
+}
+
+/// The absolute version of a path - the file itself does not need to exist (any more).
+///
+/// The watcher reports absolute paths so everything is compared in that form.
+fn absolute(path: &Path) -> io::Result<PathBuf> {
+    let parent = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    match path.file_name() {
+        Some(name) => Ok(fs::canonicalize(parent)?.join(name)),
+        None => fs::canonicalize(path),
+    }
+}
+
+/// Read one line and print its message if it is a new one.
+fn emit<W: Write>(
+    out: &mut W,
+    options: &Options,
+    pipeline: &mut Pipeline,
+    collector: &mut Collector,
+    line: &str,
+) -> io::Result<()> {
+    if let Some((meeting, chat, msg)) = collector.line(line) {
+        if options.keeps_meeting(meeting)
+            && options.in_session(meeting.time, msg.time)
+            && options.keeps(&meeting.meeting_id, &chat.chat_id, msg)
+        {
+            // the collector keeps the message as it was read, the mentions are of the participants that joined so far
+            let mut msg = msg.clone();
+            msg.mentions = mention::find(&msg.message, &meeting.users);
+            if pipeline.message(&mut msg)? {
+                print(out, options, meeting, chat, &msg)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read what is new in a followed file and print its messages.
+fn read<W: Write>(
+    tails: &mut Tails,
+    path: &Path,
+    out: &mut W,
+    options: &Options,
+    pipeline: &mut Pipeline,
+    collector: &mut Collector,
+) -> io::Result<()> {
+    collector.begin(path);
+    let mut result = Ok(());
+    tails.poll(path, |line| {
+        if result.is_ok() {
+            result = emit(out, options, pipeline, collector, line);
+        }
+    })?;
+    result
+}
+
+/// Read the command line again with the files it names, `--ignore-author-file`, `--field-map` and `--tenant-map`.
+///
+/// The meetings and users read so far are kept. If one of the files can not be read everything stays as it was.
+fn reload_files(options: &mut Options, collector: &mut Collector) -> Result<(), String> {
+    let reloaded = Options::parse(std::env::args().skip(1))?;
+    let fields = match &reloaded.field_map {
+        Some(path) => FieldMap::load(path)?,
+        None => FieldMap::default(),
+    };
+    let tenants = match &reloaded.tenant_map {
+        Some(path) => Tenants::load(path).map_err(|e| format!("{}: {}", path.display(), e))?,
+        None => Tenants::default(),
+    };
+    collector.fields = fields;
+    collector.tenants = tenants;
+    // the tenant of the source that is read is looked up again
+    let source = collector.source.clone();
+    collector.begin(&source);
+    *options = reloaded;
+    Ok(())
+}
+
+/// Reload the files if a SIGHUP asked for it.
+fn check_reload(reload: &Reload, options: &mut Options, collector: &mut Collector) {
+    if reload.requested() {
+        match reload_files(options, collector) {
+            Ok(()) => eprintln!("reloaded"),
+            Err(e) => eprintln!("not reloaded: {}", e),
+        }
+    }
+}
+
+/// A live input that is not a file, e.g. a message broker the events are published to.
+pub trait Source {
+    /// A name for the path based tenant and the clock skew reports, e.g. the url of the broker.
+    fn name(&self) -> String;
+
+    /// Wait for the next events, each one like a log line. Empty if nothing arrived for a while.
+    fn poll(&mut self) -> io::Result<Vec<String>>;
+}
+
+/// Read the source until a signal stops it and print the new messages.
+pub fn run_source(
+    options: &Options,
+    collector: &mut Collector,
+    source: &mut dyn Source,
+) -> io::Result<()> {
+    let mut options = options.clone();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if options.format == Format::Csv {
+        writeln!(out, "{}", export::CSV_HEADER)?;
+    }
+    let mut pipeline = Pipeline::from_options(&options)?;
+    let shutdown = Shutdown::register()?;
+    let reload = Reload::register()?;
+    collector.begin(Path::new(&source.name()));
+    loop {
+        // a poll waits a few seconds at most, what it got is consumed already
+        if shutdown.requested() {
+            out.flush()?;
+            eprintln!("stopped");
+            return Ok(());
+        }
+        check_reload(&reload, &mut options, collector);
+        for line in source.poll()? {
+            emit(&mut out, &options, &mut pipeline, collector, &line)?;
+        }
+    }
+}
+
+/// Print the messages of named pipes (and the standard in) as they arrive on any of them, until all of them are closed or a signal stops it.
+fn run_pipes<W: Write>(
+    options: &mut Options,
+    collector: &mut Collector,
+    pipeline: &mut Pipeline,
+    out: &mut W,
+) -> io::Result<()> {
+    let shutdown = Shutdown::register()?;
+    let reload = Reload::register()?;
+    let (sender, lines) = mpsc::channel();
+    for path in options.inputs.clone() {
+        let sender = sender.clone();
+        thread::spawn(move || {
+            let reader: Box<dyn BufRead> = if path.as_path() == Path::new("-") {
+                Box::new(BufReader::new(io::stdin()))
+            } else {
+                // opening a pipe waits until the other end is opened for writing
+                match File::open(&path) {
+                    Ok(file) => Box::new(BufReader::new(file)),
+                    Err(e) => return sender.send((path, Err(e))),
+                }
+            };
+            for line in input::lines(reader, &path) {
+                sender.send((path.clone(), line))?;
+            }
+            Ok(())
+        });
+    }
+    drop(sender);
+    let mut source = PathBuf::new();
+    loop {
+        if shutdown.requested() {
+            out.flush()?;
+            eprintln!("stopped");
+            return Ok(());
+        }
+        check_reload(&reload, options, collector);
+        match lines.recv_timeout(POLL_INTERVAL) {
+            Ok((path, line)) => {
+                let line = line
+                    .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+                if path != source {
+                    collector.begin(&path);
+                    source = path;
+                }
+                emit(out, options, pipeline, collector, &line)?;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            // every pipe was closed by its writer
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+/// Read the inputs until a signal stops it and print the new messages.
+///
+/// The content already present at the start is read silently so that the users and the already seen messages are known. The standard in is read until it ends, the signals end the process as usual then.
+pub fn run(options: &Options, collector: &mut Collector) -> io::Result<()> {
+    let mut options = options.clone();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if options.format == Format::Csv {
+        writeln!(out, "{}", export::CSV_HEADER)?;
+    }
+    let mut pipeline = Pipeline::from_options(&options)?;
+
+    // the command line makes sure the pipes are not mixed with files
+    if options.inputs.iter().any(|path| input::is_pipe(path)) {
+        return run_pipes(&mut options, collector, &mut pipeline, &mut out);
+    }
+
+    let files: HashSet<PathBuf> = options
+        .inputs
+        .iter()
+        .filter(|path| path.as_path() != Path::new("-"))
+        .map(|path| absolute(path))
+        .collect::<io::Result<_>>()?;
+    if files.is_empty() && options.dir.is_none() {
+        for line in input::lines(io::stdin().lock(), Path::new("-")) {
+            emit(&mut out, &options, &mut pipeline, collector, &line?)?;
+        }
+        return Ok(());
+    }
+
+    let shutdown = Shutdown::register()?;
+    let reload = Reload::register()?;
+    let mut tails = Tails {
+        files,
+        dir: options.dir.as_deref().map(fs::canonicalize).transpose()?,
+        tails: HashMap::new(),
+        retired: Vec::new(),
+    };
+
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(io::Error::other)?;
+    // the parent directories are watched as rotation replaces the files.
+    let dirs: HashSet<PathBuf> = tails
+        .files
+        .iter()
+        .filter_map(|path| path.parent().map(Path::to_path_buf))
+        .chain(tails.dir.clone())
+        .collect();
+    for dir in &dirs {
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(io::Error::other)?;
+    }
+
+    // catch up with the content that is already there
+    for path in tails.existing()? {
+        collector.begin(&path);
+        tails.poll(&path, |line| {
+            collector.line(line);
+        })?;
+    }
+
+    loop {
+        if shutdown.requested() {
+            // the lines that were completely written before the signal are still printed
+            for path in tails.existing()? {
+                read(
+                    &mut tails,
+                    &path,
+                    &mut out,
+                    &options,
+                    &mut pipeline,
+                    collector,
+                )?;
+            }
+            out.flush()?;
+            eprintln!("stopped");
+            return Ok(());
+        }
+        check_reload(&reload, &mut options, collector);
+        let mut changed = match events.recv_timeout(POLL_INTERVAL) {
+            Ok(Ok(event)) => event.paths,
+            Ok(Err(e)) => {
+                eprintln!("watching the inputs failed: {}", e);
+                Vec::new()
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => tails.existing()?,
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        changed.sort();
+        changed.dedup();
+        for path in changed {
+            if !tails.follows(&path) {
+                continue;
+            }
+            read(
+                &mut tails,
+                &path,
+                &mut out,
+                &options,
+                &mut pipeline,
+                collector,
+            )?;
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Grades from the chat participation, written as the CSV the grade import of Moodle reads.
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+
+use crate::{export::csv_field, stats::Participation};
+
+/// How the grade is computed: the weighted sum of the numbers of a participant, at most `max`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GradeFormula {
+    pub meetings: f64,
+    pub messages: f64,
+    pub words: f64,
+    pub characters: f64,
+    /// The highest grade, e.g. the maximum grade of the grade item.
+    pub max: f64,
+}
+
+impl Default for GradeFormula {
+    /// One point per message up to 100.
+    fn default() -> Self {
+        GradeFormula {
+            meetings: 0.0,
+            messages: 1.0,
+            words: 0.0,
+            characters: 0.0,
+            max: 100.0,
+        }
+    }
+}
+
+impl FromStr for GradeFormula {
+    type Err = String;
+
+    /// Parse a comma separated list of weights like `meetings=5,words=0.1,max=10`, the ones not given are 0.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut formula = GradeFormula {
+            messages: 0.0,
+            ..GradeFormula::default()
+        };
+        for item in s.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            let (name, weight) = item
+                .split_once('=')
+                .ok_or_else(|| format!("{} needs a weight, e.g. {}=1", item, item))?;
+            let weight: f64 = weight.parse().map_err(|e| format!("{}: {}", item, e))?;
+            match name {
+                "meetings" => formula.meetings = weight,
+                "messages" => formula.messages = weight,
+                "words" => formula.words = weight,
+                "characters" => formula.characters = weight,
+                "max" => formula.max = weight,
+                _ => return Err(format!("unknown grade metric: {}", name)),
+            }
+        }
+        Ok(formula)
+    }
+}
+
+impl GradeFormula {
+    pub fn grade(&self, participation: &Participation) -> f64 {
+        let points = self.meetings * participation.meetings as f64
+            + self.messages * participation.messages as f64
+            + self.words * participation.words as f64
+            + self.characters * participation.characters as f64;
+        points.min(self.max)
+    }
+}
+
+/// Write the grades of the participants with an external user id as a CSV for the grade import of Moodle.
+///
+/// The first column is mapped to the user id of Moodle (the external user id the Moodle plugin of BigBlueButton passes on), the second to the grade item `item`. Participants without an external user id, e.g. guests, can not be imported and are left out.
+pub fn write_moodle<'a, W: Write>(
+    out: &mut W,
+    participants: impl Iterator<Item = Participation<'a>>,
+    formula: &GradeFormula,
+    item: &str,
+) -> io::Result<()> {
+    writeln!(out, "userid,{}", csv_field(item))?;
+    let mut skipped = 0;
+    for participant in participants {
+        let ext_id = match participant.ext_id {
+            Some(ext_id) => ext_id,
+            None => {
+                skipped += 1;
+                continue;
+            }
+        };
+        // Moodle reads at most two decimals
+        let grade = format!("{:.2}", formula.grade(&participant));
+        let grade = grade.trim_end_matches('0').trim_end_matches('.');
+        writeln!(out, "{},{}", csv_field(ext_id), grade)?;
+    }
+    if skipped > 0 {
+        eprintln!(
+            "{} participants without an external user id are not in the grades",
+            skipped
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! The fields the header of every meeting in the text, Markdown and HTML transcripts shows with `--header-fields`.
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+
+use crate::{model::Meeting, recording::Recordings};
+
+/// A field of the header of a meeting.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HeaderField {
+    /// The name from the BigBlueButton API.
+    Name,
+    /// The meeting id the room was created with, e.g. by Moodle.
+    ExtId,
+    Start,
+    /// The last message, join or leave.
+    End,
+    Duration,
+    /// How many people joined, the sessions of one person count once.
+    Participants,
+    /// The url of the playback.
+    Recording,
+}
+
+impl FromStr for HeaderField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(HeaderField::Name),
+            "extId" | "ext-id" | "ext_id" => Ok(HeaderField::ExtId),
+            "start" => Ok(HeaderField::Start),
+            "end" => Ok(HeaderField::End),
+            "duration" => Ok(HeaderField::Duration),
+            "participants" => Ok(HeaderField::Participants),
+            "recording" => Ok(HeaderField::Recording),
+            _ => Err(format!("unknown header field: {}", s)),
+        }
+    }
+}
+
+/// The fields of a comma separated list like `name,start,duration`.
+pub fn parse(list: &str) -> Result<Vec<HeaderField>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// The last message, join, leave or change of the recording of the meeting.
+fn end(meeting: &Meeting) -> NaiveDateTime {
+    let messages = meeting
+        .chats
+        .values()
+        .flat_map(|c| &c.messages)
+        .map(|m| m.time);
+    let sessions = meeting
+        .users
+        .values()
+        .flat_map(|u| &u.sessions)
+        .flat_map(|(joined, left)| Some(*joined).into_iter().chain(*left));
+    let recording = meeting.recording_changes.iter().map(|(time, _)| *time);
+    messages
+        .chain(sessions)
+        .chain(recording)
+        .fold(meeting.time, NaiveDateTime::max)
+}
+
+/// The labels and values of the fields in their order, the fields without a value are left out.
+pub fn values(
+    meeting: &Meeting,
+    fields: &[HeaderField],
+    recordings: Option<&Recordings>,
+) -> Vec<(&'static str, String)> {
+    let mut values = Vec::new();
+    for field in fields {
+        let value = match field {
+            HeaderField::Name => meeting.name.clone(),
+            HeaderField::ExtId => meeting.external_id.clone(),
+            HeaderField::Start => Some(meeting.time.format("%d.%m.%Y %H:%M").to_string()),
+            HeaderField::End => Some(end(meeting).format("%d.%m.%Y %H:%M").to_string()),
+            HeaderField::Duration => {
+                let minutes = (end(meeting) - meeting.time).num_minutes();
+                Some(format!("{} h {:02} min", minutes / 60, minutes % 60))
+            }
+            HeaderField::Participants => Some(meeting.participants().to_string()),
+            HeaderField::Recording => meeting
+                .playback_url
+                .clone()
+                .or_else(|| recordings.and_then(|r| r.link(meeting, meeting.time))),
+        };
+        let label = match field {
+            HeaderField::Name => "Name",
+            HeaderField::ExtId => "External id",
+            HeaderField::Start => "Start",
+            HeaderField::End => "End",
+            HeaderField::Duration => "Duration",
+            HeaderField::Participants => "Participants",
+            HeaderField::Recording => "Recording",
+        };
+        if let Some(value) = value {
+            values.push((label, value));
+        }
+    }
+    values
+}
@@ -0,0 +1,542 @@
+//! Reading the json, ndjson and csv exports back into meetings, so they can be written in other formats after the logs are gone.
+//!
+//! The exports carry the messages with their authors but not the participants that only joined, an attendance sheet of imported meetings is empty.
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    io::{self, Read},
+    path::Path,
+};
+
+use chrono::NaiveDateTime;
+use flate2::read::GzDecoder;
+use json::JsonValue;
+use whatlang::Lang;
+
+use crate::{
+    cli::Options,
+    compress::Compression,
+    input,
+    model::{Chat, ChatClear, Meeting, Mention, Message, Score, Withheld},
+};
+
+fn invalid(path: &Path, message: impl Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), message),
+    )
+}
+
+/// Read the whole export, a compressed one is decompressed by its extension.
+fn read(path: &Path, options: &Options) -> io::Result<String> {
+    let reader = input::open(path, options)?;
+    let mut reader: Box<dyn Read> = match Compression::of(path) {
+        Some(Compression::Gzip) => Box::new(GzDecoder::new(reader)),
+        #[cfg(feature = "zstd")]
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::with_buffer(reader)?),
+        #[cfg(not(feature = "zstd"))]
+        Some(Compression::Zstd) => {
+            return Err(io::Error::other(
+                "zstd needs chatter built with the zstd feature",
+            ))
+        }
+        None => Box::new(reader),
+    };
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// A time as the exports write it, see [`crate::export::timestamp`].
+fn time(path: &Path, value: &str) -> io::Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .map_err(|e| invalid(path, format!("the time {:?}: {}", value, e)))
+}
+
+fn text(value: &JsonValue) -> Option<String> {
+    value.as_str().map(str::to_string)
+}
+
+/// A message of the json or ndjson export.
+fn message(path: &Path, value: &JsonValue, meeting_id: &str, chat_id: &str) -> io::Result<Message> {
+    let time = time(path, value["time"].as_str().unwrap_or_default())?;
+    let author_id = text(&value["author_id"]).unwrap_or_default();
+    let message = text(&value["message"]).unwrap_or_default();
+    let withheld = value["withheld"]
+        .as_bool()
+        .unwrap_or(false)
+        .then(|| Withheld {
+            words: value["words"].as_usize().unwrap_or_default(),
+            characters: value["characters"].as_usize().unwrap_or_default(),
+        });
+    Ok(Message {
+        id: text(&value["id"])
+            .unwrap_or_else(|| Message::derive_id(meeting_id, chat_id, &author_id, time, &message)),
+        author: text(&value["author"]).unwrap_or_default(),
+        author_id,
+        ext_id: text(&value["ext_user_id"]),
+        role: value["role"].as_str().map(str::to_uppercase),
+        guest: value["guest"].as_bool().unwrap_or(false),
+        message,
+        time,
+        repeated: None,
+        language: value["language"]
+            .as_str()
+            .and_then(Lang::from_code)
+            .map(|lang| lang.code()),
+        score: value["score"].as_f64().map(Score),
+        raw: text(&value["raw"]),
+        withheld,
+        mentions: value["mentions"]
+            .members()
+            .map(|mention| Mention {
+                user_id: text(&mention["user_id"]).unwrap_or_default(),
+                name: text(&mention["name"]).unwrap_or_default(),
+                ext_id: text(&mention["ext_user_id"]),
+            })
+            .collect(),
+        segment: value["segment"].as_usize(),
+        oversized: value["oversized_bytes"].as_usize(),
+    })
+}
+
+/// The meetings read so far, a meeting that is in several exports is put together.
+#[derive(Default)]
+pub struct Archive {
+    pub meetings: HashMap<String, Meeting>,
+}
+
+impl Archive {
+    /// The meeting with this id, created with the time if it is not there yet.
+    fn meeting(&mut self, meeting_id: &str, time: NaiveDateTime) -> &mut Meeting {
+        let meeting = self
+            .meetings
+            .entry(meeting_id.to_string())
+            .or_insert_with(|| Meeting::new(meeting_id.to_string(), time));
+        // without the start of the meeting (ndjson, csv) it is the time of its earliest message
+        meeting.time = meeting.time.min(time);
+        meeting
+    }
+
+    fn push(&mut self, meeting_id: &str, chat_id: &str, msg: Message) {
+        self.meeting(meeting_id, msg.time)
+            .chats
+            .entry(chat_id.to_string())
+            .or_insert_with(|| Chat {
+                chat_id: chat_id.to_string(),
+                messages: Vec::new(),
+            })
+            .messages
+            .push(msg);
+    }
+
+    /// Add the meetings of a json export.
+    fn json(&mut self, path: &Path, doc: &JsonValue) -> io::Result<()> {
+        for value in doc.members() {
+            let meeting_id = value["meeting_id"]
+                .as_str()
+                .ok_or_else(|| invalid(path, "a meeting without a meeting_id"))?;
+            let start = time(path, value["time"].as_str().unwrap_or_default())?;
+            let meeting = self.meeting(meeting_id, start);
+            // the details of the first export that has them
+            meeting.name = meeting.name.take().or_else(|| text(&value["name"]));
+            for (key, value) in value["metadata"].entries() {
+                meeting
+                    .metadata
+                    .entry(key.to_string())
+                    .or_insert_with(|| value.as_str().unwrap_or_default().to_string());
+            }
+            meeting.playback_url = meeting
+                .playback_url
+                .take()
+                .or_else(|| text(&value["playback_url"]));
+            meeting.tenant = meeting.tenant.take().or_else(|| text(&value["tenant"]));
+            meeting.welcome = meeting.welcome.take().or_else(|| text(&value["welcome"]));
+            meeting.moderator_message = meeting
+                .moderator_message
+                .take()
+                .or_else(|| text(&value["moderator_message"]));
+            for lock in value["public_chat_locks"].members() {
+                let lock = (
+                    time(path, lock["time"].as_str().unwrap_or_default())?,
+                    lock["locked"].as_bool().unwrap_or(false),
+                );
+                if !meeting.public_chat_locks.contains(&lock) {
+                    meeting.public_chat_locks.push(lock);
+                }
+            }
+            for clear in value["public_chat_clears"].members() {
+                let clear = ChatClear {
+                    time: time(path, clear["time"].as_str().unwrap_or_default())?,
+                    user_id: text(&clear["user_id"]).unwrap_or_default(),
+                    name: text(&clear["name"]).unwrap_or_default(),
+                    ext_id: text(&clear["ext_user_id"]),
+                };
+                if !meeting.public_chat_clears.contains(&clear) {
+                    meeting.public_chat_clears.push(clear);
+                }
+            }
+            for chat in value["chats"].members() {
+                let chat_id = chat["chat_id"].as_str().unwrap_or_default();
+                for msg in chat["messages"].members() {
+                    let msg = message(path, msg, meeting_id, chat_id)?;
+                    self.push(meeting_id, chat_id, msg);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the messages of an ndjson export.
+    fn ndjson(&mut self, path: &Path, content: &str) -> io::Result<()> {
+        for (number, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value = json::parse(line)
+                .map_err(|e| invalid(path, format!("line {}: {}", number + 1, e)))?;
+            let meeting_id = value["meeting_id"].as_str().unwrap_or_default();
+            let chat_id = value["chat_id"].as_str().unwrap_or_default();
+            let msg = message(path, &value, meeting_id, chat_id)?;
+            self.push(meeting_id, chat_id, msg);
+        }
+        Ok(())
+    }
+
+    /// Add the messages of a csv export, which has no scores or languages.
+    fn csv(&mut self, path: &Path, content: &str) -> io::Result<()> {
+        let mut records = csv_records(content).into_iter();
+        let header = records.next().unwrap_or_default();
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|c| c == name)
+                .ok_or_else(|| invalid(path, format!("the {} column is missing", name)))
+        };
+        let (meeting_id, chat_id, time_column) =
+            (column("meeting_id")?, column("chat_id")?, column("time")?);
+        let (author, author_id, message) =
+            (column("author")?, column("author_id")?, column("message")?);
+        let (ext_id, words, characters) = (
+            column("ext_user_id").ok(),
+            column("words").ok(),
+            column("characters").ok(),
+        );
+        // the exports before the message ids, roles and guests were added
+        let (id, role, guest) = (column("id").ok(), column("role").ok(), column("guest").ok());
+        for record in records {
+            let field = |i: usize| record.get(i).map(String::as_str).unwrap_or_default();
+            let count = |i: Option<usize>| i.and_then(|i| field(i).parse().ok()).unwrap_or(0);
+            let time = time(path, field(time_column))?;
+            let text = field(message).to_string();
+            // an empty message with words had its text left out
+            let withheld = (text.is_empty() && count(words) > 0).then(|| Withheld {
+                words: count(words),
+                characters: count(characters),
+            });
+            let msg = Message {
+                id: id
+                    .map(field)
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| {
+                        Message::derive_id(
+                            field(meeting_id),
+                            field(chat_id),
+                            field(author_id),
+                            time,
+                            &text,
+                        )
+                    }),
+                author: field(author).to_string(),
+                author_id: field(author_id).to_string(),
+                ext_id: ext_id
+                    .map(field)
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string),
+                role: role
+                    .map(field)
+                    .filter(|role| !role.is_empty())
+                    .map(str::to_uppercase),
+                guest: guest.is_some_and(|i| field(i) == "true"),
+                message: text,
+                time,
+                repeated: None,
+                language: None,
+                score: None,
+                raw: None,
+                withheld,
+                mentions: Vec::new(),
+                segment: None,
+                oversized: None,
+            };
+            self.push(field(meeting_id), field(chat_id), msg);
+        }
+        Ok(())
+    }
+
+    /// Add an export, the format is told by its content.
+    pub fn load(&mut self, path: &Path, options: &Options) -> io::Result<()> {
+        let content = read(path, options)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        let content = content.trim_start_matches('\u{feff}');
+        match content.trim_start().chars().next() {
+            Some('[') => {
+                let doc = json::parse(content).map_err(|e| invalid(path, e))?;
+                self.json(path, &doc)
+            }
+            Some('{') => self.ndjson(path, content),
+            Some(_) if content.starts_with("meeting_id,") => self.csv(path, content),
+            // an empty export has no meetings
+            None => Ok(()),
+            Some(_) => Err(invalid(path, "not a json, ndjson or csv export of chatter")),
+        }
+    }
+
+    /// Sort the messages and keep only the first one of every message id in a chat, e.g. of exports that overlap.
+    pub fn finish(mut self) -> HashMap<String, Meeting> {
+        for meeting in self.meetings.values_mut() {
+            for chat in meeting.chats.values_mut() {
+                let mut seen = HashSet::new();
+                chat.messages.retain(|msg| seen.insert(msg.id.clone()));
+            }
+            meeting.sort_by_time();
+        }
+        self.meetings
+    }
+}
+
+/// The records of a CSV text, the fields may be quoted and contain separators, quotes and line breaks.
+fn csv_records(content: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{export, model::PUBLIC_CHAT};
+
+    fn meeting() -> Meeting {
+        let time = NaiveDate::from_ymd(2021, 3, 1).and_hms(10, 0, 0);
+        let mut moderator = Message::example("w_1", "Alice", "Hello, everyone", time);
+        moderator.role = Some("MODERATOR".to_string());
+        moderator.ext_id = Some("moodle-1".to_string());
+        let mut guest = Message::example("w_2", "Gast", "hi \"all\"\nsecond line", time);
+        guest.role = Some("VIEWER".to_string());
+        guest.guest = true;
+        let mut meeting = Meeting::new("meeting".to_string(), time);
+        meeting.chats.insert(
+            PUBLIC_CHAT.to_string(),
+            Chat {
+                chat_id: PUBLIC_CHAT.to_string(),
+                messages: vec![moderator, guest],
+            },
+        );
+        meeting
+    }
+
+    fn import(content: &str) -> Vec<Message> {
+        let mut archive = Archive {
+            meetings: HashMap::new(),
+        };
+        archive.csv(Path::new("export.csv"), content).unwrap();
+        let mut meetings = archive.finish();
+        let mut chats = meetings.remove("meeting").unwrap().chats;
+        chats.remove(PUBLIC_CHAT).unwrap().messages
+    }
+
+    fn csv(meeting: &Meeting) -> String {
+        let mut out = Vec::new();
+        export::write_csv(&mut out, std::iter::once(meeting)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn json<'a>(meetings: impl Iterator<Item = &'a Meeting>) -> String {
+        let mut out = Vec::new();
+        export::write_json(&mut out, meetings, None).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn ndjson<'a>(meetings: impl Iterator<Item = &'a Meeting>) -> String {
+        let mut out = Vec::new();
+        export::write_ndjson(&mut out, meetings).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Write the export into a temporary file.
+    fn file(test: &str, name: &str, content: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "chatter-import-{}-{}-{}",
+            test,
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// Read the exports the way convert and merge do.
+    fn load(paths: &[std::path::PathBuf]) -> HashMap<String, Meeting> {
+        let mut archive = Archive::default();
+        for path in paths {
+            archive.load(path, &Options::default()).unwrap();
+            std::fs::remove_file(path).unwrap();
+        }
+        archive.finish()
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut meeting = meeting();
+        meeting.name = Some("Mathe 1".to_string());
+        meeting.external_id = Some("course-42".to_string());
+        let exported = json(std::iter::once(&meeting));
+        let meetings = load(&[file("json", "export.json", exported.as_bytes())]);
+        assert_eq!(json(meetings.values()), exported);
+    }
+
+    #[test]
+    fn ndjson_round_trip_also_compressed() {
+        use std::io::Write;
+
+        let exported = ndjson(std::iter::once(&meeting()));
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(exported.as_bytes()).unwrap();
+        for path in [
+            file("ndjson", "export.ndjson", exported.as_bytes()),
+            file("ndjson", "export.ndjson.gz", &gzip.finish().unwrap()),
+        ] {
+            let meetings = load(&[path]);
+            assert_eq!(ndjson(meetings.values()), exported);
+        }
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let exported = csv(&meeting());
+        let meetings = load(&[file("csv", "export.csv", exported.as_bytes())]);
+        assert_eq!(csv(&meetings["meeting"]), exported);
+    }
+
+    fn texts(meeting: &Meeting) -> Vec<&str> {
+        meeting.chats[PUBLIC_CHAT]
+            .messages
+            .iter()
+            .map(|m| m.message.as_str())
+            .collect()
+    }
+
+    fn later(meeting: &mut Meeting, text: &str, minutes: i64) {
+        let time = meeting.time + chrono::Duration::minutes(minutes);
+        let chat = meeting.chats.get_mut(PUBLIC_CHAT).unwrap();
+        chat.messages
+            .push(Message::example("w_1", "Alice", text, time));
+    }
+
+    #[test]
+    fn an_export_is_merged_with_the_meetings_of_the_logs() {
+        let mut logs = meeting();
+        let exported = ndjson(std::iter::once(&logs));
+        later(&mut logs, "see you", 5);
+        let path = file("logs", "archive.ndjson", exported.as_bytes());
+        let mut archive = Archive {
+            meetings: HashMap::from([("meeting".to_string(), logs)]),
+        };
+        archive.load(&path, &Options::default()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let meetings = archive.finish();
+        assert_eq!(
+            texts(&meetings["meeting"]),
+            ["Hello, everyone", "hi \"all\"\nsecond line", "see you"]
+        );
+    }
+
+    #[test]
+    fn the_meetings_of_several_exports_are_merged() {
+        let (mut first, mut second) = (meeting(), meeting());
+        later(&mut first, "only on the first server", 1);
+        later(&mut second, "only on the second server", 2);
+        let mut other = Meeting::new("other".to_string(), second.time);
+        other.chats = second.chats.clone();
+        let meetings = load(&[
+            file(
+                "merge",
+                "first.json",
+                json(std::iter::once(&first)).as_bytes(),
+            ),
+            file(
+                "merge",
+                "second.json",
+                json(vec![&second, &other].into_iter()).as_bytes(),
+            ),
+        ]);
+        assert_eq!(meetings.len(), 2);
+        assert_eq!(
+            texts(&meetings["meeting"]),
+            [
+                "Hello, everyone",
+                "hi \"all\"\nsecond line",
+                "only on the first server",
+                "only on the second server"
+            ]
+        );
+        assert_eq!(texts(&meetings["other"]).len(), 3);
+    }
+
+    #[test]
+    fn not_an_export() {
+        let path = file("invalid", "export.txt", b"2021-03-01 INFO something");
+        let error = Archive::default()
+            .load(&path, &Options::default())
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn csv_keeps_the_roles_and_guests() {
+        let messages = import(&csv(&meeting()));
+        let people: Vec<(&str, Option<&str>, bool)> = messages
+            .iter()
+            .map(|m| (m.author.as_str(), m.role.as_deref(), m.guest))
+            .collect();
+        assert_eq!(
+            people,
+            [
+                ("Alice", Some("MODERATOR"), false),
+                ("Gast", Some("VIEWER"), true)
+            ]
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! The `report` subcommand: everything written by, to and about one person in one chronological document, for the investigation of an incident.
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use crate::{
+    model::{Meeting, Message, PUBLIC_CHAT},
+    sar::is_subject_message,
+};
+
+/// Check if the message addresses the subject with `@Name`.
+fn mentions_subject(msg: &Message, subject: &str) -> bool {
+    msg.mentions
+        .iter()
+        .any(|m| m.ext_id.as_deref() == Some(subject) || m.name == subject)
+}
+
+/// The meetings reduced to the messages from, to and mentioning the subject, written between the days `from` and `to`.
+///
+/// The private chats have no list of their participants, so a private chat belongs to the subject if the subject wrote in it, then all its messages are kept.
+pub fn select(
+    meetings: &HashMap<String, Meeting>,
+    subject: &str,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> HashMap<String, Meeting> {
+    let in_range = |msg: &Message| {
+        let day = msg.time.date();
+        from.is_none_or(|from| from <= day) && to.is_none_or(|to| day <= to)
+    };
+    let mut selected = HashMap::new();
+    for meeting in meetings.values() {
+        let mut part = meeting.without_chats();
+        for chat in meeting.chats.values() {
+            let private = chat.chat_id != PUBLIC_CHAT
+                && chat
+                    .messages
+                    .iter()
+                    .any(|msg| is_subject_message(msg, subject));
+            let messages: Vec<Message> = chat
+                .messages
+                .iter()
+                .filter(|msg| {
+                    private || is_subject_message(msg, subject) || mentions_subject(msg, subject)
+                })
+                .filter(|msg| in_range(msg))
+                .cloned()
+                .collect();
+            if !messages.is_empty() {
+                let mut chat = chat.without_messages();
+                chat.messages = messages;
+                part.chats.insert(chat.chat_id.clone(), chat);
+            }
+        }
+        if !part.chats.is_empty() {
+            selected.insert(part.meeting_id.clone(), part);
+        }
+    }
+    selected
+}
@@ -0,0 +1,316 @@
+//! Finding, opening and decoding the log files.
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Cursor, Read},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::cli::Options;
+
+/// Rotated logs are often compressed, those can not be read line by line and are skipped.
+pub fn is_compressed(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("gz" | "zst" | "xz" | "bz2" | "zip")
+    )
+}
+
+/// Whether the path is the URL of an object in S3 compatible storage, `s3://bucket/key`.
+pub fn is_object(path: &Path) -> bool {
+    path.to_str().is_some_and(|path| path.starts_with("s3://"))
+}
+
+/// Whether the path is an `http://` or `https://` URL.
+pub fn is_url(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|path| path.starts_with("http://") || path.starts_with("https://"))
+}
+
+/// Whether the path is a named pipe, e.g. the `/dev/fd/63` of a process substitution `<(…)`, which can be read only once and while it is written.
+#[cfg(unix)]
+pub fn is_pipe(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata(path).is_ok_and(|meta| meta.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+pub fn is_pipe(_path: &Path) -> bool {
+    false
+}
+
+/// How long to wait before reading again from a non-blocking input that has nothing yet.
+const RETRY: Duration = Duration::from_millis(10);
+
+/// Read into the buffer, waiting while a non-blocking input has nothing to read. Returns 0 at the end.
+pub fn read_blocking<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match reader.read(buf) {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(RETRY),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            result => return result,
+        }
+    }
+}
+
+/// The chunks one named pipe sends, read by its own thread.
+struct Received {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    chunk: Cursor<Vec<u8>>,
+}
+
+impl Read for Received {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.chunk.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            match self.chunks.recv() {
+                Ok(chunk) => self.chunk = Cursor::new(chunk?),
+                // the writer closed the pipe
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
+
+/// The named pipes among the inputs, all of them are read from the start.
+///
+/// The inputs are read one after the other, but a process that writes to several pipes (e.g. through `tee`) or several processes that wait for each other would block while their pipe is full. So every pipe is read by a thread of its own into memory until its turn comes.
+pub struct Pipes(HashMap<PathBuf, Receiver<io::Result<Vec<u8>>>>);
+
+impl Pipes {
+    pub fn start(inputs: &[PathBuf]) -> Pipes {
+        let mut pipes = HashMap::new();
+        for path in inputs.iter().filter(|path| is_pipe(path)) {
+            let (sender, chunks) = mpsc::channel();
+            let pipe = path.clone();
+            thread::spawn(move || {
+                // opening a pipe waits until the other end is opened for writing
+                let mut file = match File::open(&pipe) {
+                    Ok(file) => file,
+                    Err(e) => return sender.send(Err(e)),
+                };
+                loop {
+                    let mut chunk = vec![0; 64 * 1024];
+                    match read_blocking(&mut file, &mut chunk) {
+                        Ok(0) => return Ok(()),
+                        Ok(read) => {
+                            chunk.truncate(read);
+                            sender.send(Ok(chunk))?;
+                        }
+                        Err(e) => return sender.send(Err(e)),
+                    }
+                }
+            });
+            pipes.insert(path.clone(), chunks);
+        }
+        Pipes(pipes)
+    }
+
+    /// Open an input like [`open`], a pipe is read from its thread.
+    pub fn open(&mut self, path: &Path, options: &Options) -> io::Result<Box<dyn BufRead>> {
+        match self.0.remove(path) {
+            Some(chunks) => unicode(Box::new(BufReader::new(Received {
+                chunks,
+                chunk: Cursor::new(Vec::new()),
+            }))),
+            None => open(path, options),
+        }
+    }
+}
+
+/// Download the file at the URL with the `--http-user` and `--http-header`s, the body is read as it arrives.
+fn get(url: &str, options: &Options) -> io::Result<Box<dyn Read + Send>> {
+    let mut request = ureq::get(url);
+    if let Some(user) = &options.http_user {
+        request = request.set("Authorization", &format!("Basic {}", STANDARD.encode(user)));
+    }
+    for (name, value) in &options.http_headers {
+        request = request.set(name, value);
+    }
+    match request.call() {
+        Ok(response) => Ok(response.into_reader()),
+        Err(ureq::Error::Status(status, response)) => {
+            let kind = if status == 404 {
+                io::ErrorKind::NotFound
+            } else {
+                io::ErrorKind::Other
+            };
+            Err(io::Error::new(
+                kind,
+                format!("status code {} {}", status, response.status_text()),
+            ))
+        }
+        // without the URL, it is in front of the error already
+        Err(ureq::Error::Transport(e)) => Err(io::Error::other(match e.message() {
+            Some(message) => format!("{}: {}", e.kind(), message),
+            None => e.kind().to_string(),
+        })),
+    }
+}
+
+/// All the files in a directory that look like readable log files sorted by their name.
+pub fn dir_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && !is_compressed(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The files given on the command line followed by the files of the directory. An `s3://bucket/prefix/` stands for all the objects under the prefix.
+pub fn files(options: &Options) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in &options.inputs {
+        #[cfg(feature = "s3")]
+        if is_object(path) {
+            files.extend(crate::s3::list(path)?);
+            continue;
+        }
+        files.push(path.clone());
+    }
+    if let Some(dir) = &options.dir {
+        files.extend(dir_files(dir)?);
+    }
+    Ok(files)
+}
+
+/// Open an input for reading, `-` is the standard in, `s3://bucket/key` an object and an `http(s)://` URL is downloaded.
+///
+/// A file that starts with the byte order mark of UTF-16, e.g. because it was saved again by a Windows editor, is read completely and converted to UTF-8.
+pub fn open(path: &Path, options: &Options) -> io::Result<Box<dyn BufRead>> {
+    let reader: Box<dyn BufRead> = if path == Path::new("-") {
+        Box::new(BufReader::new(io::stdin()))
+    } else if let Some(url) = path.to_str().filter(|_| is_url(path)) {
+        Box::new(BufReader::new(get(url, options)?))
+    } else if is_object(path) {
+        #[cfg(feature = "s3")]
+        {
+            Box::new(BufReader::new(crate::s3::open(path)?))
+        }
+        #[cfg(not(feature = "s3"))]
+        return Err(io::Error::other(
+            "s3:// needs chatter built with the s3 feature",
+        ));
+    } else {
+        Box::new(BufReader::new(File::open(path)?))
+    };
+    unicode(reader)
+}
+
+/// The start of the buffered input, waiting while a non-blocking input has nothing to read.
+fn fill_buf(reader: &mut Box<dyn BufRead>) -> io::Result<&[u8]> {
+    loop {
+        match reader.fill_buf() {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(RETRY),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+            Ok(_) => break,
+        }
+    }
+    reader.fill_buf()
+}
+
+/// The input converted to UTF-8 if it starts with the byte order mark of UTF-16.
+fn unicode(mut reader: Box<dyn BufRead>) -> io::Result<Box<dyn BufRead>> {
+    let little_endian = match fill_buf(&mut reader)? {
+        [0xff, 0xfe, ..] => true,
+        [0xfe, 0xff, ..] => false,
+        _ => return Ok(reader),
+    };
+    let mut bytes = Vec::new();
+    let mut chunk = [0; 8192];
+    loop {
+        match read_blocking(&mut reader, &mut chunk)? {
+            0 => break,
+            read => bytes.extend_from_slice(&chunk[..read]),
+        }
+    }
+    let units: Vec<u16> = bytes[2..]
+        .chunks_exact(2)
+        .map(|pair| {
+            let pair = [pair[0], pair[1]];
+            if little_endian {
+                u16::from_le_bytes(pair)
+            } else {
+                u16::from_be_bytes(pair)
+            }
+        })
+        .collect();
+    Ok(Box::new(Cursor::new(
+        String::from_utf16_lossy(&units).into_bytes(),
+    )))
+}
+
+/// The `number`th line of a file without its line ending (`\n` or `\r\n`) and the byte order mark of UTF-8 at the start of the file. Invalid UTF-8 is replaced by U+FFFD with a warning, so the logs that passed through other systems can still be read.
+pub fn decode(bytes: &[u8], path: &Path, number: usize) -> String {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    let bytes = match bytes.strip_prefix(b"\xef\xbb\xbf") {
+        Some(rest) if number == 1 => rest,
+        _ => bytes,
+    };
+    match String::from_utf8_lossy(bytes) {
+        Cow::Borrowed(line) => line.to_string(),
+        Cow::Owned(line) => {
+            eprintln!(
+                "{}:{}: invalid UTF-8 replaced by U+FFFD",
+                path.display(),
+                number
+            );
+            line
+        }
+    }
+}
+
+/// The lines of an input, see [`decode`].
+///
+/// A line that is only partly there when a non-blocking input has nothing more to read yet is completed when the rest arrives, the last line counts even without a line break.
+pub struct Lines<R> {
+    reader: R,
+    path: PathBuf,
+    number: usize,
+    partial: Vec<u8>,
+}
+
+/// Read the lines of an input opened from `path`.
+pub fn lines<R: BufRead>(reader: R, path: &Path) -> Lines<R> {
+    Lines {
+        reader,
+        path: path.to_path_buf(),
+        number: 0,
+        partial: Vec::new(),
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+        loop {
+            match self.reader.read_until(b'\n', &mut self.partial) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(RETRY),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Some(Err(e)),
+                Ok(0) if self.partial.is_empty() => return None,
+                Ok(_) => {
+                    self.number += 1;
+                    let bytes = std::mem::take(&mut self.partial);
+                    return Some(Ok(decode(&bytes, &self.path, self.number)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! The envelope names of a log sample and which of their fields are found, to tell quickly whether the logs of an unfamiliar BigBlueButton version can be read.
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use json::JsonValue;
+
+use crate::{
+    cli::{Format, Options},
+    correlate,
+    fields::{FieldMap, Selector},
+    input,
+    table::Table,
+};
+
+const COLUMNS: [&str; 5] = ["envelope", "lines", "read as", "fields", "missing"];
+
+/// An event that is no chat message.
+struct Known {
+    name: &'static str,
+    /// What it is read as.
+    kind: &'static str,
+    /// Its fields besides the timestamp and the meeting id with their selectors.
+    fields: &'static [(&'static str, &'static str)],
+}
+
+const KNOWN: [Known; 8] = [
+    Known {
+        name: "MeetingCreatedEvtMsg",
+        kind: "meeting created",
+        fields: &[
+            ("external_id", "core.body.props.meetingProp.extId"),
+            ("welcome", "core.body.props.welcomeProp.welcomeMsg"),
+            (
+                "moderator_message",
+                "core.body.props.welcomeProp.modOnlyMessage",
+            ),
+            ("metadata", "core.body.props.metadataProp.metadata"),
+        ],
+    },
+    Known {
+        name: "UserJoinedMeetingEvtMsg",
+        kind: "join",
+        fields: &[
+            ("user_id", "core.body.intId"),
+            ("name", "core.body.name"),
+            ("ext_id", "core.body.extId"),
+            ("role", "core.body.role"),
+            ("guest", "core.body.guest"),
+        ],
+    },
+    Known {
+        name: "UserLeftMeetingEvtMsg",
+        kind: "leave",
+        fields: &[("user_id", "core.body.intId")],
+    },
+    Known {
+        name: "RecordingStatusChangedEvtMsg",
+        kind: "recording",
+        fields: &[("recording", "core.body.recording")],
+    },
+    Known {
+        name: "LockSettingsInMeetingChangedEvtMsg",
+        kind: "lock settings",
+        fields: &[("public_chat_locked", "core.body.disablePubChat")],
+    },
+    Known {
+        name: "ClearPublicChatHistoryPubMsg",
+        kind: "public chat cleared",
+        fields: &[("user_id", "core.header.userId")],
+    },
+    Known {
+        name: "ClearPublicChatHistoryEvtMsg",
+        kind: "public chat cleared",
+        fields: &[("user_id", "core.header.userId")],
+    },
+    Known {
+        name: "PollStartedEvtMsg",
+        kind: "poll",
+        fields: &[
+            ("question", "core.body.question"),
+            ("poll_id", "core.body.pollId"),
+        ],
+    },
+];
+
+/// What an event is read as and the selectors of the fields that are taken from it.
+fn fields_of(fields: &FieldMap, name: &str) -> (&'static str, Vec<(&'static str, Selector)>) {
+    let selectors = fields.fields(name);
+    let mut of = vec![
+        ("timestamp", selectors.timestamp.clone()),
+        ("meeting_id", selectors.meeting_id.clone()),
+    ];
+    if let Some(known) = KNOWN.iter().find(|known| known.name == name) {
+        for &(field, selector) in known.fields {
+            of.push((
+                field,
+                selector.parse().expect("the known selectors are valid"),
+            ));
+        }
+        return (known.kind, of);
+    }
+    of.extend([
+        ("chat_id", selectors.chat_id.clone()),
+        ("author", selectors.author.clone()),
+        ("author_id", selectors.author_id.clone()),
+        ("role", selectors.role.clone()),
+        ("text", selectors.text.clone()),
+        ("id", selectors.id.clone()),
+    ]);
+    // every other event of the log is taken as a chat message
+    let kind = if name == "GroupChatMessageBroadcastEvtMsg" || fields.has_event(name) {
+        "chat message"
+    } else {
+        "chat message, unknown event"
+    };
+    (kind, of)
+}
+
+/// Whether the field is in the event, the timestamp has to be a number like for [`crate::event::parse`].
+fn found(field: &str, value: &JsonValue) -> bool {
+    match field {
+        "timestamp" => {
+            value.as_i64().is_some() || value.as_str().is_some_and(|t| t.parse::<i64>().is_ok())
+        }
+        _ => !value.is_null(),
+    }
+}
+
+/// The lines of one envelope name.
+struct Sample {
+    lines: usize,
+    kind: &'static str,
+    fields: Vec<(&'static str, Selector)>,
+    /// How many lines have each of the fields.
+    found: Vec<usize>,
+}
+
+impl Sample {
+    fn new(kind: &'static str, fields: Vec<(&'static str, Selector)>) -> Sample {
+        Sample {
+            lines: 0,
+            kind,
+            found: vec![0; fields.len()],
+            fields,
+        }
+    }
+}
+
+/// Count a line without an envelope, it has no fields.
+fn count(samples: &mut HashMap<String, Sample>, name: &str, kind: &'static str) {
+    samples
+        .entry(name.to_string())
+        .or_insert_with(|| Sample::new(kind, Vec::new()))
+        .lines += 1;
+}
+
+/// Read the first `--sample` lines of the inputs and print a table of their envelope names, how many lines have each name, what chatter reads them as, the fields found in every line and the ones missing in some, with the share of the lines they are in.
+pub fn run<W: Write>(
+    out: &mut W,
+    options: &Options,
+    fields: &FieldMap,
+    inputs: &[PathBuf],
+) -> io::Result<()> {
+    let mut samples: HashMap<String, Sample> = HashMap::new();
+    let mut read = 0;
+    'inputs: for path in inputs {
+        let reader = input::open(path, options)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        for line in input::lines(reader, path) {
+            if read == options.sample {
+                break 'inputs;
+            }
+            read += 1;
+            let line = line?;
+            let start = match line.find('{') {
+                Some(start) => start,
+                None => {
+                    count(&mut samples, "(no JSON)", "skipped");
+                    continue;
+                }
+            };
+            let data = match json::parse(&line[start..]) {
+                Ok(data) => data,
+                Err(_) => {
+                    count(&mut samples, "(invalid JSON)", "skipped");
+                    continue;
+                }
+            };
+            if correlate::is_external(&data) {
+                let log = if data.has_key("logCode") {
+                    "(bbb-web)"
+                } else {
+                    "(FreeSWITCH)"
+                };
+                let kind = if correlate::parse(&line, &data).is_some() {
+                    "correlation"
+                } else {
+                    "skipped"
+                };
+                count(&mut samples, log, kind);
+                continue;
+            }
+            let name = data["envelope"]["name"].as_str().unwrap_or("(no name)");
+            let sample = samples.entry(name.to_string()).or_insert_with(|| {
+                let (kind, of) = fields_of(fields, name);
+                Sample::new(kind, of)
+            });
+            sample.lines += 1;
+            for (i, (field, selector)) in sample.fields.iter().enumerate() {
+                if found(field, selector.select(&data)) {
+                    sample.found[i] += 1;
+                }
+            }
+        }
+    }
+
+    let mut samples: Vec<(String, Sample)> = samples.into_iter().collect();
+    samples.sort_by(|(a, x), (b, y)| y.lines.cmp(&x.lines).then_with(|| a.cmp(b)));
+    let mut table = Table::new(COLUMNS.to_vec());
+    for (name, sample) in samples {
+        let mut present = Vec::new();
+        let mut missing = Vec::new();
+        for ((field, _), found) in sample.fields.iter().zip(&sample.found) {
+            if *found == sample.lines {
+                present.push(field.to_string());
+            } else {
+                missing.push(format!(
+                    "{} {:.0}%",
+                    field,
+                    100.0 * *found as f64 / sample.lines as f64
+                ));
+            }
+        }
+        table.push(vec![
+            name.into(),
+            sample.lines.into(),
+            sample.kind.into(),
+            present.join(", ").into(),
+            missing.join(", ").into(),
+        ]);
+    }
+    table.write(out, options.format)?;
+    // the rows of the table are enough for the machine readable formats
+    if options.format == Format::Text {
+        writeln!(out, "{} lines read", read)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! Read the events from a Kafka topic, e.g. one the messages of the BigBlueButton Redis channels are mirrored to.
+use std::io;
+
+use kafka::consumer::{Consumer, FetchOffset, GroupOffsetStorage};
+
+use crate::{event, fields::FieldMap, follow::Source};
+
+/// A consumer of the topic in a consumer group, so a restarted run continues where the last one stopped.
+pub struct Topic {
+    consumer: Consumer,
+    /// Which events are passed on.
+    fields: FieldMap,
+    name: String,
+}
+
+impl Topic {
+    /// Join the group on the brokers, a new group only gets the events published from now on.
+    pub fn connect(
+        brokers: &[String],
+        topic: &str,
+        group: &str,
+        fields: &FieldMap,
+    ) -> io::Result<Topic> {
+        let consumer = Consumer::from_hosts(brokers.to_vec())
+            .with_topic(topic.to_string())
+            .with_group(group.to_string())
+            .with_fallback_offset(FetchOffset::Latest)
+            .with_offset_storage(Some(GroupOffsetStorage::Kafka))
+            .create()
+            .map_err(io::Error::other)?;
+        Ok(Topic {
+            consumer,
+            fields: fields.clone(),
+            name: format!("kafka://{}/{}", brokers.join(","), topic),
+        })
+    }
+}
+
+impl Source for Topic {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn poll(&mut self) -> io::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        let sets = self.consumer.poll().map_err(io::Error::other)?;
+        for set in sets.iter() {
+            for message in set.messages() {
+                // the key is not needed, the event carries the meeting id itself. The topic mirrors every event of the channels like Redis, only the ones of the log are passed on
+                let payload = String::from_utf8_lossy(message.value);
+                if event::is_read_payload(&self.fields, &payload) {
+                    lines.push(payload.into_owned());
+                }
+            }
+            self.consumer
+                .consume_messageset(set)
+                .map_err(io::Error::other)?;
+        }
+        self.consumer.commit_consumed().map_err(io::Error::other)?;
+        Ok(lines)
+    }
+}
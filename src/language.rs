@@ -0,0 +1,78 @@
+//! The languages of the messages, to see which meetings drifted into another language than the one of the course.
+use std::{collections::BTreeMap, io};
+
+use whatlang::{Detector, Lang};
+
+use crate::{
+    model::{Meeting, Message},
+    transform::MessageTransform,
+};
+
+/// Below this confidence a message counts as undetermined, chat messages are short and often just a "ok" or a link.
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// Parse a comma separated list of ISO 639-3 codes like `deu,eng`.
+pub fn parse_list(list: &str) -> Result<Vec<Lang>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .map(|code| Lang::from_code(code).ok_or_else(|| format!("unknown language: {}", code)))
+        .collect()
+}
+
+/// Detects the language of every message.
+pub struct Detection {
+    detector: Detector,
+}
+
+impl Detection {
+    /// Only the `allowed` languages are considered unless the list is empty.
+    pub fn new(allowed: &[Lang]) -> Detection {
+        let detector = if allowed.is_empty() {
+            Detector::new()
+        } else {
+            Detector::with_allowlist(allowed.to_vec())
+        };
+        Detection { detector }
+    }
+}
+
+impl MessageTransform for Detection {
+    fn message(&mut self, msg: &mut Message) -> io::Result<bool> {
+        msg.language = self
+            .detector
+            .detect(&msg.lines().join("\n"))
+            .filter(|info| info.confidence() >= MIN_CONFIDENCE)
+            .map(|info| info.lang().code());
+        Ok(true)
+    }
+}
+
+/// The share of the messages of the meeting in every language, the most common first. The messages without a detected language do not count.
+pub fn shares(meeting: &Meeting) -> Vec<(&'static str, f64)> {
+    let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for language in meeting
+        .chats
+        .values()
+        .flat_map(|c| &c.messages)
+        .filter_map(|msg: &Message| msg.language)
+    {
+        *counts.entry(language).or_insert(0) += 1;
+    }
+    let total: usize = counts.values().sum();
+    let mut shares: Vec<(&'static str, f64)> = counts
+        .into_iter()
+        .map(|(language, n)| (language, n as f64 * 100.0 / total as f64))
+        .collect();
+    shares.sort_by(|a, b| b.1.total_cmp(&a.1));
+    shares
+}
+
+/// The shares of the languages like `85% deu, 10% eng`.
+pub fn summary(meeting: &Meeting) -> String {
+    shares(meeting)
+        .iter()
+        .map(|(language, share)| format!("{:.0}% {}", share, language))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,81 @@
+//! Reading the chats of BigBlueButton meetings from the akka-apps logs.
+//!
+//! [`EventStream`] reads the log lines as typed [`Event`]s one by one, so an embedder can count or aggregate them without keeping the transcripts. [`MeetingAggregator`] collects the events into [`Meeting`](model::Meeting)s with their chats like the `chatter` command does:
+//!
+//! ```ignore
+//! let mut meetings = MeetingAggregator::default();
+//! for event in EventStream::new(BufReader::new(File::open("bbb-apps-akka.log")?)) {
+//!     meetings.event(event);
+//! }
+//! ```
+//!
+//! A [`Pipeline`] of [`MessageTransform`]s changes or drops the collected messages, e.g. scores them or replaces their authors by pseudonyms, and takes stages of the embedder as well.
+//!
+//! With the `tokio` feature [`parse_async`] reads the events from an `AsyncBufRead` instead, e.g. in an ingestion service reading from TCP syslog or Kafka.
+pub mod anonymize;
+pub mod bbb;
+pub mod checksum;
+pub mod cli;
+pub mod collation;
+pub mod collect;
+pub mod compress;
+pub mod correlate;
+pub mod deliver;
+pub mod diff;
+pub mod document;
+pub mod epub;
+pub mod event;
+pub mod explain;
+pub mod export;
+pub mod expr;
+pub mod fields;
+pub mod follow;
+pub mod grade;
+pub mod header;
+pub mod import;
+pub mod incident;
+pub mod input;
+pub mod inspect;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod language;
+pub mod mention;
+pub mod model;
+pub mod normalize;
+pub mod notify;
+pub mod odt;
+pub mod output;
+pub mod pager;
+pub mod provenance;
+pub mod query;
+pub mod quote;
+pub mod recording;
+#[cfg(feature = "redis")]
+pub mod redis;
+pub mod report;
+pub mod rollup;
+#[cfg(feature = "s3")]
+pub mod s3;
+pub mod sample;
+pub mod sar;
+pub mod schema;
+pub mod score;
+pub mod segment;
+pub mod shutdown;
+pub mod spam;
+pub mod spill;
+pub mod split;
+pub mod stats;
+pub mod subtitle;
+pub mod summary;
+pub mod table;
+pub mod talk;
+pub mod tenant;
+pub mod timeline;
+pub mod transform;
+
+pub use collect::Collector as MeetingAggregator;
+#[cfg(feature = "tokio")]
+pub use event::{parse_async, AsyncEventStream};
+pub use event::{Event, EventStream};
+pub use transform::{MessageTransform, Pipeline};
@@ -1,163 +1,688 @@
 use std::{
     collections::HashMap,
-    fmt::Display,
-    hash::Hash,
-    io::{self, BufRead},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
 };
 
-use chrono::{Duration, NaiveDateTime};
+use chatter::{
+    bbb,
+    cli::{self, Command, Format, Options},
+    collect::{self, Collector, Unattributed},
+    compress::Output,
+    diff, explain, export,
+    fields::FieldMap,
+    follow, import, incident, input, inspect,
+    model::{self, Meeting},
+    notify, output, pager, provenance,
+    recording::Recordings,
+    report, sample, sar, schema, segment, spam,
+    spill::Spill,
+    split, talk, tenant, timeline, Pipeline,
+};
 
-/// Representing a BigBlueButton meeting most of the things are omitted and just meeting_id the approximate time and the (private and public)chats.
-struct Meeting {
-    meeting_id: String,
-    time: NaiveDateTime,
-    chats: HashMap<String, Chat>,
+/// Connect to a live input that is not a file.
+#[cfg_attr(
+    not(any(feature = "redis", feature = "kafka")),
+    allow(unused_variables)
+)]
+fn live_source(options: &Options, fields: &FieldMap) -> io::Result<Box<dyn follow::Source>> {
+    #[cfg(feature = "kafka")]
+    if options.input == cli::Input::Kafka {
+        return Ok(Box::new(chatter::kafka::Topic::connect(
+            &options.brokers,
+            options.topic.as_deref().unwrap_or_default(),
+            &options.group,
+            fields,
+        )?));
+    }
+    #[cfg(feature = "redis")]
+    if options.input == cli::Input::Redis {
+        let channels = if options.channels.is_empty() {
+            vec![chatter::redis::DEFAULT_CHANNEL.to_string()]
+        } else {
+            options.channels.clone()
+        };
+        return Ok(Box::new(chatter::redis::Channels::subscribe(
+            options.url.as_deref().unwrap_or_default(),
+            &channels,
+            fields,
+        )?));
+    }
+    // the options only accept the inputs that are built in
+    Err(io::Error::other(format!(
+        "{:?} is not built in",
+        options.input
+    )))
 }
 
-impl Hash for Meeting {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.meeting_id.hash(state);
+/// Parse the input files or the stdin (incomming pipe) and print nicely formatted chatmessages ordered by their meetings and their chatroom.
+///
+/// Note: if the order of the messages isnt consequential the meeting times will be slightly wrong
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.command == Command::Schema {
+        let stdout = io::stdout();
+        schema::print(&mut stdout.lock(), options.format).expect("Could not write the schema");
+        return;
+    }
+    if options.command == Command::Diff {
+        let stdout = io::stdout();
+        let differ = diff::run(&mut stdout.lock(), &options.inputs[0], &options.inputs[1])
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            });
+        std::process::exit(if differ { 1 } else { 0 });
+    }
+    if matches!(options.command, Command::Convert | Command::Merge) {
+        let mut inputs = input::files(&options).expect("Could not list the input directory");
+        if inputs.is_empty() {
+            inputs.push(PathBuf::from("-"));
+        }
+        let mut archive = import::Archive::default();
+        for path in &inputs {
+            archive.load(path, &options).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            });
+        }
+        let read = messages(&archive.meetings);
+        let meetings = archive.finish();
+        if options.command == Command::Merge {
+            eprintln!(
+                "{} meetings with {} messages from {} exports, {} messages were in more than one",
+                meetings.len(),
+                messages(&meetings),
+                inputs.len(),
+                read - messages(&meetings)
+            );
+        }
+        write(&options, &inputs, &meetings, None);
+        return;
+    }
+    let fields = match &options.field_map {
+        Some(path) => FieldMap::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }),
+        None => FieldMap::default(),
+    };
+    let tenants = match &options.tenant_map {
+        Some(path) => tenant::Tenants::load(path).unwrap_or_else(|e| {
+            eprintln!("Could not read {}: {}", path.display(), e);
+            std::process::exit(2);
+        }),
+        None => tenant::Tenants::default(),
+    };
+    if options.command == Command::Explain {
+        let stdout = io::stdout();
+        let line = options.explain.as_deref().unwrap_or_default();
+        let kept = explain::run(&mut stdout.lock(), &options, &fields, &tenants, line)
+            .expect("Could not write the explanation");
+        std::process::exit(if kept { 0 } else { 1 });
+    }
+    if options.command == Command::Inspect {
+        let mut inputs = input::files(&options).expect("Could not list the input directory");
+        if inputs.is_empty() {
+            inputs.push(PathBuf::from("-"));
+        }
+        let stdout = io::stdout();
+        inspect::run(&mut stdout.lock(), &options, &fields, &inputs).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        });
+        return;
+    }
+    let mut collector = Collector {
+        keep_raw: options.include_raw,
+        fields,
+        events: options.events.clone(),
+        max_meetings: options.max_meetings,
+        tenants,
+        clock: collect::Clock {
+            max_skew: Some(chrono::Duration::seconds(options.max_clock_skew)),
+            ..collect::Clock::default()
+        },
+        spill: options.max_memory.map(|max| {
+            Spill::new(max).unwrap_or_else(|e| {
+                eprintln!("Could not create the spill database: {}", e);
+                std::process::exit(2);
+            })
+        }),
+        ..Collector::default()
+    };
+
+    if options.input != cli::Input::Files {
+        let mut source = live_source(&options, &collector.fields).unwrap_or_else(|e| {
+            eprintln!("Could not connect to the input: {}", e);
+            std::process::exit(2);
+        });
+        follow::run_source(&options, &mut collector, source.as_mut())
+            .expect("Could not follow the input");
+        return;
+    }
+    if options.follow {
+        follow::run(&options, &mut collector).expect("Could not follow the inputs");
+        return;
+    }
+
+    let files = input::files(&options).expect("Could not list the input directory");
+    // without files the pipe is read.
+    let mut inputs = if files.is_empty() && options.imports.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        files
+    };
+    // iterate over all the loglines found in the input
+    let mut pipes = input::Pipes::start(&inputs);
+    for path in &inputs {
+        let reader = pipes
+            .open(path, &options)
+            .unwrap_or_else(|e| panic!("Could not open {}: {}", path.display(), e));
+        collector.begin(path);
+        for line in input::lines(reader, path) {
+            let line = line.expect("Could not read line from the input");
+            collector.line(&line);
+            if let Some(e) = collector.spill_error.take() {
+                // the database is removed before the exit
+                drop(collector.spill.take());
+                eprintln!("Could not write to the spill database: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    collector.assign_audio();
+    if !options.imports.is_empty() {
+        // the messages of the logs that are in an export already are kept once
+        let mut archive = import::Archive {
+            meetings: std::mem::take(&mut collector.meetings),
+        };
+        for path in &options.imports {
+            archive.load(path, &options).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            });
+        }
+        collector.meetings = archive.finish();
+        inputs.extend(options.imports.iter().cloned());
+    }
+    let skews = std::mem::take(&mut collector.clock.skews);
+    if let Some(largest) = skews.iter().max_by_key(|s| s.back()) {
+        eprintln!(
+            "{} clock skews of more than {} s, the largest goes back {} s in {} (meeting {})",
+            skews.len(),
+            options.max_clock_skew,
+            largest.back().num_seconds(),
+            largest.source.display(),
+            largest.meeting_id
+        );
+    }
+    let parse_errors = std::mem::take(&mut collector.parse_errors);
+    report_unattributed(&options, &collector.unattributed);
+
+    if let Some(spill) = collector.spill.take().filter(Spill::spilled) {
+        // the lines are read again, their clock skews are reported already
+        collector.clock.max_skew = None;
+        write_spilled(&options, &mut collector, &spill).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        return;
+    }
+    let mut meetings = collector.meetings;
+    let mut pipeline = pipeline(&options);
+    let skipped = prepare(&options, &mut pipeline, &mut meetings);
+    report_skipped(&options, skipped);
+    if options.command == Command::Report {
+        let subject = options.subject.as_deref().unwrap_or_default();
+        meetings = incident::select(&meetings, subject, options.from, options.to);
+        eprintln!(
+            "{} messages from, to or mentioning {} in {} meetings",
+            messages(&meetings),
+            subject,
+            meetings.len()
+        );
+    }
+
+    let mut recordings = (options.recording_base_url.is_some() || options.bbb_url.is_some())
+        .then(|| Recordings::new(options.recording_base_url.clone()));
+    if let (Some(recordings), Some(map)) = (&mut recordings, &options.recording_map) {
+        recordings
+            .load_map(map)
+            .unwrap_or_else(|e| panic!("Could not read {}: {}", map.display(), e));
     }
-}
 
-impl PartialEq for Meeting {
-    fn eq(&self, other: &Self) -> bool {
-        self.meeting_id.eq(&other.meeting_id)
+    write(&options, &inputs, &meetings, recordings.as_ref());
+    // neither the report nor the notification are made for what was not written
+    if options.dry_run {
+        return;
+    }
+
+    if let Some(path) = &options.report {
+        let now = chrono::Utc::now().naive_utc();
+        File::create(path)
+            .and_then(|file| {
+                let mut out = BufWriter::new(file);
+                report::write_junit(&mut out, &options, &inputs, &parse_errors, &meetings, now)?;
+                out.flush()
+            })
+            .unwrap_or_else(|e| panic!("Could not write {}: {}", path.display(), e));
+    }
+
+    if let Some(url) = &options.notify_webhook {
+        notify::send(
+            url,
+            options.notify_template.as_deref(),
+            &options,
+            &meetings,
+            &skews,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
     }
 }
 
-impl Display for Meeting {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "\n{}\n\n{} - {}\n",
-            "#".repeat(80),
-            self.time.format("%d.%m.%Y %H:%M"),
-            self.meeting_id
-        )?;
-        for (_, chat) in &self.chats {
-            write!(f, "{}", chat)?;
+/// The number of messages in all the meetings.
+fn messages(meetings: &HashMap<String, Meeting>) -> usize {
+    meetings
+        .values()
+        .flat_map(|m| m.chats.values())
+        .map(|c| c.messages.len())
+        .sum()
+}
+
+/// The transform stages of the command line, the scorer is started once for all the meetings.
+fn pipeline(options: &Options) -> Pipeline {
+    Pipeline::from_options(options).unwrap_or_else(|e| {
+        eprintln!("Could not start the transform stages: {}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Order the collected meetings, keep only what the options ask for and transform the messages. Returns how many meetings were skipped for having too few messages.
+fn prepare(
+    options: &Options,
+    pipeline: &mut Pipeline,
+    meetings: &mut HashMap<String, Meeting>,
+) -> usize {
+    for meeting in meetings.values_mut() {
+        meeting.resolve_users();
+        meeting.sort_by_time();
+    }
+    if options.verbose {
+        for meeting in model::sorted(meetings) {
+            eprintln!(
+                "{} starts {} according to {}",
+                meeting.meeting_id,
+                meeting.time.format("%d.%m.%Y %H:%M:%S"),
+                meeting.time_source
+            );
         }
-        Ok(())
     }
+
+    if !options.tenants.is_empty() || !options.metadata.is_empty() {
+        meetings.retain(|_, meeting| options.keeps_meeting(meeting));
+    }
+
+    if options.respect_clear {
+        for meeting in meetings.values_mut() {
+            meeting.drop_cleared();
+        }
+    }
+
+    // only keep the requested messages and drop everything that is empty afterwards.
+    if options.filters() {
+        for meeting in meetings.values_mut() {
+            let meeting_id = &meeting.meeting_id;
+            let start = meeting.time;
+            for chat in meeting.chats.values_mut() {
+                let chat_id = &chat.chat_id;
+                chat.messages.retain(|msg| {
+                    options.in_session(start, msg.time) && options.keeps(meeting_id, chat_id, msg)
+                });
+            }
+            meeting.chats.retain(|_, chat| !chat.messages.is_empty());
+        }
+        meetings.retain(|_, meeting| !meeting.chats.is_empty());
+    }
+
+    let before = meetings.len();
+    if let Some(min) = options.min_messages {
+        meetings.retain(|_, meeting| {
+            meeting
+                .chats
+                .values()
+                .map(|c| c.messages.len())
+                .sum::<usize>()
+                >= min
+        });
+    }
+    let skipped = before - meetings.len();
+
+    // sampled from what would be written, before the expensive transforms
+    if let Some(sampling) = options.sampling {
+        let before = meetings.len();
+        sample::select(meetings, sampling, options.seed);
+        eprintln!("sampled {} of {} meetings", meetings.len(), before);
+    }
+
+    // the conversations are found in what is left after the filters, before the texts are changed
+    if options.segment_gap.is_some() || options.segment_marker.is_some() {
+        segment::Segmenter {
+            gap: options.segment_gap,
+            marker: options.segment_marker.clone(),
+        }
+        .assign(meetings);
+    }
+
+    // scores, languages, normalized texts and pseudonyms, only of the messages that passed the filters
+    pipeline.apply(meetings).unwrap_or_else(|e| {
+        eprintln!("Could not transform the messages: {}", e);
+        std::process::exit(1);
+    });
+
+    // without the texts every message would look like a repetition
+    if options.collapse_spam
+        && !options.no_bodies
+        && options.command == Command::Transcript
+        && options.format == Format::Text
+    {
+        for chat in meetings.values_mut().flat_map(|m| m.chats.values_mut()) {
+            spam::collapse(chat);
+        }
+    }
+
+    if let (Some(url), Some(secret)) = (&options.bbb_url, &options.bbb_secret) {
+        let cache = options.bbb_cache.clone().unwrap_or_else(bbb::default_cache);
+        let api = bbb::Api::new(url.clone(), secret.clone(), cache);
+        // the transcripts are still useful without the metadata
+        if let Err(e) = api.enrich(meetings) {
+            eprintln!(
+                "Could not get the meetings from the BigBlueButton API: {}",
+                e
+            );
+        }
+    }
+    skipped
 }
 
-/// A BigBlueButton chat - either private or public. Most of the parameters are omitted so it is mostly a collection of the contained messages.
-#[derive(Eq, Hash, PartialEq, Clone)]
-struct Chat {
-    chat_id: String,
-    messages: Vec<Message>,
+fn report_skipped(options: &Options, skipped: usize) {
+    if let Some(min) = options.min_messages {
+        eprintln!(
+            "skipped {} meetings with fewer than {} messages",
+            skipped, min
+        );
+    }
 }
-impl Display for Chat {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "\n{}\n{}\n", "_".repeat(80), self.chat_id)?;
-        for msg in &self.messages {
-            write!(f, "  {}\n", msg)?;
+
+/// Tell how many chat messages had no meeting or chat id and write them to the `--unattributed` file.
+fn report_unattributed(options: &Options, unattributed: &[Unattributed]) {
+    if unattributed.is_empty() {
+        return;
+    }
+    match &options.unattributed {
+        Some(path) if options.dry_run => eprintln!(
+            "{} chat messages without a meeting or chat id, would be written to {}",
+            unattributed.len(),
+            path.display()
+        ),
+        Some(path) => {
+            eprintln!(
+                "{} chat messages without a meeting or chat id, written to {}",
+                unattributed.len(),
+                path.display()
+            );
+            File::create(path)
+                .and_then(|file| {
+                    let mut out = BufWriter::new(file);
+                    export::write_unattributed(&mut out, unattributed)?;
+                    out.flush()
+                })
+                .unwrap_or_else(|e| panic!("Could not write {}: {}", path.display(), e));
         }
-        Ok(())
+        None => eprintln!(
+            "{} chat messages without a meeting or chat id were left out, --unattributed FILE lists them",
+            unattributed.len()
+        ),
     }
 }
-/// Representing one chatmessage in BigBlueButton
-#[derive(Eq, Hash, PartialEq, Clone)]
-struct Message {
-    author: String,
-    message: String,
-    time: NaiveDateTime,
+
+/// Write the meetings batch by batch as they are read again from the spill database, `--max-memory` only works with the formats written meeting by meeting.
+fn write_spilled(options: &Options, collector: &mut Collector, spill: &Spill) -> io::Result<()> {
+    let mut out = Output::create(options.output.as_deref(), options.compress)?;
+    if options.format == Format::Csv {
+        writeln!(out, "{}", export::CSV_HEADER)?;
+    }
+    let mut pipeline = pipeline(options);
+    let mut skipped = 0;
+    for batch in spill.batches(&collector.meetings) {
+        let mut meetings = spill.load(collector, &batch)?;
+        skipped += prepare(options, &mut pipeline, &mut meetings);
+        match options.format {
+            Format::Csv => export::write_csv_rows(&mut out, model::sorted(&meetings).into_iter())?,
+            _ => export::write_ndjson(&mut out, model::sorted(&meetings).into_iter())?,
+        }
+    }
+    report_skipped(options, skipped);
+    out.finish()
 }
 
-impl Display for Message {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "    {}:{:.>15}: {}",
-            self.time.format("%H:%M"),
-            self.author,
-            self.message
-        )
+/// A number of bytes in the units of `--max-memory`.
+fn size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} bytes", bytes),
+        1024..=1048575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        1048576..=1073741823 => format!("{:.1} MB", bytes as f64 / 1048576.0),
+        _ => format!("{:.1} GB", bytes as f64 / 1073741824.0),
     }
 }
 
-/// Parse the stdin (incomming pipe) and print nicely formatted chatmessages ordered by their meetings and their chatroom.
-///
-/// Note: if the order of the messages isnt consequential the meeting times will be slightly wrong
-fn main() {
-    // get the pipe
-    let stdin = io::stdin();
-    // the collection of meetings.
-    let mut meetings: HashMap<String, Meeting> = HashMap::new();
+/// Print what [`write`] would write with the sizes of the files, by writing them nowhere.
+fn dry_run(
+    options: &Options,
+    inputs: &[PathBuf],
+    meetings: &HashMap<String, Meeting>,
+    recordings: Option<&Recordings>,
+) -> io::Result<()> {
+    let provenance = if options.provenance {
+        Some(provenance::record(options, inputs)?)
+    } else {
+        None
+    };
+    let mut files: Vec<(PathBuf, Option<u64>)> = Vec::new();
+    if let (Command::Sar, Some(archive)) = (options.command, &options.archive) {
+        // the ZIP is only put together when it is written
+        files.push((archive.clone(), None));
+    } else if let Some(split_by) = options.split_by {
+        let planned = split::plan(options, split_by, meetings, recordings, provenance.as_ref())?;
+        // several tenants get an archive or directory each, the placeholder stays in the names
+        let (base, prefix) = match (&options.archive, &options.output_dir) {
+            (Some(archive), _) => (PathBuf::new(), format!("{}:", archive.display())),
+            (None, Some(dir)) => (dir.clone(), String::new()),
+            (None, None) => (PathBuf::new(), String::new()),
+        };
+        for (name, bytes) in planned {
+            let path = if prefix.is_empty() {
+                base.join(name)
+            } else {
+                PathBuf::from(format!("{}{}", prefix, name))
+            };
+            files.push((path, Some(bytes)));
+        }
+    } else {
+        let mut counter = split::Counter::default();
+        let mut out = Output::wrap(&mut counter, options.compress)?;
+        output::render(&mut out, options, meetings, recordings, provenance.as_ref())?;
+        out.finish()?;
+        let path = options
+            .output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("(standard out)"));
+        if let (Some(provenance), Some(output)) = (&provenance, &options.output) {
+            if options.format != Format::Json {
+                files.push((
+                    provenance::sidecar(output),
+                    Some(provenance.pretty(2).len() as u64 + 1),
+                ));
+            }
+        }
+        files.insert(0, (path, Some(counter.0)));
+    }
+    // their sizes are not known before they are written
+    for path in [&options.svg, &options.report]
+        .iter()
+        .filter_map(|p| p.as_ref())
+    {
+        files.push((path.clone(), None));
+    }
 
-    // iterate over all the loglines found in the input
-    for line in stdin.lock().lines() {
-        let line = line.expect("Could not read line from standard in");
-        // skip the first part (date and processinformation and go to the first brace which is the starting of the json log)
-        if let Some(start_pos) = line.find("{") {
-            let data = json::parse(&line[start_pos..]);
-            match data {
-                Ok(data) => {
-                    // First get the time of the message. The timestamp is in milliseconds since epoch.
-                    let secs = data["envelope"]["timestamp"].as_i64().expect(&format!(
-                        "{} is not a number",
-                        &data["envelope"]["timestamp"]
-                    ));
-                    let time = NaiveDateTime::from_timestamp(0, 0) + Duration::milliseconds(secs);
-
-                    // get the meeting_id and create the meeting if it does not exist yet.
-                    let meeting_id = data["envelope"]["routing"]["meetingId"].to_string();
-                    if !meetings.contains_key(&meeting_id) {
-                        let meeting = Meeting {
-                            meeting_id: meeting_id.clone(),
-                            time,
-                            chats: HashMap::new(),
-                        };
-                        println!("inserting: {}", &meeting_id);
-                        meetings.insert(meeting_id.to_string(), meeting);
-                    }
-
-                    // either get the newly created or the already existing meeting
-                    let meeting = meetings
-                        .get_mut(&meeting_id)
-                        .expect("there should be a meeting");
-
-                    // create the chat
-                    let body = &data["core"]["body"];
-                    let chat_id = body["chatId"].to_string();
-
-                    // check if the chat already exists if not create it
-                    if !meeting.chats.contains_key(&chat_id) {
-                        let chat = Chat {
-                            chat_id: chat_id.clone(),
-                            messages: Vec::new(),
-                        };
-                        meeting.chats.insert(chat_id.clone(), chat);
-                    }
-
-                    // get the information on the message
-                    let msg = &body["msg"];
-                    let sender = msg["sender"]["name"].to_string();
-                    let message = msg["message"].to_string();
-                    // add the message to the list
-                    let chat = meeting.chats.get_mut(&chat_id).unwrap();
-                    chat.messages.push(Message {
-                        author: sender.clone(),
-                        message,
-                        time,
-                    });
-                    // every message is twice in the logs which is why the messages are deduped. This could be done more performant at a different place but it was not an issue with my problem.
-                    chat.messages.dedup_by(|s, o| s.message == o.message);
-                }
-                Err(e) => {
-                    // If for some reason the message could not be parsed print the message at the very beginning.
-                    println!("{}\n{}", e, &line[start_pos..])
-                }
+    let out = io::stdout();
+    let mut out = out.lock();
+    writeln!(
+        out,
+        "{} meetings with {} messages from {} inputs, nothing was written",
+        meetings.len(),
+        messages(meetings),
+        inputs.len()
+    )?;
+    let width = files
+        .iter()
+        .map(|(path, _)| path.display().to_string().chars().count())
+        .max()
+        .unwrap_or(0);
+    for (path, bytes) in &files {
+        writeln!(
+            out,
+            "  {:width$}  {}",
+            path.display().to_string(),
+            bytes.map(size).unwrap_or_else(|| "?".to_string()),
+            width = width
+        )?;
+    }
+    let total: u64 = files.iter().filter_map(|(_, bytes)| *bytes).sum();
+    writeln!(out, "{} files, {}", files.len(), size(total))
+}
+
+/// Write the meetings where the command line says.
+fn write(
+    options: &Options,
+    inputs: &[PathBuf],
+    meetings: &HashMap<String, Meeting>,
+    recordings: Option<&Recordings>,
+) {
+    if options.dry_run {
+        dry_run(options, inputs, meetings, recordings).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        return;
+    }
+
+    if let (Command::Sar, Some(archive)) = (options.command, &options.archive) {
+        sar::write(options, inputs, meetings, recordings, archive)
+            .unwrap_or_else(|e| panic!("Could not write {}: {}", archive.display(), e));
+        return;
+    }
+
+    if options.sink.is_some() {
+        talk::post(options, meetings).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        return;
+    }
+
+    // the checksums of the inputs are recorded before anything is written, an output may be one of them
+    let provenance = options.provenance.then(|| {
+        provenance::record(options, inputs).unwrap_or_else(|e| {
+            eprintln!("Could not record the provenance: {}", e);
+            std::process::exit(1);
+        })
+    });
+
+    if let Some(split_by) = options.split_by {
+        let per_tenant = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .is_some_and(|p| p.to_string_lossy().contains(tenant::PLACEHOLDER))
+        };
+        if per_tenant(&options.archive) || per_tenant(&options.output_dir) {
+            // every tenant gets its own archive or directory
+            for (tenant, part) in tenant::split(meetings) {
+                let replace = |path: &PathBuf| {
+                    PathBuf::from(
+                        path.to_string_lossy()
+                            .replace(tenant::PLACEHOLDER, &split::file_name(tenant)),
+                    )
+                };
+                let mut options = options.clone();
+                options.archive = options.archive.as_ref().map(replace);
+                options.output_dir = options.output_dir.as_ref().map(replace);
+                split::write(&options, split_by, &part, recordings, provenance.as_ref())
+                    .expect("Could not write the split output");
             }
         } else {
-            println!("{}", line);
+            split::write(options, split_by, meetings, recordings, provenance.as_ref())
+                .expect("Could not write the split output");
         }
+        return;
     }
 
-    // print everything to stdout
-    println!("{}", meetings.len());
-    for (_, meeting) in meetings {
-        println!("\n\n{}", meeting);
+    if let Some(svg) = &options.svg {
+        File::create(svg)
+            .and_then(|file| {
+                let mut out = BufWriter::new(file);
+                timeline::write_svg(&mut out, meetings)?;
+                out.flush()
+            })
+            .unwrap_or_else(|e| panic!("Could not write {}: {}", svg.display(), e));
+    }
+
+    // print everything to stdout, the pager or the --output
+    let printed = match pager::Pager::start(options) {
+        Some(pager) => {
+            let mut out = BufWriter::new(pager);
+            let printed =
+                output::render(&mut out, options, meetings, recordings, provenance.as_ref())
+                    .and_then(|_| out.flush());
+            let (pager, _) = out.into_parts();
+            pager.wait().and(printed).or_else(|e| {
+                // the pager was quit before the end
+                if e.kind() == io::ErrorKind::BrokenPipe {
+                    Ok(())
+                } else {
+                    Err(e)
+                }
+            })
+        }
+        None => Output::create(options.output.as_deref(), options.compress).and_then(|mut out| {
+            output::render(&mut out, options, meetings, recordings, provenance.as_ref())?;
+            out.finish()
+        }),
+    };
+    printed.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    // the json transcripts carry it themselves
+    if let (Some(provenance), Some(path)) = (&provenance, &options.output) {
+        if options.format != Format::Json {
+            let sidecar = provenance::sidecar(path);
+            File::create(&sidecar)
+                .and_then(|mut file| {
+                    provenance.write_pretty(&mut file, 2)?;
+                    writeln!(file)
+                })
+                .unwrap_or_else(|e| panic!("Could not write {}: {}", sidecar.display(), e));
+        }
     }
 }
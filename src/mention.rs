@@ -0,0 +1,99 @@
+//! The participants addressed with `@Name` in a message, to see who talks to whom.
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::model::{Mention, User};
+
+/// The participants of the meeting whose name follows an `@` in the text.
+///
+/// The names are compared ignoring the case and the longest matching name wins, so `@Anna Maria` is not taken for `@Anna` if both are there. The `@` of an e-mail address does not count. Every participant is in the list once, in the order they are mentioned first.
+pub fn find(text: &str, users: &HashMap<String, User>) -> Vec<Mention> {
+    let mut mentions: Vec<Mention> = Vec::new();
+    if !text.contains('@') {
+        return mentions;
+    }
+    // sorted by id so that the same one of several users with the same name is taken every time
+    let mut users: Vec<(String, &User)> = users
+        .values()
+        .filter(|u| !u.name.trim().is_empty())
+        .map(|u| (u.name.to_lowercase(), u))
+        .collect();
+    users.sort_by(|(_, a), (_, b)| a.user_id.cmp(&b.user_id));
+    let text = text.to_lowercase();
+    let mut previous = None;
+    for (i, c) in text.char_indices() {
+        if c == '@' && !previous.is_some_and(char::is_alphanumeric) {
+            let rest = &text[i + 1..];
+            let found = users
+                .iter()
+                .filter(|(name, _)| {
+                    rest.starts_with(name.as_str())
+                        && !rest[name.len()..]
+                            .chars()
+                            .next()
+                            .is_some_and(char::is_alphanumeric)
+                })
+                .min_by_key(|(name, _)| Reverse(name.len()));
+            if let Some((_, user)) = found {
+                if !mentions.iter().any(|m| m.user_id == user.user_id) {
+                    mentions.push(Mention {
+                        user_id: user.user_id.clone(),
+                        name: user.name.clone(),
+                        ext_id: user.ext_id.clone(),
+                    });
+                }
+            }
+        }
+        previous = Some(c);
+    }
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users(names: &[(&str, &str)]) -> HashMap<String, User> {
+        names
+            .iter()
+            .map(|&(user_id, name)| {
+                let user = User {
+                    user_id: user_id.to_string(),
+                    name: name.to_string(),
+                    ext_id: None,
+                    role: None,
+                    guest: false,
+                    sessions: Vec::new(),
+                };
+                (user_id.to_string(), user)
+            })
+            .collect()
+    }
+
+    fn names(text: &str, users: &HashMap<String, User>) -> Vec<String> {
+        find(text, users).into_iter().map(|m| m.name).collect()
+    }
+
+    #[test]
+    fn a_name_followed_by_punctuation() {
+        let users = users(&[("w_1", "Bob"), ("w_2", "Alice")]);
+        assert_eq!(names("@bob, what do you think?", &users), ["Bob"]);
+        assert_eq!(names("thanks @Alice! and @Bob.", &users), ["Alice", "Bob"]);
+        assert_eq!(names("@Bob @bob", &users), ["Bob"]);
+    }
+
+    #[test]
+    fn the_longest_name_with_a_space_wins() {
+        let users = users(&[("w_1", "Anna"), ("w_2", "Anna Maria")]);
+        assert_eq!(names("@Anna Maria please", &users), ["Anna Maria"]);
+        assert_eq!(names("@Anna Marian, no", &users), ["Anna"]);
+    }
+
+    #[test]
+    fn no_mentions() {
+        let users = users(&[("w_1", "Bob"), ("w_2", "Eve")]);
+        assert!(names("write to bob@example.org", &users).is_empty());
+        assert!(names("@Bobby", &users).is_empty());
+        assert!(names("@Carol", &users).is_empty());
+        assert!(names("Bob without an at", &users).is_empty());
+    }
+}
@@ -0,0 +1,833 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    hash::Hash,
+    str::FromStr,
+};
+
+use chrono::{Duration, NaiveDateTime};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    checksum::hex,
+    export,
+    header::{self, HeaderField},
+    mention,
+};
+
+/// The id of the chat every participant of a BigBlueButton meeting can read.
+pub const PUBLIC_CHAT: &str = "MAIN-PUBLIC-GROUP-CHAT";
+
+/// The meetings in the order they are written: by their start and their id.
+pub fn sorted(meetings: &HashMap<String, Meeting>) -> Vec<&Meeting> {
+    let mut sorted: Vec<&Meeting> = meetings.values().collect();
+    sorted.sort_by(|a, b| (a.time, &a.meeting_id).cmp(&(b.time, &b.meeting_id)));
+    sorted
+}
+
+/// Representing a BigBlueButton meeting most of the things are omitted and just meeting_id the approximate time and the (private and public)chats.
+#[derive(Clone)]
+pub struct Meeting {
+    pub meeting_id: String,
+    pub time: NaiveDateTime,
+    /// Where the time of the meeting comes from.
+    pub time_source: TimeSource,
+    pub chats: HashMap<String, Chat>,
+    /// The users that joined the meeting keyed by their internal user id.
+    pub users: HashMap<String, User>,
+    /// The times the recording was started (`true`) or stopped (`false`).
+    pub recording_changes: Vec<(NaiveDateTime, bool)>,
+    /// The times the public chat was locked (`true`) or unlocked (`false`) for the viewers.
+    pub public_chat_locks: Vec<(NaiveDateTime, bool)>,
+    /// The times a moderator cleared the public chat.
+    pub public_chat_clears: Vec<ChatClear>,
+    /// The times polls were started together with their question (or their id if the question is not logged).
+    pub polls: Vec<(NaiveDateTime, String)>,
+    /// The name of the meeting as the BigBlueButton API knows it.
+    pub name: Option<String>,
+    /// The meeting id the room was created with, e.g. by Moodle. Every session of a room has the same one but another internal meeting id.
+    pub external_id: Option<String>,
+    /// The metadata the meeting was created with (e.g. `bbb-context-name` for the course or `bbb-origin`).
+    pub metadata: BTreeMap<String, String>,
+    /// The url of the presentation playback of the recording.
+    pub playback_url: Option<String>,
+    /// When bbb-web created the meeting through the API.
+    pub created: Option<NaiveDateTime>,
+    /// The number of the audio conference of the meeting.
+    pub voice_bridge: Option<String>,
+    /// The names of the participants joining through the API and when (from the bbb-web log).
+    pub api_joins: Vec<(NaiveDateTime, String)>,
+    /// Who was in the audio conference when (from the FreeSWITCH log).
+    pub audio: Vec<AudioSession>,
+    /// The tenant (e.g. the faculty) of the server the meeting ran on, see `--tenant-map`.
+    pub tenant: Option<String>,
+    /// The welcome message of the chat the meeting was created with, e.g. the rules of an exam.
+    pub welcome: Option<String>,
+    /// The welcome message only the moderators saw.
+    pub moderator_message: Option<String>,
+}
+
+impl Meeting {
+    pub fn new(meeting_id: String, time: NaiveDateTime) -> Self {
+        Meeting {
+            meeting_id,
+            time,
+            time_source: TimeSource::EarliestEvent,
+            chats: HashMap::new(),
+            users: HashMap::new(),
+            recording_changes: Vec::new(),
+            public_chat_locks: Vec::new(),
+            public_chat_clears: Vec::new(),
+            polls: Vec::new(),
+            name: None,
+            external_id: None,
+            metadata: BTreeMap::new(),
+            playback_url: None,
+            created: None,
+            voice_bridge: None,
+            api_joins: Vec::new(),
+            audio: Vec::new(),
+            tenant: None,
+            welcome: None,
+            moderator_message: None,
+        }
+    }
+
+    /// A short handle for the meeting that can be typed: the day it started and the first 6 hex digits of SHA-256 of its id, e.g. `2021-03-01-4f1c9a`.
+    ///
+    /// The day is taken from the milliseconds the internal meeting ids of BigBlueButton end with, so the handle stays the same whichever lines of the meeting are read.
+    pub fn handle(&self) -> String {
+        let day = self
+            .meeting_id
+            .rsplit_once('-')
+            .and_then(|(_, millis)| millis.parse::<i64>().ok())
+            .map(|millis| NaiveDateTime::from_timestamp(0, 0) + Duration::milliseconds(millis))
+            .unwrap_or(self.time);
+        let hash = hex(&Sha256::digest(self.meeting_id.as_bytes()));
+        format!("{}-{}", day.format("%Y-%m-%d"), &hash[..6])
+    }
+
+    /// Whether the meeting has this internal meeting id or [handle](Meeting::handle).
+    pub fn is(&self, id: &str) -> bool {
+        self.meeting_id == id || self.handle() == id
+    }
+
+    /// A copy of the meeting without any chats.
+    pub fn without_chats(&self) -> Self {
+        Meeting {
+            meeting_id: self.meeting_id.clone(),
+            time: self.time,
+            time_source: self.time_source,
+            chats: HashMap::new(),
+            users: self.users.clone(),
+            recording_changes: self.recording_changes.clone(),
+            public_chat_locks: self.public_chat_locks.clone(),
+            public_chat_clears: self.public_chat_clears.clone(),
+            polls: self.polls.clone(),
+            name: self.name.clone(),
+            external_id: self.external_id.clone(),
+            metadata: self.metadata.clone(),
+            playback_url: self.playback_url.clone(),
+            created: self.created,
+            voice_bridge: self.voice_bridge.clone(),
+            api_joins: self.api_joins.clone(),
+            audio: self.audio.clone(),
+            tenant: self.tenant.clone(),
+            welcome: self.welcome.clone(),
+            moderator_message: self.moderator_message.clone(),
+        }
+    }
+
+    /// The chats in the order they are written: the public chat first and the private chats by their id.
+    pub fn sorted_chats(&self) -> Vec<&Chat> {
+        let mut chats: Vec<&Chat> = self.chats.values().collect();
+        chats.sort_by_key(|c| (c.chat_id != PUBLIC_CHAT, &c.chat_id));
+        chats
+    }
+
+    /// The messages of the chat in their order, the public chat with the times it was locked, unlocked and cleared in between.
+    pub fn entries<'a>(&'a self, chat: &'a Chat) -> Vec<ChatEntry<'a>> {
+        let mut marks = Vec::new();
+        if chat.chat_id == PUBLIC_CHAT {
+            marks.extend(
+                self.public_chat_locks
+                    .iter()
+                    .map(|(time, locked)| (*time, ChatEntry::Lock(*time, *locked))),
+            );
+            marks.extend(
+                self.public_chat_clears
+                    .iter()
+                    .map(|clear| (clear.time, ChatEntry::Clear(clear))),
+            );
+            marks.sort_by_key(|(time, _)| *time);
+        }
+        let mut marks = marks.into_iter().peekable();
+        let mut entries = Vec::new();
+        for msg in &chat.messages {
+            while let Some((_, mark)) = marks.next_if(|(time, _)| *time <= msg.time) {
+                entries.push(mark);
+            }
+            entries.push(ChatEntry::Message(msg));
+        }
+        entries.extend(marks.map(|(_, mark)| mark));
+        entries
+    }
+
+    /// Drop the messages of the public chat from before it was cleared the last time, what the participants still saw at the end.
+    pub fn drop_cleared(&mut self) {
+        let Some(cleared) = self.public_chat_clears.iter().map(|c| c.time).max() else {
+            return;
+        };
+        if let Some(chat) = self.chats.get_mut(PUBLIC_CHAT) {
+            chat.messages.retain(|msg| msg.time >= cleared);
+            if chat.messages.is_empty() {
+                self.chats.remove(PUBLIC_CHAT);
+            }
+        }
+    }
+
+    /// The welcome messages with their label and their lines, as they head the transcripts.
+    pub fn welcome_messages(&self) -> Vec<(&'static str, Vec<&str>)> {
+        [
+            ("Welcome", &self.welcome),
+            ("Moderators only", &self.moderator_message),
+        ]
+        .iter()
+        .filter_map(|&(label, text)| text.as_deref().map(|text| (label, text_lines(text))))
+        .collect()
+    }
+
+    /// How many people joined, the sessions of one person (the same external id or name) count once.
+    pub fn participants(&self) -> usize {
+        self.users
+            .values()
+            .map(|u| u.ext_id.as_deref().unwrap_or(&u.name))
+            .collect::<HashSet<&str>>()
+            .len()
+    }
+
+    /// The users ordered by their internal id.
+    pub fn sorted_users(&self) -> Vec<&User> {
+        let mut users: Vec<&User> = self.users.values().collect();
+        users.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+        users
+    }
+
+    /// Order everything by its time instead of the order the lines were read in.
+    ///
+    /// The sorts are stable so events with the same time keep their order.
+    pub fn sort_by_time(&mut self) {
+        for chat in self.chats.values_mut() {
+            chat.messages.sort_by_key(|m| m.time);
+        }
+        for user in self.users.values_mut() {
+            user.sessions.sort_by_key(|(joined, _)| *joined);
+        }
+        self.recording_changes.sort_by_key(|(time, _)| *time);
+        self.public_chat_locks.sort_by_key(|(time, _)| *time);
+        // the lock settings also change for the microphones, the webcams and so on, only the changes of the chat are kept
+        let mut locked = false;
+        self.public_chat_locks.retain(|(_, l)| {
+            let changed = *l != locked;
+            locked = *l;
+            changed
+        });
+        self.public_chat_clears.sort_by_key(|clear| clear.time);
+        self.polls.sort_by_key(|(time, _)| *time);
+        self.api_joins.sort_by_key(|(time, _)| *time);
+        self.audio.sort_by_key(|s| s.joined);
+    }
+
+    /// Fill in the external user ids, the roles, the guest flags and the mentions of all messages from the users that joined the meeting.
+    ///
+    /// This is done after all the lines are read as the join event is not necessarily read before the messages. The meetings of imported exports know no users, their messages keep the mentions they were exported with.
+    pub fn resolve_users(&mut self) {
+        let users = &self.users;
+        for chat in self.chats.values_mut() {
+            for msg in &mut chat.messages {
+                let user = users.get(&msg.author_id);
+                if msg.ext_id.is_none() {
+                    msg.ext_id = user.and_then(|u| u.ext_id.clone());
+                }
+                if msg.role.is_none() {
+                    msg.role = user.and_then(|u| u.role.clone());
+                }
+                msg.guest |= user.is_some_and(|u| u.guest);
+                if !users.is_empty() {
+                    msg.mentions = mention::find(&msg.message, users);
+                }
+            }
+        }
+    }
+}
+
+/// Where the start of a meeting is known from, the best first.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimeSource {
+    /// The `MeetingCreatedEvtMsg` of akka-apps.
+    Created,
+    /// The create call in the bbb-web log.
+    Api,
+    /// No creation was logged, the earliest event of the meeting.
+    EarliestEvent,
+}
+
+impl Display for TimeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TimeSource::Created => "MeetingCreatedEvtMsg",
+            TimeSource::Api => "the create call of bbb-web",
+            TimeSource::EarliestEvent => "the earliest event",
+        })
+    }
+}
+
+impl Hash for Meeting {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.meeting_id.hash(state);
+    }
+}
+
+impl PartialEq for Meeting {
+    fn eq(&self, other: &Self) -> bool {
+        self.meeting_id.eq(&other.meeting_id)
+    }
+}
+
+/// Which times of the messages are shown in the transcripts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimeKind {
+    /// The time of day.
+    Wall,
+    /// The time since the start of the meeting like `+01:12:33`, to find the message in the recording.
+    Offset,
+    /// The time of day followed by the offset.
+    Both,
+}
+
+impl FromStr for TimeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wall" => Ok(TimeKind::Wall),
+            "offset" => Ok(TimeKind::Offset),
+            "both" => Ok(TimeKind::Both),
+            _ => Err(format!("unknown time display: {}", s)),
+        }
+    }
+}
+
+/// How exact the times of the messages are shown in the transcripts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimePrecision {
+    /// `11:02`, the offset always has its seconds.
+    Minutes,
+    /// `11:02:17`
+    Seconds,
+    /// `11:02:17.042`, to see the order of a rapid exchange.
+    Millis,
+}
+
+impl FromStr for TimePrecision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minutes" => Ok(TimePrecision::Minutes),
+            "seconds" => Ok(TimePrecision::Seconds),
+            "millis" => Ok(TimePrecision::Millis),
+            _ => Err(format!("unknown time precision: {}", s)),
+        }
+    }
+}
+
+/// How the times of the messages are shown in the transcripts. The structured formats always have the milliseconds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimeDisplay {
+    pub kind: TimeKind,
+    pub precision: TimePrecision,
+}
+
+impl Default for TimeDisplay {
+    fn default() -> Self {
+        TimeDisplay {
+            kind: TimeKind::Wall,
+            precision: TimePrecision::Minutes,
+        }
+    }
+}
+
+impl TimeDisplay {
+    /// Show the time of something in the meeting, `wall` is the format of the time of day to the minute.
+    pub fn format(self, meeting: &Meeting, time: NaiveDateTime, wall: &str) -> String {
+        let wall = match self.precision {
+            TimePrecision::Minutes => wall.to_string(),
+            TimePrecision::Seconds => format!("{}:%S", wall),
+            TimePrecision::Millis => format!("{}:%S%.3f", wall),
+        };
+        let offset = || {
+            let millis = (time - meeting.time).num_milliseconds();
+            let sign = if millis < 0 { '-' } else { '+' };
+            let seconds = millis.abs() / 1000;
+            let offset = format!(
+                "{}{:02}:{:02}:{:02}",
+                sign,
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            );
+            match self.precision {
+                TimePrecision::Millis => format!("{}.{:03}", offset, millis.abs() % 1000),
+                _ => offset,
+            }
+        };
+        match self.kind {
+            TimeKind::Wall => time.format(&wall).to_string(),
+            TimeKind::Offset => offset(),
+            TimeKind::Both => format!("{} {}", time.format(&wall), offset()),
+        }
+    }
+}
+
+/// The text transcript of a meeting with the message times shown in some way, the [`Display`] of [`Meeting`] shows the time of day.
+///
+/// With header fields the heading only names the meeting and the fields follow it, see [`crate::header`].
+pub struct Transcript<'a>(
+    pub &'a Meeting,
+    pub TimeDisplay,
+    pub Option<&'a [HeaderField]>,
+);
+
+/// A message of the transcript with its time already formatted.
+struct Timed<'a>(&'a Message, String);
+
+impl Display for Timed<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_message(f, self.0, &self.1)
+    }
+}
+
+impl Display for Meeting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Transcript(self, TimeDisplay::default(), None).fmt(f)
+    }
+}
+
+impl Display for Transcript<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Transcript(meeting, times, header) = *self;
+        if let Some(fields) = header {
+            writeln!(
+                f,
+                "\n{}\n\n{} ({})",
+                "#".repeat(80),
+                meeting.meeting_id,
+                meeting.handle()
+            )?;
+            for (label, value) in header::values(meeting, fields, None) {
+                writeln!(f, "{}: {}", label, value)?;
+            }
+        } else {
+            write!(
+                f,
+                "\n{}\n\n{} - {} ({})",
+                "#".repeat(80),
+                meeting.time.format("%d.%m.%Y %H:%M"),
+                meeting.meeting_id,
+                meeting.handle()
+            )?;
+            if let Some(name) = &meeting.name {
+                write!(f, " - {}", name)?;
+            }
+            writeln!(f)?;
+        }
+        for (label, lines) in meeting.welcome_messages() {
+            writeln!(f, "{}: {}", label, lines.join("\n  "))?;
+        }
+        for chat in meeting.sorted_chats() {
+            write!(f, "\n{}\n{}\n", "_".repeat(80), chat.chat_id)?;
+            let mut segment = None;
+            for entry in meeting.entries(chat) {
+                match entry {
+                    ChatEntry::Message(msg) => {
+                        if let Some(number) = segment_start(&mut segment, msg) {
+                            writeln!(f, "  -- segment {} --", number)?;
+                        }
+                        let time = times.format(meeting, msg.time, "%H:%M");
+                        let message = Timed(msg, time).to_string();
+                        writeln!(f, "  {}", message.replace('\n', "\n  "))?
+                    }
+                    ChatEntry::Lock(time, locked) => writeln!(
+                        f,
+                        "      {}: -- {} --",
+                        times.format(meeting, time, "%H:%M"),
+                        lock_text(locked)
+                    )?,
+                    ChatEntry::Clear(clear) => writeln!(
+                        f,
+                        "      {}: -- {} --",
+                        times.format(meeting, clear.time, "%H:%M"),
+                        clear.text()
+                    )?,
+                }
+            }
+        }
+        let mut spam = meeting
+            .sorted_chats()
+            .into_iter()
+            .flat_map(|c| c.messages.iter().map(move |m| (c, m)))
+            .filter_map(|(c, m)| m.repeated.as_ref().map(|r| (c, m, r)))
+            .peekable();
+        if spam.peek().is_some() {
+            write!(f, "\n{}\nSpam\n", "_".repeat(80))?;
+            for (chat, msg, repeated) in spam {
+                writeln!(
+                    f,
+                    "  {} repeated \"{}\" {} times at {} in {}",
+                    msg.author,
+                    msg.message,
+                    repeated.count,
+                    msg.time.format("%H:%M"),
+                    chat.chat_id
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A line of a chat transcript.
+pub enum ChatEntry<'a> {
+    Message(&'a Message),
+    /// The public chat was locked (`true`) or unlocked (`false`) for the viewers at that time.
+    Lock(NaiveDateTime, bool),
+    Clear(&'a ChatClear),
+}
+
+/// A moderator cleared the public chat, the messages before were gone for everyone in the meeting.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ChatClear {
+    pub time: NaiveDateTime,
+    /// The internal user id of the moderator.
+    pub user_id: String,
+    pub name: String,
+    pub ext_id: Option<String>,
+}
+
+impl ChatClear {
+    /// What the transcripts say where the chat was cleared.
+    pub fn text(&self) -> String {
+        format!("the public chat was cleared by {}", self.name)
+    }
+}
+
+/// What the transcripts say when the public chat is locked or unlocked.
+pub fn lock_text(locked: bool) -> &'static str {
+    if locked {
+        "the public chat is locked for the viewers"
+    } else {
+        "the public chat is unlocked"
+    }
+}
+
+/// A participant of a meeting as announced by the join event.
+#[derive(Eq, Hash, PartialEq, Clone)]
+pub struct User {
+    pub user_id: String,
+    pub name: String,
+    /// The external user id (e.g. the LTI/Moodle user id) if the meeting was joined through an external system.
+    pub ext_id: Option<String>,
+    /// `MODERATOR` or `VIEWER` as the join event tells it.
+    pub role: Option<String>,
+    /// Whether BigBlueButton marked the user as a guest.
+    pub guest: bool,
+    /// When the user joined and left the meeting. The end is missing if the leave event was not seen.
+    pub sessions: Vec<(NaiveDateTime, Option<NaiveDateTime>)>,
+}
+
+/// A participant in the audio conference of a meeting.
+#[derive(Eq, Hash, PartialEq, Clone)]
+pub struct AudioSession {
+    /// The name the participant called in with.
+    pub name: String,
+    pub joined: NaiveDateTime,
+    /// Missing if the leave event was not seen.
+    pub left: Option<NaiveDateTime>,
+}
+
+/// A BigBlueButton chat - either private or public. Most of the parameters are omitted so it is mostly a collection of the contained messages.
+#[derive(Eq, Hash, PartialEq, Clone)]
+pub struct Chat {
+    pub chat_id: String,
+    pub messages: Vec<Message>,
+}
+
+impl Chat {
+    /// A copy of the chat without any messages.
+    pub fn without_messages(&self) -> Self {
+        Chat {
+            chat_id: self.chat_id.clone(),
+            messages: Vec::new(),
+        }
+    }
+}
+
+impl Display for Chat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\n{}\n{}\n", "_".repeat(80), self.chat_id)?;
+        for msg in &self.messages {
+            // the continuation lines of a message need the same indentation as the message
+            writeln!(f, "  {}", msg.to_string().replace('\n', "\n  "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Representing one chatmessage in BigBlueButton
+#[derive(Eq, Hash, PartialEq, Clone)]
+pub struct Message {
+    /// Stays the same in every run, see [`Message::derive_id`].
+    pub id: String,
+    pub author: String,
+    /// The internal user id of the author.
+    pub author_id: String,
+    /// The external user id of the author - see [`User::ext_id`].
+    pub ext_id: Option<String>,
+    /// The role of the author, `MODERATOR` or `VIEWER` - see [`User::role`].
+    pub role: Option<String>,
+    /// Whether the author was a guest - see [`User::guest`].
+    pub guest: bool,
+    pub message: String,
+    pub time: NaiveDateTime,
+    /// Set if this message stands for a flood of near-identical messages.
+    pub repeated: Option<Repeated>,
+    /// The ISO 639-3 code of the language of the text, only detected on request.
+    pub language: Option<&'static str>,
+    /// What the `--score-cmd` answered for the text.
+    pub score: Option<Score>,
+    /// The log line the message was read from, only kept if requested for debugging.
+    pub raw: Option<String>,
+    /// Set if the text was left out with `--no-bodies`.
+    pub withheld: Option<Withheld>,
+    /// The participants addressed with `@Name`, see [`crate::mention::find`].
+    pub mentions: Vec<Mention>,
+    /// The number of the conversation in its chat, only with `--segment-gap` or `--segment-marker`.
+    pub segment: Option<usize>,
+    /// The size in bytes of a text longer than `--max-message-bytes`, only with `--oversized flag`.
+    pub oversized: Option<usize>,
+}
+
+/// A participant that is addressed in a message.
+#[derive(Eq, Hash, PartialEq, Clone)]
+pub struct Mention {
+    pub user_id: String,
+    pub name: String,
+    pub ext_id: Option<String>,
+}
+
+/// The counts of a text that is not written.
+#[derive(Eq, Hash, PartialEq, Clone, Copy)]
+pub struct Withheld {
+    pub words: usize,
+    pub characters: usize,
+}
+
+/// The score of a message, never `NaN` so messages stay comparable and hashable.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Score(pub f64);
+
+impl Eq for Score {}
+
+impl Hash for Score {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+/// How often a message was repeated in a flood.
+#[derive(Eq, Hash, PartialEq, Clone)]
+pub struct Repeated {
+    pub count: usize,
+    /// The time of the last repetition.
+    pub last: NaiveDateTime,
+}
+
+/// The line breaks of a message are either newlines or `<br/>` tags (depending on the BigBlueButton version).
+const LINE_BREAKS: [&str; 4] = ["<br/>", "<br />", "<br>", "\r\n"];
+
+/// The lines of a text of the chat, either separated by newlines or by `<br/>` tags.
+pub fn text_lines(text: &str) -> Vec<&str> {
+    let mut lines = vec![text];
+    for separator in LINE_BREAKS.iter().copied().chain(Some("\n")) {
+        lines = lines.iter().flat_map(|l| l.split(separator)).collect();
+    }
+    lines
+}
+
+/// The number of the segment the message starts, `None` if it continues the one of the message before.
+pub fn segment_start(current: &mut Option<usize>, msg: &Message) -> Option<usize> {
+    if msg.segment.is_none() || msg.segment == *current {
+        return None;
+    }
+    *current = msg.segment;
+    msg.segment
+}
+
+impl Message {
+    /// The id of a message without one from BigBlueButton: the first 16 hex digits of SHA-256 of its meeting, chat, author, time and text.
+    pub fn derive_id(
+        meeting_id: &str,
+        chat_id: &str,
+        author_id: &str,
+        time: NaiveDateTime,
+        text: &str,
+    ) -> String {
+        let mut hasher = Sha256::new();
+        for part in [
+            meeting_id,
+            chat_id,
+            author_id,
+            &export::timestamp(&time),
+            text,
+        ] {
+            hasher.update(part.as_bytes());
+            // the parts are separated so that moving a character from one to the next changes the id
+            hasher.update([0]);
+        }
+        hex(&hasher.finalize())[..16].to_string()
+    }
+
+    /// A message with nothing but its author, text and time, for the tests.
+    #[cfg(test)]
+    pub(crate) fn example(
+        author_id: &str,
+        author: &str,
+        text: &str,
+        time: NaiveDateTime,
+    ) -> Message {
+        Message {
+            id: Message::derive_id("meeting", PUBLIC_CHAT, author_id, time, text),
+            author: author.to_string(),
+            author_id: author_id.to_string(),
+            ext_id: None,
+            role: None,
+            guest: false,
+            message: text.to_string(),
+            time,
+            repeated: None,
+            language: None,
+            score: None,
+            raw: None,
+            withheld: None,
+            mentions: Vec::new(),
+            segment: None,
+            oversized: None,
+        }
+    }
+
+    /// Whether the author was a moderator, authors without a known role are taken as viewers.
+    pub fn is_moderator(&self) -> bool {
+        self.role.as_deref() == Some("MODERATOR")
+    }
+
+    /// The name of the author as the transcripts show it, moderators are marked with a star.
+    pub fn display_author(&self) -> String {
+        if self.is_moderator() {
+            format!("★{}", self.author)
+        } else {
+            self.author.clone()
+        }
+    }
+
+    /// The lines of the message text.
+    pub fn lines(&self) -> Vec<&str> {
+        text_lines(&self.message)
+    }
+
+    /// The number of words of the message text.
+    pub fn words(&self) -> usize {
+        if let Some(withheld) = self.withheld {
+            return withheld.words;
+        }
+        self.lines()
+            .iter()
+            .map(|line| line.split_whitespace().count())
+            .sum()
+    }
+
+    /// The number of characters of the message text without the line breaks.
+    pub fn characters(&self) -> usize {
+        if let Some(withheld) = self.withheld {
+            return withheld.characters;
+        }
+        self.lines().iter().map(|line| line.chars().count()).sum()
+    }
+
+    /// Keep only the first `max` lines of the message and note how many were dropped.
+    pub fn truncate_lines(&mut self, max: usize) {
+        let lines = self.lines();
+        if lines.len() > max {
+            let dropped = lines.len() - max;
+            self.message = format!("{}\n… ({} more lines)", lines[..max].join("\n"), dropped);
+        }
+    }
+}
+
+/// Write the lines of a message, indenting the continuation lines to the column of the first line.
+fn write_lines(f: &mut std::fmt::Formatter<'_>, prefix: &str, msg: &Message) -> std::fmt::Result {
+    let indent = " ".repeat(prefix.chars().count());
+    for (i, line) in msg.lines().iter().enumerate() {
+        if i == 0 {
+            write!(f, "{}{}", prefix, line)?;
+        } else {
+            write!(f, "\n{}{}", indent, line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a message with its time the way the transcripts show it.
+fn write_message(f: &mut std::fmt::Formatter<'_>, msg: &Message, time: &str) -> std::fmt::Result {
+    let prefix = format!("    {}:{:.>15}: ", time, msg.display_author());
+    write_lines(f, &prefix, msg)?;
+    if let Some(repeated) = &msg.repeated {
+        let span = repeated.last - msg.time;
+        if span < Duration::minutes(1) {
+            write!(f, " (×{} within {} s)", repeated.count, span.num_seconds())?;
+        } else {
+            // rounded up to full minutes
+            let minutes = (span.num_seconds() + 59) / 60;
+            write!(f, " (×{} within {} min)", repeated.count, minutes)?;
+        }
+    }
+    Ok(())
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_message(f, self, &self.time.format("%H:%M").to_string())
+    }
+}
+
+/// A message on one line together with the meeting and the chat it belongs to.
+pub struct ContextLine<'a>(
+    pub &'a Meeting,
+    pub &'a Chat,
+    pub &'a Message,
+    pub TimeDisplay,
+);
+
+impl Display for ContextLine<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ContextLine(meeting, chat, msg, times) = *self;
+        let prefix = format!(
+            "{} {} - {} - {:.>15}: ",
+            times.format(meeting, msg.time, "%d.%m.%Y %H:%M"),
+            meeting.meeting_id,
+            chat.chat_id,
+            msg.display_author(),
+        );
+        write_lines(f, &prefix, msg)
+    }
+}
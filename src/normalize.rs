@@ -0,0 +1,89 @@
+//! Normalization of the message texts for consumers that need predictable text.
+use std::{io, str::FromStr};
+
+use crate::{model::Message, transform::MessageTransform};
+
+/// Which normalizations are applied to the message texts.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct Normalization {
+    /// Replace all runs of whitespace (including line breaks) by a single space and trim the text.
+    pub whitespace: bool,
+    /// Remove emoji.
+    pub strip_emoji: bool,
+    /// Transliterate everything to ASCII.
+    pub ascii: bool,
+    /// Cut the text after this many characters and add an ellipsis.
+    pub max_length: Option<usize>,
+}
+
+impl FromStr for Normalization {
+    type Err = String;
+
+    /// Parse a comma separated list like `whitespace,strip-emoji,ascii,max-length=200`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut normalization = Normalization::default();
+        for item in s.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            match item.split_once('=') {
+                None if item == "whitespace" => normalization.whitespace = true,
+                None if item == "strip-emoji" => normalization.strip_emoji = true,
+                None if item == "ascii" => normalization.ascii = true,
+                Some(("max-length", length)) => {
+                    normalization.max_length = Some(
+                        length
+                            .parse()
+                            .map_err(|e| format!("max-length {}: {}", length, e))?,
+                    )
+                }
+                _ => return Err(format!("unknown normalization: {}", item)),
+            }
+        }
+        Ok(normalization)
+    }
+}
+
+/// Check if a character is an emoji or one of the invisible characters that are used to combine emoji.
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF // emoticons, pictographs, transport, flags, ...
+        | 0x2600..=0x27BF // miscellaneous symbols and dingbats
+        | 0x2B00..=0x2BFF // arrows and stars
+        | 0xFE00..=0xFE0F // variation selectors
+        | 0x200D // zero width joiner
+        | 0xE0020..=0xE007F // tags
+    )
+}
+
+impl Normalization {
+    pub fn is_empty(&self) -> bool {
+        *self == Normalization::default()
+    }
+
+    /// Apply the normalizations to a text.
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = if self.strip_emoji {
+            text.chars().filter(|c| !is_emoji(*c)).collect()
+        } else {
+            text.to_string()
+        };
+        if self.ascii {
+            text = deunicode::deunicode(&text);
+        }
+        if self.whitespace {
+            text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        if let Some(max_length) = self.max_length {
+            if let Some((cut, _)) = text.char_indices().nth(max_length) {
+                text.truncate(cut);
+                text.push_str(if self.ascii { "..." } else { "…" });
+            }
+        }
+        text
+    }
+}
+
+impl MessageTransform for Normalization {
+    fn message(&mut self, msg: &mut Message) -> io::Result<bool> {
+        msg.message = self.apply(&msg.message);
+        Ok(true)
+    }
+}
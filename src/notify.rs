@@ -0,0 +1,97 @@
+//! Announcing the result of a run on a Slack or Teams incoming webhook.
+use std::{collections::HashMap, fs, io, path::Path};
+
+use json::object;
+
+use crate::{
+    cli::Options,
+    collect::Skew,
+    deliver::{Delivery, Post},
+    model::Meeting,
+    spam, stats,
+};
+
+/// The message if no template is given.
+const DEFAULT_TEMPLATE: &str =
+    "chatter: {meetings} meetings with {messages} messages, {floods} floods and {anomalies} anomalies - {exports}";
+
+/// The placeholders of the template and their values.
+fn values(
+    options: &Options,
+    meetings: &HashMap<String, Meeting>,
+    skews: &[Skew],
+) -> Vec<(&'static str, String)> {
+    let (mut count, mut floods) = (0, 0);
+    for chat in meetings.values().flat_map(|m| m.chats.values()) {
+        count += chat.messages.len();
+        // the floods are only collapsed for the text transcript, collapsing an already collapsed chat keeps them
+        let mut chat = chat.clone();
+        spam::collapse(&mut chat);
+        floods += chat
+            .messages
+            .iter()
+            .filter(|m| m.repeated.is_some())
+            .count();
+    }
+    let exports = match (&options.archive, &options.output_dir, options.sink) {
+        (Some(archive), _, _) => archive.display().to_string(),
+        (None, Some(dir), _) => dir.display().to_string(),
+        (None, None, Some(_)) => options.talk_conversation.clone().unwrap_or_default(),
+        (None, None, None) => "standard out".to_string(),
+    };
+    vec![
+        ("{meetings}", meetings.len().to_string()),
+        ("{messages}", count.to_string()),
+        ("{floods}", floods.to_string()),
+        (
+            "{anomalies}",
+            stats::anomalies(meetings, options).len().to_string(),
+        ),
+        ("{exports}", exports),
+        ("{skews}", skews.len().to_string()),
+    ]
+}
+
+/// The body of the webhook request.
+///
+/// A template that is a JSON document (e.g. a Teams card) is sent as it is with the placeholders replaced by JSON strings, any other template is the text of the message.
+fn payload(template: &str, values: &[(&str, String)]) -> String {
+    let is_json = template.trim_start().starts_with('{');
+    let mut text = template.to_string();
+    for (placeholder, value) in values {
+        let value = if is_json {
+            let quoted = json::stringify(value.as_str());
+            quoted[1..quoted.len() - 1].to_string()
+        } else {
+            value.clone()
+        };
+        text = text.replace(placeholder, &value);
+    }
+    if is_json {
+        text
+    } else {
+        object! { text: text.trim_end() }.dump()
+    }
+}
+
+/// Post the summary of the run to the webhook.
+pub fn send(
+    url: &str,
+    template: Option<&Path>,
+    options: &Options,
+    meetings: &HashMap<String, Meeting>,
+    skews: &[Skew],
+) -> io::Result<()> {
+    let template = match template {
+        Some(path) => fs::read_to_string(path)?,
+        None => DEFAULT_TEMPLATE.to_string(),
+    };
+    let body = payload(&template, &values(options, meetings, skews));
+    Delivery::from_options(options).send(vec![Post {
+        what: "the summary to the webhook".to_string(),
+        url: url.to_string(),
+        headers: vec![("Content-Type", "application/json".to_string())],
+        body,
+    }])?;
+    Ok(())
+}
@@ -0,0 +1,284 @@
+//! The OpenDocument export: the transcripts as a text document for the offices that only file ODF documents as records.
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crate::{
+    document::escape_html,
+    model::{lock_text, segment_start, ChatEntry, Meeting, TimeDisplay},
+    recording::Recordings,
+    split::{entry_options, package},
+};
+
+const NAMESPACES: &str = r#"xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:meta="urn:oasis:names:tc:opendocument:xmlns:meta:1.0" office:version="1.3""#;
+
+const MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.3">
+<manifest:file-entry manifest:full-path="/" manifest:version="1.3" manifest:media-type="application/vnd.oasis.opendocument.text"/>
+<manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+<manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/>
+<manifest:file-entry manifest:full-path="meta.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>
+"#;
+
+/// The named styles, so the headings show up in the navigator and the styles can be changed for the whole document.
+const STYLES: &str = r##"<office:styles>
+<style:default-style style:family="paragraph"><style:paragraph-properties fo:margin-bottom="0.1cm"/><style:text-properties fo:font-size="11pt"/></style:default-style>
+<style:style style:name="Standard" style:family="paragraph" style:class="text"/>
+<style:style style:name="Heading" style:family="paragraph" style:parent-style-name="Standard" style:class="text"><style:paragraph-properties fo:margin-top="0.4cm" fo:margin-bottom="0.2cm" fo:keep-with-next="always"/><style:text-properties fo:font-weight="bold"/></style:style>
+<style:style style:name="Heading_20_1" style:display-name="Heading 1" style:family="paragraph" style:parent-style-name="Heading" style:default-outline-level="1" style:class="text"><style:text-properties fo:font-size="16pt"/></style:style>
+<style:style style:name="Heading_20_2" style:display-name="Heading 2" style:family="paragraph" style:parent-style-name="Heading" style:default-outline-level="2" style:class="text"><style:text-properties fo:font-size="13pt"/></style:style>
+<style:style style:name="Heading_20_3" style:display-name="Heading 3" style:family="paragraph" style:parent-style-name="Heading" style:default-outline-level="3" style:class="text"><style:text-properties fo:font-size="11pt" fo:font-style="italic"/></style:style>
+<style:style style:name="Meeting" style:family="paragraph" style:parent-style-name="Heading_20_1" style:default-outline-level="1" style:class="text"><style:paragraph-properties fo:break-before="page"/></style:style>
+<style:style style:name="Welcome" style:family="paragraph" style:parent-style-name="Standard" style:class="text"><style:paragraph-properties fo:margin-left="0.5cm" fo:padding-left="0.2cm" fo:border-left="0.06pt solid #808080"/></style:style>
+<style:style style:name="Message" style:family="paragraph" style:parent-style-name="Standard" style:class="text"><style:paragraph-properties fo:margin-left="1.5cm" fo:text-indent="-1.5cm"/></style:style>
+<style:style style:name="Note" style:family="paragraph" style:parent-style-name="Message" style:class="text"><style:text-properties fo:font-style="italic" fo:color="#666666"/></style:style>
+<style:style style:name="Table_20_Contents" style:display-name="Table Contents" style:family="paragraph" style:parent-style-name="Standard" style:class="extra"/>
+<style:style style:name="Table_20_Heading" style:display-name="Table Heading" style:family="paragraph" style:parent-style-name="Table_20_Contents" style:class="extra"><style:text-properties fo:font-weight="bold"/></style:style>
+<style:style style:name="Time" style:family="text"><style:text-properties fo:color="#666666"/></style:style>
+<style:style style:name="Author" style:family="text"><style:text-properties fo:font-weight="bold"/></style:style>
+</office:styles>"##;
+
+/// The styles of the participant tables, which ODF only allows as automatic styles.
+const TABLE_STYLES: &str = r##"<office:automatic-styles>
+<style:style style:name="Participants" style:family="table"><style:table-properties style:width="17cm" table:align="left" fo:margin-bottom="0.3cm"/></style:style>
+<style:style style:name="Cell" style:family="table-cell"><style:table-cell-properties fo:padding="0.05cm" fo:border="0.5pt solid #808080"/></style:style>
+</office:automatic-styles>"##;
+
+const COLUMNS: [&str; 5] = ["Name", "Role", "Joined", "Left", "Messages"];
+
+/// The text escaped for XML, without the control characters XML does not allow.
+fn xml(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .filter(|&c| c >= ' ' || c == '\t' || c == '\n')
+        .collect();
+    escape_html(&text)
+}
+
+fn write_cell<W: Write>(out: &mut W, style: &str, text: &str) -> io::Result<()> {
+    writeln!(
+        out,
+        "<table:table-cell table:style-name=\"Cell\" office:value-type=\"string\"><text:p text:style-name=\"{}\">{}</text:p></table:table-cell>",
+        style,
+        xml(text)
+    )
+}
+
+/// The participants with their role, the time they joined first and left last, and how many messages they wrote.
+///
+/// The authors of the imported meetings, which only know the messages, are listed without the times.
+fn write_participants<W: Write>(
+    out: &mut W,
+    number: usize,
+    meeting: &Meeting,
+    times: TimeDisplay,
+) -> io::Result<()> {
+    let mut written: HashMap<&str, usize> = HashMap::new();
+    let mut authors = Vec::new();
+    for msg in meeting.chats.values().flat_map(|c| &c.messages) {
+        let count = written.entry(&msg.author_id).or_insert(0);
+        if *count == 0 && !meeting.users.contains_key(&msg.author_id) {
+            authors.push(msg);
+        }
+        *count += 1;
+    }
+    let mut rows: Vec<[String; 5]> = meeting
+        .sorted_users()
+        .into_iter()
+        .map(|user| {
+            let joined = user.sessions.iter().map(|(joined, _)| *joined).min();
+            let left = user.sessions.iter().filter_map(|(_, left)| *left).max();
+            [
+                user.name.clone(),
+                user.role.as_deref().unwrap_or_default().to_lowercase(),
+                joined
+                    .map(|time| times.format(meeting, time, "%H:%M"))
+                    .unwrap_or_default(),
+                left.map(|time| times.format(meeting, time, "%H:%M"))
+                    .unwrap_or_default(),
+                written
+                    .get(user.user_id.as_str())
+                    .copied()
+                    .unwrap_or(0)
+                    .to_string(),
+            ]
+        })
+        .collect();
+    authors.sort_by(|a, b| a.author_id.cmp(&b.author_id));
+    rows.extend(authors.into_iter().map(|msg| {
+        [
+            msg.author.clone(),
+            msg.role.as_deref().unwrap_or_default().to_lowercase(),
+            String::new(),
+            String::new(),
+            written[msg.author_id.as_str()].to_string(),
+        ]
+    }));
+    if rows.is_empty() {
+        return Ok(());
+    }
+    // stable, the participants with the same name stay in the order of their ids
+    rows.sort_by(|a, b| a[0].to_lowercase().cmp(&b[0].to_lowercase()));
+    writeln!(
+        out,
+        "<text:h text:style-name=\"Heading_20_2\" text:outline-level=\"2\">Participants</text:h>\n<table:table table:name=\"Participants{}\" table:style-name=\"Participants\">\n<table:table-column table:number-columns-repeated=\"{}\"/>\n<table:table-header-rows>\n<table:table-row>",
+        number,
+        COLUMNS.len()
+    )?;
+    for column in COLUMNS {
+        write_cell(out, "Table_20_Heading", column)?;
+    }
+    writeln!(out, "</table:table-row>\n</table:table-header-rows>")?;
+    for row in rows {
+        writeln!(out, "<table:table-row>")?;
+        for field in &row {
+            write_cell(out, "Table_20_Contents", field)?;
+        }
+        writeln!(out, "</table:table-row>")?;
+    }
+    writeln!(out, "</table:table>")
+}
+
+/// A meeting starting on a new page: the heading, the welcome messages, the participants and the chats.
+fn write_meeting<W: Write>(
+    out: &mut W,
+    number: usize,
+    meeting: &Meeting,
+    times: TimeDisplay,
+    recordings: Option<&Recordings>,
+) -> io::Result<()> {
+    let name = meeting
+        .name
+        .as_ref()
+        .map(|name| format!(" - {}", name))
+        .unwrap_or_default();
+    writeln!(
+        out,
+        "<text:h text:style-name=\"Meeting\" text:outline-level=\"1\">{}</text:h>",
+        xml(&format!(
+            "{} - {} ({}){}",
+            meeting.time.format("%d.%m.%Y %H:%M"),
+            meeting.meeting_id,
+            meeting.handle(),
+            name
+        ))
+    )?;
+    for (label, lines) in meeting.welcome_messages() {
+        writeln!(
+            out,
+            "<text:p text:style-name=\"Welcome\"><text:span text:style-name=\"Author\">{}:</text:span> {}</text:p>",
+            label,
+            lines
+                .iter()
+                .map(|line| xml(line))
+                .collect::<Vec<_>>()
+                .join("<text:line-break/>")
+        )?;
+    }
+    write_participants(out, number, meeting, times)?;
+    for chat in meeting.sorted_chats() {
+        writeln!(
+            out,
+            "<text:h text:style-name=\"Heading_20_2\" text:outline-level=\"2\">{}</text:h>",
+            xml(&chat.chat_id)
+        )?;
+        let mut segment = None;
+        for entry in meeting.entries(chat) {
+            let msg = match entry {
+                ChatEntry::Message(msg) => msg,
+                ChatEntry::Lock(time, locked) => {
+                    writeln!(
+                        out,
+                        "<text:p text:style-name=\"Note\">{} {}</text:p>",
+                        times.format(meeting, time, "%H:%M"),
+                        lock_text(locked)
+                    )?;
+                    continue;
+                }
+                ChatEntry::Clear(clear) => {
+                    writeln!(
+                        out,
+                        "<text:p text:style-name=\"Note\">{} {}</text:p>",
+                        times.format(meeting, clear.time, "%H:%M"),
+                        xml(&clear.text())
+                    )?;
+                    continue;
+                }
+            };
+            if let Some(number) = segment_start(&mut segment, msg) {
+                writeln!(
+                    out,
+                    "<text:h text:style-name=\"Heading_20_3\" text:outline-level=\"3\">Segment {}</text:h>",
+                    number
+                )?;
+            }
+            let time = times.format(meeting, msg.time, "%H:%M");
+            let time = match recordings.and_then(|r| r.link(meeting, msg.time)) {
+                Some(link) => format!(
+                    "<text:a xlink:type=\"simple\" xlink:href=\"{}\">{}</text:a>",
+                    xml(&link),
+                    time
+                ),
+                None => time,
+            };
+            writeln!(
+                out,
+                "<text:p text:style-name=\"Message\"><text:span text:style-name=\"Time\">{}</text:span> <text:span text:style-name=\"Author\">{}</text:span>: {}</text:p>",
+                time,
+                xml(&msg.display_author()),
+                msg.lines()
+                    .iter()
+                    .map(|line| xml(line))
+                    .collect::<Vec<_>>()
+                    .join("<text:line-break/>")
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the transcripts as an OpenDocument text, every meeting on its own pages. With `--split-by meeting` every meeting is a document of its own.
+pub fn write<'a, W: Write>(
+    out: &mut W,
+    meetings: impl Iterator<Item = &'a Meeting>,
+    times: TimeDisplay,
+    recordings: Option<&Recordings>,
+) -> io::Result<()> {
+    let meetings: Vec<&Meeting> = meetings.collect();
+    let mut zip = package("application/vnd.oasis.opendocument.text")?;
+    zip.start_file("META-INF/manifest.xml", entry_options())?;
+    zip.write_all(MANIFEST.as_bytes())?;
+    let title = match meetings.as_slice() {
+        [meeting] => meeting.name.clone().unwrap_or_else(|| meeting.handle()),
+        _ => "Chats".to_string(),
+    };
+    zip.start_file("meta.xml", entry_options())?;
+    writeln!(
+        zip,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<office:document-meta {}>\n<office:meta>\n<meta:generator>chatter</meta:generator>\n<dc:title>{}</dc:title>\n</office:meta>\n</office:document-meta>",
+        NAMESPACES,
+        xml(&title)
+    )?;
+    zip.start_file("styles.xml", entry_options())?;
+    writeln!(
+        zip,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<office:document-styles {}>\n{}\n</office:document-styles>",
+        NAMESPACES, STYLES
+    )?;
+    zip.start_file("content.xml", entry_options())?;
+    writeln!(
+        zip,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<office:document-content {}>\n{}\n<office:body>\n<office:text>",
+        NAMESPACES, TABLE_STYLES
+    )?;
+    for (i, meeting) in meetings.iter().enumerate() {
+        write_meeting(&mut zip, i + 1, meeting, times, recordings)?;
+    }
+    writeln!(
+        zip,
+        "</office:text>\n</office:body>\n</office:document-content>"
+    )?;
+    out.write_all(&zip.finish()?.into_inner())
+}
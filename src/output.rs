@@ -0,0 +1,154 @@
+//! Writing the collected meetings in the chosen format.
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use json::JsonValue;
+
+use crate::{
+    cli::{Command, Format, Options},
+    document, epub, export, grade,
+    header::HeaderField,
+    model::{sorted, ContextLine, Meeting, TimeDisplay, Transcript},
+    odt, query, quote,
+    recording::Recordings,
+    rollup, stats,
+    subtitle::{self, Kind},
+    summary,
+};
+
+/// Write the meetings as requested on the command line, the json transcripts carry the provenance if there is one.
+pub fn render<W: Write>(
+    out: &mut W,
+    options: &Options,
+    meetings: &HashMap<String, Meeting>,
+    recordings: Option<&Recordings>,
+    provenance: Option<&JsonValue>,
+) -> io::Result<()> {
+    match options.command {
+        Command::Stats if options.compare_room.is_some() => {
+            stats::print_room(out, meetings, options)
+        }
+        Command::Stats if options.anomalies => stats::print_anomalies(out, meetings, options),
+        Command::Stats if options.flagged => stats::print_flagged(out, meetings, options),
+        Command::Stats if options.participation => {
+            stats::print_participation(out, meetings, options)
+        }
+        Command::Stats if options.mentions => stats::print_mentions(out, meetings, options),
+        Command::Stats if options.name_collisions => {
+            stats::print_name_collisions(out, meetings, options)
+        }
+        Command::Stats => stats::print_stats(out, meetings, options),
+        Command::List => summary::print_list(out, meetings, options),
+        Command::Count => summary::print_count(out, meetings, options),
+        Command::Query => query::run(
+            out,
+            options.query.as_deref().unwrap_or_default(),
+            options.format,
+            meetings,
+        ),
+        // the exports are compared, the line is explained, the sample inspected and the schema printed before any meeting is collected
+        Command::Diff | Command::Explain | Command::Inspect | Command::Schema => Ok(()),
+        Command::Transcript if options.participants => export::write_participants(
+            out,
+            sorted(meetings).into_iter(),
+            &stats::collation(options),
+        ),
+        Command::Transcript if options.participation_moodle => grade::write_moodle(
+            out,
+            stats::participation(meetings, &stats::collation(options)).into_iter(),
+            &options.grade_formula,
+            &options.grade_item,
+        ),
+        Command::Transcript if options.rollup.is_some() => rollup::write(out, meetings, options),
+        Command::Transcript if options.format == Format::Text && options.chat.is_some() => {
+            quote::write(out, meetings, options.chat.as_deref().unwrap_or_default())
+        }
+        // the messages of the private and the public chats in the order they were written, with the chat on every line
+        Command::Report if options.format == Format::Text => {
+            print_lines(out, options.time_display, meetings)
+        }
+        Command::Transcript
+        | Command::Sar
+        | Command::Report
+        | Command::Convert
+        | Command::Merge => render_transcript(
+            out,
+            options.format,
+            options,
+            meetings,
+            recordings,
+            provenance,
+        ),
+    }
+}
+
+/// Write the transcripts of the meetings in the given format.
+pub fn render_transcript<W: Write>(
+    out: &mut W,
+    format: Format,
+    options: &Options,
+    meetings: &HashMap<String, Meeting>,
+    recordings: Option<&Recordings>,
+    provenance: Option<&JsonValue>,
+) -> io::Result<()> {
+    let times = options.time_display;
+    let header = options.header_fields.as_deref();
+    match format {
+        Format::Text => print_text(out, times, header, meetings),
+        Format::Json => export::write_json(out, sorted(meetings).into_iter(), provenance),
+        Format::Ndjson => export::write_ndjson(out, sorted(meetings).into_iter()),
+        Format::Csv => export::write_csv(out, sorted(meetings).into_iter()),
+        Format::Markdown => {
+            document::write_markdown(out, sorted(meetings).into_iter(), times, header, recordings)
+        }
+        Format::Html => document::write_html(
+            out,
+            sorted(meetings).into_iter(),
+            times,
+            header,
+            recordings,
+            &options.html,
+        ),
+        Format::Epub => epub::write(out, sorted(meetings).into_iter(), times, header, recordings),
+        Format::Odt => odt::write(out, sorted(meetings).into_iter(), times, recordings),
+        Format::Srt => subtitle::write(out, sorted(meetings).into_iter(), Kind::Srt),
+        Format::Vtt => subtitle::write(out, sorted(meetings).into_iter(), Kind::Vtt),
+    }
+}
+
+fn print_text<W: Write>(
+    out: &mut W,
+    times: TimeDisplay,
+    header: Option<&[HeaderField]>,
+    meetings: &HashMap<String, Meeting>,
+) -> io::Result<()> {
+    writeln!(out, "{}", meetings.len())?;
+    for meeting in sorted(meetings) {
+        writeln!(out, "\n\n{}", Transcript(meeting, times, header))?;
+    }
+    Ok(())
+}
+
+/// Print all messages of all meetings in chronological order, one per line together with their meeting and chat.
+pub fn print_lines<W: Write>(
+    out: &mut W,
+    times: TimeDisplay,
+    meetings: &HashMap<String, Meeting>,
+) -> io::Result<()> {
+    let mut lines: Vec<ContextLine> = sorted(meetings)
+        .into_iter()
+        .flat_map(|m| m.sorted_chats().into_iter().map(move |c| (m, c)))
+        .flat_map(|(m, c)| {
+            c.messages
+                .iter()
+                .map(move |msg| ContextLine(m, c, msg, times))
+        })
+        .collect();
+    lines.sort_by_key(|line| line.2.time);
+    for line in lines {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
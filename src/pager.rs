@@ -0,0 +1,75 @@
+//! Showing the output that is printed into a terminal in `$PAGER`, like git does, so a long transcript can be scrolled instead of rushing by.
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+use crate::cli::Options;
+
+/// The pager when `$PAGER` is not set.
+const DEFAULT: &str = "less";
+
+/// The running pager, what is written goes to its standard in.
+pub struct Pager {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl Pager {
+    /// Start the pager if the output goes to a terminal.
+    ///
+    /// `$PAGER` set to nothing or `cat` turns it off. `less` gets `LESS=FRX` unless it is set, so an output that fits on one screen is printed as it is. A pager that can not be started is no error, the output is printed without it.
+    pub fn start(options: &Options) -> Option<Pager> {
+        if options.no_pager
+            || options.output.is_some()
+            || options.compress.is_some()
+            || !io::stdout().is_terminal()
+        {
+            return None;
+        }
+        let command = env::var("PAGER").unwrap_or_else(|_| DEFAULT.to_string());
+        if command.trim().is_empty() || command.trim() == "cat" {
+            return None;
+        }
+        let mut pager = Command::new("sh");
+        pager.arg("-c").arg(&command).stdin(Stdio::piped());
+        if env::var_os("LESS").is_none() {
+            pager.env("LESS", "FRX");
+        }
+        if env::var_os("LV").is_none() {
+            pager.env("LV", "-c");
+        }
+        let mut child = pager.spawn().ok()?;
+        // Ctrl-C is meant for the pager, chatter ends when the pager is quit
+        #[cfg(unix)]
+        let _ = signal_hook::flag::register(
+            signal_hook::consts::SIGINT,
+            std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+        );
+        let stdin = child.stdin.take();
+        Some(Pager { child, stdin })
+    }
+
+    /// Close the standard in of the pager and wait until it is quit.
+    pub fn wait(mut self) -> io::Result<()> {
+        drop(self.stdin.take());
+        self.child.wait().map(|_| ())
+    }
+}
+
+impl Write for Pager {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.stdin {
+            Some(stdin) => stdin.write(buf),
+            None => Err(io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.stdin {
+            Some(stdin) => stdin.flush(),
+            None => Ok(()),
+        }
+    }
+}
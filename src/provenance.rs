@@ -0,0 +1,130 @@
+//! Where an export comes from: the version of chatter, the inputs with their checksums, the command line with its filters and when it ran, so an archived transcript can be traced back to the logs it was made of.
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use json::{object, JsonValue};
+
+use crate::{checksum, cli::Options, export::timestamp};
+
+/// The name of the file next to the documents of `--split-by` that can not carry the provenance themselves.
+pub const FILE: &str = "provenance.json";
+
+/// The file next to the `--output` of a format that can not carry the provenance, e.g. `chats.html.provenance.json`.
+pub fn sidecar(output: &Path) -> PathBuf {
+    let mut name = output.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(FILE);
+    output.with_file_name(name)
+}
+
+/// The options whose values are secrets, they are recorded as `***`.
+const SECRETS: [&str; 6] = [
+    "--anonymize-key",
+    "--bbb-secret",
+    "--http-user",
+    "--http-header",
+    "--nextcloud-password",
+    "--notify-webhook",
+];
+
+/// The options that decide which meetings and messages are in the export and how much of them, with whether they take a value.
+const FILTERS: [(&str, bool); 24] = [
+    ("--ignore-author", true),
+    ("--ignore-author-file", true),
+    ("--author-ext-id", true),
+    ("--filter-expr", true),
+    ("--chat", true),
+    ("--moderators-only", false),
+    ("--viewers-only", false),
+    ("--guests-only", false),
+    ("--since", true),
+    ("--until", true),
+    ("--tenant", true),
+    ("--meta", true),
+    ("--min-messages", true),
+    ("--sample", true),
+    ("--sample-meetings", true),
+    ("--seed", true),
+    ("--min-score", true),
+    ("--respect-clear", false),
+    ("--no-collapse-spam", false),
+    ("--no-bodies", false),
+    ("--anonymize-key", true),
+    ("--normalize", true),
+    ("--max-message-lines", true),
+    ("--max-message-bytes", true),
+];
+
+/// The command line with the values of the [secret](SECRETS) options replaced.
+pub fn redact(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.split_once('=') {
+            Some((flag, _)) if SECRETS.contains(&flag) => redacted.push(format!("{}=***", flag)),
+            _ if SECRETS.contains(&arg.as_str()) => {
+                redacted.push(arg.clone());
+                if args.next().is_some() {
+                    redacted.push("***".to_string());
+                }
+            }
+            _ => redacted.push(arg.clone()),
+        }
+    }
+    redacted
+}
+
+/// The [filters](FILTERS) of the command line with their values, e.g. `--since 10:15`.
+fn filters(args: &[String]) -> Vec<String> {
+    let mut filters = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let flag = arg.split_once('=').map_or(arg.as_str(), |(flag, _)| flag);
+        match FILTERS.iter().find(|(name, _)| *name == flag) {
+            Some((_, true)) if flag == arg => match args.next() {
+                Some(value) => filters.push(format!("{} {}", flag, value)),
+                None => filters.push(flag.to_string()),
+            },
+            Some(_) => filters.push(arg.replacen('=', " ", 1)),
+            None => {}
+        }
+    }
+    filters
+}
+
+/// The provenance of this run, the local input files are read again for their checksums.
+pub fn record(options: &Options, inputs: &[PathBuf]) -> io::Result<JsonValue> {
+    let mut files = Vec::new();
+    for input in inputs {
+        let path = input.display().to_string();
+        // reproducible exports are the same on all platforms
+        let path = if options.deterministic {
+            path.replace('\\', "/")
+        } else {
+            path
+        };
+        let mut file = object! { path: path };
+        // the urls are not downloaded a second time
+        if input.is_file() {
+            file["bytes"] = input.metadata()?.len().into();
+            file["sha256"] = checksum::of_file(input)?.into();
+        }
+        files.push(file);
+    }
+    if files.is_empty() {
+        files.push(object! { path: "-" });
+    }
+    let mut provenance = object! {
+        generator: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        arguments: options.arguments.clone(),
+        filters: filters(&options.arguments),
+        inputs: files,
+    };
+    if !options.deterministic {
+        provenance["created"] = timestamp(&Utc::now().naive_utc()).into();
+    }
+    Ok(provenance)
+}
@@ -0,0 +1,156 @@
+//! Running SQL over the collected meetings.
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use rusqlite::{types::ValueRef, Connection};
+
+use crate::{
+    cli::Format,
+    export::{csv_field, timestamp},
+    model::{sorted, Meeting},
+    schema::SCHEMA_VERSION,
+};
+
+/// The tables the queries can use.
+const SCHEMA: &str = "
+CREATE TABLE meetings (meeting_id TEXT PRIMARY KEY, time TEXT, name TEXT, tenant TEXT, handle TEXT);
+CREATE TABLE messages (meeting_id TEXT, chat_id TEXT, time TEXT, author TEXT, author_id TEXT, ext_user_id TEXT, message TEXT, guest INTEGER, id TEXT);
+CREATE TABLE mentions (meeting_id TEXT, chat_id TEXT, message_id TEXT, author_id TEXT, user_id TEXT, name TEXT, ext_user_id TEXT);
+CREATE TABLE presence (meeting_id TEXT, user_id TEXT, name TEXT, ext_user_id TEXT, joined TEXT, left TEXT, guest INTEGER);
+";
+
+fn to_io(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+/// Load the meetings into an in-memory database.
+fn load(meetings: &HashMap<String, Meeting>) -> rusqlite::Result<Connection> {
+    let mut db = Connection::open_in_memory()?;
+    db.execute_batch(SCHEMA)?;
+    // the same version as the json exports, read with `PRAGMA user_version`
+    db.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    let tx = db.transaction()?;
+    {
+        let mut meeting_row = tx.prepare("INSERT INTO meetings VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        let mut message_row =
+            tx.prepare("INSERT INTO messages VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)")?;
+        let mut mention_row =
+            tx.prepare("INSERT INTO mentions VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+        let mut presence_row =
+            tx.prepare("INSERT INTO presence VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+        for meeting in sorted(meetings) {
+            meeting_row.execute((
+                &meeting.meeting_id,
+                timestamp(&meeting.time),
+                &meeting.name,
+                &meeting.tenant,
+                meeting.handle(),
+            ))?;
+            for chat in meeting.sorted_chats() {
+                for msg in &chat.messages {
+                    message_row.execute((
+                        &meeting.meeting_id,
+                        &chat.chat_id,
+                        timestamp(&msg.time),
+                        &msg.author,
+                        &msg.author_id,
+                        &msg.ext_id,
+                        &msg.message,
+                        msg.guest,
+                        &msg.id,
+                    ))?;
+                    for mention in &msg.mentions {
+                        mention_row.execute((
+                            &meeting.meeting_id,
+                            &chat.chat_id,
+                            &msg.id,
+                            &msg.author_id,
+                            &mention.user_id,
+                            &mention.name,
+                            &mention.ext_id,
+                        ))?;
+                    }
+                }
+            }
+            for user in meeting.sorted_users() {
+                for (joined, left) in &user.sessions {
+                    presence_row.execute((
+                        &meeting.meeting_id,
+                        &user.user_id,
+                        &user.name,
+                        &user.ext_id,
+                        timestamp(joined),
+                        left.as_ref().map(timestamp),
+                        user.guest,
+                    ))?;
+                }
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(db)
+}
+
+fn value_text(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => String::new(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => f.to_string(),
+        ValueRef::Text(t) | ValueRef::Blob(t) => String::from_utf8_lossy(t).into_owned(),
+    }
+}
+
+/// Run the query and print the result as an aligned table or as CSV.
+pub fn run<W: Write>(
+    out: &mut W,
+    sql: &str,
+    format: Format,
+    meetings: &HashMap<String, Meeting>,
+) -> io::Result<()> {
+    let db = load(meetings).map_err(to_io)?;
+    let mut statement = db.prepare(sql).map_err(to_io)?;
+    let header: Vec<String> = statement
+        .column_names()
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    let mut rows = statement.query([]).map_err(to_io)?;
+    let mut table = Vec::new();
+    while let Some(row) = rows.next().map_err(to_io)? {
+        let values = (0..header.len())
+            .map(|i| row.get_ref(i).map(value_text))
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(to_io)?;
+        table.push(values);
+    }
+    if format == Format::Csv {
+        for row in Some(&header).into_iter().chain(&table) {
+            let row: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+            writeln!(out, "{}", row.join(","))?;
+        }
+        return Ok(());
+    }
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in &table {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    let line = |row: &[String]| -> String {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<1$}", value, width))
+            .collect();
+        cells.join(" | ").trim_end().to_string()
+    };
+    writeln!(out, "{}", line(&header))?;
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    writeln!(out, "{}", rule.join("-+-"))?;
+    for row in &table {
+        writeln!(out, "{}", line(row))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! One chat quoted with everything needed to judge it, e.g. when somebody complains about a private chat: the meeting, when its participants joined and left and the messages to the second.
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use chrono::NaiveDateTime;
+
+use crate::model::{self, Meeting, User};
+
+fn time(time: &NaiveDateTime) -> String {
+    time.format("%d.%m.%Y %H:%M:%S").to_string()
+}
+
+/// The users that wrote in the chat.
+///
+/// The internal user ids change with every join, so the other sessions of the same person count as well, found by the external id or the name.
+fn participants<'a>(meeting: &'a Meeting, authors: &[(&str, &str, Option<&str>)]) -> Vec<&'a User> {
+    meeting
+        .sorted_users()
+        .into_iter()
+        .filter(|user| {
+            authors.iter().any(|&(id, name, ext_id)| {
+                user.user_id == id
+                    || match (ext_id, &user.ext_id) {
+                        (Some(ext_id), Some(user_ext_id)) => ext_id == user_ext_id,
+                        (None, None) => user.name == name,
+                        _ => false,
+                    }
+            })
+        })
+        .collect()
+}
+
+/// Write the chat with the id in all the meetings it is found in.
+pub fn write<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    chat_id: &str,
+) -> io::Result<()> {
+    for meeting in model::sorted(meetings) {
+        let chat = match meeting.chats.get(chat_id) {
+            Some(chat) => chat,
+            None => continue,
+        };
+        writeln!(out, "Chat {}", chat.chat_id)?;
+        writeln!(out, "Meeting {} ({})", meeting.meeting_id, meeting.handle())?;
+        if let Some(name) = &meeting.name {
+            writeln!(out, "  name: {}", name)?;
+        }
+        writeln!(out, "  started: {}", time(&meeting.time))?;
+        if let Some(external_id) = &meeting.external_id {
+            writeln!(out, "  external id: {}", external_id)?;
+        }
+        if let Some(tenant) = &meeting.tenant {
+            writeln!(out, "  tenant: {}", tenant)?;
+        }
+        for (key, value) in &meeting.metadata {
+            writeln!(out, "  {}: {}", key, value)?;
+        }
+
+        let mut authors: Vec<(&str, &str, Option<&str>)> = Vec::new();
+        for msg in &chat.messages {
+            let author = (
+                msg.author_id.as_str(),
+                msg.author.as_str(),
+                msg.ext_id.as_deref(),
+            );
+            if !authors.contains(&author) {
+                authors.push(author);
+            }
+        }
+        // somebody who only read the chat did not leave a trace in it
+        writeln!(out, "\nParticipants (who wrote in the chat)")?;
+        for user in participants(meeting, &authors) {
+            let mut details = Vec::new();
+            if let Some(role) = &user.role {
+                details.push(role.to_lowercase());
+            }
+            if user.guest {
+                details.push("guest".to_string());
+            }
+            if let Some(ext_id) = &user.ext_id {
+                details.push(format!("external id {}", ext_id));
+            }
+            details.push(format!("user id {}", user.user_id));
+            writeln!(out, "  {} ({})", user.name, details.join(", "))?;
+            for (joined, left) in &user.sessions {
+                let left = left.as_ref().map_or("(not seen)".to_string(), time);
+                writeln!(out, "    joined {}, left {}", time(joined), left)?;
+            }
+        }
+
+        writeln!(out, "\nMessages")?;
+        for msg in &chat.messages {
+            let prefix = format!("  {} {}: ", time(&msg.time), msg.display_author());
+            let indent = " ".repeat(prefix.chars().count());
+            for (i, line) in msg.lines().iter().enumerate() {
+                writeln!(out, "{}{}", if i == 0 { &prefix } else { &indent }, line)?;
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
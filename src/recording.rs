@@ -0,0 +1,93 @@
+//! Links from the transcripts into the recordings.
+use std::{collections::HashMap, fs, io, path::Path};
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::model::Meeting;
+
+/// Builds the playback links of the messages.
+pub struct Recordings {
+    /// The playback url, `<recordId>` is replaced by the id of the recording. Without it the playback urls from the BigBlueButton API are used.
+    base_url: Option<String>,
+    /// Record ids that differ from the meeting ids.
+    record_ids: HashMap<String, String>,
+}
+
+impl Recordings {
+    pub fn new(base_url: Option<String>) -> Self {
+        Recordings {
+            base_url,
+            record_ids: HashMap::new(),
+        }
+    }
+
+    /// Read a file with one `meeting_id record_id` pair per line (separated by whitespace or a comma).
+    pub fn load_map(&mut self, path: &Path) -> io::Result<()> {
+        for line in fs::read_to_string(path)?.lines() {
+            let mut fields = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|f| !f.is_empty());
+            if let (Some(meeting_id), Some(record_id)) = (fields.next(), fields.next()) {
+                self.record_ids
+                    .insert(meeting_id.to_string(), record_id.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// The url to play the recording of the meeting at the given time.
+    ///
+    /// There is no link for messages that were written while the meeting was not recorded.
+    pub fn link(&self, meeting: &Meeting, time: NaiveDateTime) -> Option<String> {
+        let offset = recorded_offset(meeting, time)?;
+        // BigBlueButton uses the internal meeting id as record id.
+        let record_id = self
+            .record_ids
+            .get(&meeting.meeting_id)
+            .unwrap_or(&meeting.meeting_id);
+        let url = match &self.base_url {
+            Some(base_url) if base_url.contains("<recordId>") => {
+                base_url.replace("<recordId>", record_id)
+            }
+            Some(base_url) => format!("{}/{}", base_url.trim_end_matches('/'), record_id),
+            None => meeting.playback_url.clone()?,
+        };
+        let separator = if url.contains('?') { '&' } else { '?' };
+        let seconds = offset.num_seconds();
+        Some(format!(
+            "{}{}t={}h{}m{}s",
+            url,
+            separator,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ))
+    }
+}
+
+/// The position of a moment in the recording of a meeting.
+///
+/// Without any recording events the whole meeting is assumed to be recorded. Otherwise only the recorded periods count, which is how the playback skips the parts that were not recorded.
+pub fn recorded_offset(meeting: &Meeting, time: NaiveDateTime) -> Option<Duration> {
+    if meeting.recording_changes.is_empty() {
+        return Some(time - meeting.time).filter(|offset| *offset >= Duration::zero());
+    }
+    let mut changes = meeting.recording_changes.clone();
+    changes.sort();
+    let mut offset = Duration::zero();
+    let mut recording_since = None;
+    for (changed, recording) in changes {
+        if changed > time {
+            break;
+        }
+        match (recording, recording_since) {
+            (true, None) => recording_since = Some(changed),
+            (false, Some(since)) => {
+                offset = offset + (changed - since);
+                recording_since = None;
+            }
+            _ => {}
+        }
+    }
+    recording_since.map(|since| offset + (time - since))
+}
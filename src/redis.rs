@@ -0,0 +1,71 @@
+//! Subscribe to the Redis channels the BigBlueButton components talk over, to read the events as they happen without any log file.
+use std::{io, sync::mpsc, thread, time::Duration};
+
+use crate::{event, fields::FieldMap, follow::Source};
+
+/// The channel akka-apps publishes the events of the meetings to, the same ones it writes into its log.
+pub const DEFAULT_CHANNEL: &str = "from-akka-apps-redis-channel";
+
+/// How long to wait for an event before the source reports that nothing arrived.
+const WAIT: Duration = Duration::from_secs(2);
+
+/// The subscribed channels, read by a thread as the subscription blocks.
+pub struct Channels {
+    events: mpsc::Receiver<io::Result<String>>,
+    /// Which events are passed on.
+    fields: FieldMap,
+    name: String,
+}
+
+impl Channels {
+    /// Connect to the Redis server and subscribe to the channels.
+    pub fn subscribe(url: &str, channels: &[String], fields: &FieldMap) -> io::Result<Channels> {
+        let mut connection = redis::Client::open(url)
+            .and_then(|client| client.get_connection())
+            .map_err(io::Error::other)?;
+        let channels = channels.to_vec();
+        let (sender, events) = mpsc::channel();
+        thread::spawn(move || {
+            let mut pubsub = connection.as_pubsub();
+            let result = pubsub.subscribe(&channels).and_then(|_| loop {
+                let payload = pubsub.get_message()?.get_payload()?;
+                if sender.send(Ok(payload)).is_err() {
+                    return Ok(());
+                }
+            });
+            if let Err(e) = result {
+                let _ = sender.send(Err(io::Error::other(e)));
+            }
+        });
+        Ok(Channels {
+            events,
+            fields: fields.clone(),
+            name: url.to_string(),
+        })
+    }
+}
+
+impl Source for Channels {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn poll(&mut self) -> io::Result<Vec<String>> {
+        let first = match self.events.recv_timeout(WAIT) {
+            Ok(event) => event,
+            Err(mpsc::RecvTimeoutError::Timeout) => return Ok(Vec::new()),
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(io::Error::other("the subscription ended"))
+            }
+        };
+        let mut lines = Vec::new();
+        for payload in std::iter::once(first).chain(self.events.try_iter()) {
+            let payload = payload?;
+            // the events that are not in the log (e.g. who is talking) must not end up as chat messages
+            if event::is_read_payload(&self.fields, &payload) {
+                lines.push(payload);
+            }
+        }
+        Ok(lines)
+    }
+}
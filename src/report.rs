@@ -0,0 +1,210 @@
+//! A report of the checks of a run in the JUnit XML format, so CI and compliance pipelines can show the failures in their dashboards.
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::{
+    cli::Options,
+    collect::ParseError,
+    document::escape_html,
+    model::{self, Meeting},
+};
+
+/// How many of the lines that could not be parsed are quoted in a failure.
+const QUOTED_ERRORS: usize = 5;
+
+/// The result of one check.
+enum Outcome {
+    Passed,
+    Failed {
+        message: String,
+        details: String,
+    },
+    /// The check is not configured.
+    Skipped(&'static str),
+}
+
+struct Case {
+    class: String,
+    name: &'static str,
+    outcome: Outcome,
+}
+
+/// The lines of the inputs that could not be parsed.
+///
+/// The log lines contain the names and ids of the participants, so when they are anonymized only the file, the line number and the error are quoted.
+fn parse_errors(inputs: &[PathBuf], errors: &[ParseError], anonymized: bool) -> Vec<Case> {
+    inputs
+        .iter()
+        .map(|input| {
+            let errors: Vec<String> = errors
+                .iter()
+                .filter(|e| &e.source == input)
+                .map(|e| {
+                    let position = format!("{}:{}: {}", e.source.display(), e.line_number, e.error);
+                    if anonymized {
+                        position
+                    } else {
+                        format!("{}: {}", position, e.line)
+                    }
+                })
+                .collect();
+            let outcome = if errors.is_empty() {
+                Outcome::Passed
+            } else {
+                Outcome::Failed {
+                    message: format!("{} lines could not be parsed", errors.len()),
+                    details: errors[..errors.len().min(QUOTED_ERRORS)].join("\n"),
+                }
+            };
+            Case {
+                class: input.display().to_string(),
+                name: "parse errors",
+                outcome,
+            }
+        })
+        .collect()
+}
+
+/// The messages of the meeting that contain one of the alert words.
+fn alert_words(meeting: &Meeting, options: &Options) -> Outcome {
+    if options.alert_words.is_empty() {
+        return Outcome::Skipped("no --alert-word given");
+    }
+    let words: Vec<String> = options
+        .alert_words
+        .iter()
+        .map(|w| w.to_lowercase())
+        .collect();
+    let mut found = Vec::new();
+    for chat in meeting.sorted_chats() {
+        for msg in &chat.messages {
+            let text = msg.message.to_lowercase();
+            if let Some(word) = words.iter().find(|w| text.contains(w.as_str())) {
+                found.push(format!(
+                    "{} {} {}: {} ({})",
+                    msg.time.format("%H:%M"),
+                    chat.chat_id,
+                    msg.author,
+                    msg.message.replace('\n', " "),
+                    word
+                ));
+            }
+        }
+    }
+    if found.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed {
+            message: format!("{} messages with alert words", found.len()),
+            details: found.join("\n"),
+        }
+    }
+}
+
+/// A meeting that is older than the retention period should not be in the logs anymore.
+fn retention(meeting: &Meeting, options: &Options, now: NaiveDateTime) -> Outcome {
+    let days = match options.retention_days {
+        Some(days) => days,
+        None => return Outcome::Skipped("no --retention-days given"),
+    };
+    let age = now - meeting.time;
+    if age > Duration::days(days) {
+        Outcome::Failed {
+            message: format!(
+                "the meeting is {} days old, the retention period is {} days",
+                age.num_days(),
+                days
+            ),
+            details: String::new(),
+        }
+    } else {
+        Outcome::Passed
+    }
+}
+
+fn write_suite<W: Write>(out: &mut W, name: &str, cases: &[Case]) -> io::Result<()> {
+    let failures = cases
+        .iter()
+        .filter(|c| matches!(c.outcome, Outcome::Failed { .. }))
+        .count();
+    let skipped = cases
+        .iter()
+        .filter(|c| matches!(c.outcome, Outcome::Skipped(_)))
+        .count();
+    writeln!(
+        out,
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\">",
+        name,
+        cases.len(),
+        failures,
+        skipped
+    )?;
+    for case in cases {
+        write!(
+            out,
+            "    <testcase classname=\"{}\" name=\"{}\"",
+            escape_html(&case.class),
+            case.name
+        )?;
+        match &case.outcome {
+            Outcome::Passed => writeln!(out, "/>")?,
+            Outcome::Failed { message, details } => writeln!(
+                out,
+                ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                escape_html(message),
+                escape_html(details)
+            )?,
+            Outcome::Skipped(reason) => writeln!(
+                out,
+                ">\n      <skipped message=\"{}\"/>\n    </testcase>",
+                reason
+            )?,
+        }
+    }
+    writeln!(out, "  </testsuite>")
+}
+
+/// Write the report: one test case per input for the lines that could not be parsed and per meeting one for the alert words and one for the retention period.
+pub fn write_junit<W: Write>(
+    out: &mut W,
+    options: &Options,
+    inputs: &[PathBuf],
+    errors: &[ParseError],
+    meetings: &HashMap<String, Meeting>,
+    now: NaiveDateTime,
+) -> io::Result<()> {
+    let inputs = parse_errors(inputs, errors, options.anonymize_key.is_some());
+    let mut checks = Vec::new();
+    for meeting in model::sorted(meetings) {
+        checks.push(Case {
+            class: meeting.meeting_id.clone(),
+            name: "alert words",
+            outcome: alert_words(meeting, options),
+        });
+        checks.push(Case {
+            class: meeting.meeting_id.clone(),
+            name: "retention",
+            outcome: retention(meeting, options, now),
+        });
+    }
+    let failures = inputs
+        .iter()
+        .chain(&checks)
+        .filter(|c| matches!(c.outcome, Outcome::Failed { .. }))
+        .count();
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        out,
+        "<testsuites name=\"chatter\" tests=\"{}\" failures=\"{}\">",
+        inputs.len() + checks.len(),
+        failures
+    )?;
+    write_suite(out, "inputs", &inputs)?;
+    write_suite(out, "meetings", &checks)?;
+    writeln!(out, "</testsuites>")
+}
@@ -0,0 +1,61 @@
+//! The messages counted per time bucket and meeting, for dashboards that should get the numbers but not the texts.
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{self, Write},
+};
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::{
+    cli::{Format, Options},
+    export::timestamp,
+    model::Meeting,
+    table::Table,
+};
+
+const COLUMNS: [&str; 4] = ["bucket", "meeting", "messages", "authors"];
+
+/// The start of the bucket the time falls into, the buckets are counted from the Unix epoch so they line up across meetings and runs.
+fn bucket(time: NaiveDateTime, size: Duration) -> NaiveDateTime {
+    let seconds = time.timestamp();
+    let size = size.num_seconds();
+    NaiveDateTime::from_timestamp(seconds - seconds.rem_euclid(size), 0)
+}
+
+/// Write one row per bucket and meeting with messages in it, the number of messages and of their distinct authors, ordered by the bucket.
+///
+/// The buckets are as long as the `--rollup`, the text is written as CSV.
+pub fn write<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    options: &Options,
+) -> io::Result<()> {
+    let size = match options.rollup {
+        Some(size) => size,
+        None => return Ok(()),
+    };
+    let mut buckets: BTreeMap<(NaiveDateTime, &str), (usize, HashSet<&str>)> = BTreeMap::new();
+    for meeting in meetings.values() {
+        for msg in meeting.chats.values().flat_map(|c| &c.messages) {
+            let (messages, authors) = buckets
+                .entry((bucket(msg.time, size), meeting.meeting_id.as_str()))
+                .or_default();
+            *messages += 1;
+            authors.insert(&msg.author_id);
+        }
+    }
+    let mut table = Table::new(COLUMNS.to_vec());
+    for ((start, meeting_id), (messages, authors)) in buckets {
+        table.push(vec![
+            timestamp(&start).into(),
+            meeting_id.into(),
+            messages.into(),
+            authors.len().into(),
+        ]);
+    }
+    let format = match options.format {
+        Format::Json => Format::Json,
+        _ => Format::Csv,
+    };
+    table.write(out, format)
+}
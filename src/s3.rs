@@ -0,0 +1,404 @@
+//! Reading the logs from and writing the output to S3 compatible object storage like MinIO, with `s3://bucket/key` in place of a path.
+//!
+//! The credentials, the region and the endpoint come from the environment variables of the AWS tools: `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, `AWS_SESSION_TOKEN`, `AWS_REGION` (default `us-east-1`) and `AWS_ENDPOINT_URL`, e.g. `http://minio:9000`, without it Amazon S3 is used. The bucket is addressed in the path of the requests, which both understand.
+use std::{
+    env,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::{checksum::hex, input};
+
+/// The output is uploaded in parts of this size, S3 takes at most 10000 parts of at least 5 MiB.
+const PART_SIZE: usize = 16 * 1024 * 1024;
+
+/// A bucket and a key, or a prefix of keys if it is empty or ends in `/`.
+struct Object {
+    bucket: String,
+    key: String,
+}
+
+impl Object {
+    fn parse(url: &Path) -> io::Result<Object> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: not an s3://bucket/key", url.display()),
+            )
+        };
+        let rest = url
+            .to_str()
+            .and_then(|url| url.strip_prefix("s3://"))
+            .ok_or_else(invalid)?;
+        let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Object {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+        })
+    }
+
+    fn is_prefix(&self) -> bool {
+        self.key.is_empty() || self.key.ends_with('/')
+    }
+}
+
+/// Percent encode everything but the unreserved characters (and `/` in paths) like the signature expects.
+fn encode(text: &str, path: bool) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if path => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes any key");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The text of the first element with this name.
+fn element(xml: &str, name: &str) -> Option<String> {
+    let doc = roxmltree::Document::parse(xml).ok()?;
+    let text = doc
+        .descendants()
+        .find(|n| n.tag_name().name() == name)?
+        .text()?
+        .to_string();
+    Some(text)
+}
+
+/// Signs the requests with AWS Signature Version 4.
+struct Client {
+    endpoint: String,
+    host: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl Client {
+    fn from_env() -> io::Result<Client> {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+        let (access_key, secret_key) =
+            match (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY")) {
+                (Some(access_key), Some(secret_key)) => (access_key, secret_key),
+                _ => {
+                    return Err(io::Error::other(
+                        "s3:// needs AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY",
+                    ))
+                }
+            };
+        let region = var("AWS_REGION")
+            .or_else(|| var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|| "us-east-1".to_string());
+        let endpoint = var("AWS_ENDPOINT_URL_S3")
+            .or_else(|| var("AWS_ENDPOINT_URL"))
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+        let host = endpoint
+            .split_once("://")
+            .map_or(endpoint.as_str(), |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        Ok(Client {
+            endpoint,
+            host,
+            region,
+            access_key,
+            secret_key,
+            session_token: var("AWS_SESSION_TOKEN"),
+        })
+    }
+
+    /// Send a signed request for the object, an error status becomes an error with the message of S3.
+    fn request(
+        &self,
+        method: &str,
+        object: &Object,
+        query: &[(&str, &str)],
+        body: &[u8],
+    ) -> io::Result<ureq::Response> {
+        let now = Utc::now();
+        let date = now.format("%Y%m%d").to_string();
+        let time = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let path = format!("/{}/{}", object.bucket, encode(&object.key, true));
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(name, value)| (encode(name, false), encode(value, false)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&");
+        let payload = hex(&Sha256::digest(body));
+
+        let mut headers = vec![
+            ("host", self.host.as_str()),
+            ("x-amz-content-sha256", payload.as_str()),
+            ("x-amz-date", time.as_str()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token));
+        }
+        let signed = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method,
+            path,
+            query,
+            headers
+                .iter()
+                .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+                .collect::<String>(),
+            signed,
+            payload
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            time,
+            scope,
+            hex(&Sha256::digest(canonical.as_bytes()))
+        );
+        let key = hmac(format!("AWS4{}", self.secret_key).as_bytes(), &date);
+        let key = hmac(&key, &self.region);
+        let key = hmac(&key, "s3");
+        let key = hmac(&key, "aws4_request");
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key,
+            scope,
+            signed,
+            hex(&hmac(&key, &to_sign))
+        );
+
+        let url = if query.is_empty() {
+            format!("{}{}", self.endpoint, path)
+        } else {
+            format!("{}{}?{}", self.endpoint, path, query)
+        };
+        let mut request = ureq::request(method, &url).set("authorization", &authorization);
+        for (name, value) in &headers {
+            request = request.set(name, value);
+        }
+        let url = format!("s3://{}/{}", object.bucket, object.key);
+        match request.send_bytes(body) {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(status, response)) => {
+                let xml = response.into_string().unwrap_or_default();
+                let message = element(&xml, "Message")
+                    .or_else(|| element(&xml, "Code"))
+                    .unwrap_or_default();
+                let kind = if status == 404 {
+                    io::ErrorKind::NotFound
+                } else {
+                    io::ErrorKind::Other
+                };
+                Err(io::Error::new(
+                    kind,
+                    format!("{}: status code {} {}", url, status, message),
+                ))
+            }
+            Err(e) => Err(io::Error::other(format!("{}: {}", url, e))),
+        }
+    }
+}
+
+/// The objects of an `s3://bucket/prefix/` as their own URLs sorted by their key, like the files of a directory. The compressed rotated logs are skipped. A URL that does not end in `/` is one object.
+pub fn list(url: &Path) -> io::Result<Vec<PathBuf>> {
+    let object = Object::parse(url)?;
+    if !object.is_prefix() {
+        return Ok(vec![url.to_path_buf()]);
+    }
+    let client = Client::from_env()?;
+    let bucket = Object {
+        bucket: object.bucket.clone(),
+        key: String::new(),
+    };
+    let mut keys = Vec::new();
+    let mut token: Option<String> = None;
+    loop {
+        let mut query = vec![("list-type", "2"), ("prefix", object.key.as_str())];
+        if let Some(token) = &token {
+            query.push(("continuation-token", token));
+        }
+        let xml = client.request("GET", &bucket, &query, &[])?.into_string()?;
+        let doc = roxmltree::Document::parse(&xml)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        for key in doc
+            .descendants()
+            .filter(|n| n.tag_name().name() == "Key")
+            .filter_map(|n| n.text())
+        {
+            keys.push(key.to_string());
+        }
+        token = element(&xml, "NextContinuationToken");
+        if element(&xml, "IsTruncated").as_deref() != Some("true") || token.is_none() {
+            break;
+        }
+    }
+    keys.sort();
+    Ok(keys
+        .into_iter()
+        .filter(|key| !key.ends_with('/'))
+        .map(|key| PathBuf::from(format!("s3://{}/{}", object.bucket, key)))
+        .filter(|path| !input::is_compressed(path))
+        .collect())
+}
+
+/// Read an object as it is downloaded.
+pub fn open(url: &Path) -> io::Result<Box<dyn Read + Send>> {
+    let object = Object::parse(url)?;
+    let response = Client::from_env()?.request("GET", &object, &[], &[])?;
+    Ok(response.into_reader())
+}
+
+/// An object written in parts as they fill up, so an output of any size needs only the memory of one part.
+pub struct Upload {
+    client: Client,
+    object: Object,
+    part: Vec<u8>,
+    /// The upload of the parts once the first one is full.
+    upload_id: Option<String>,
+    /// The ETags of the uploaded parts.
+    etags: Vec<String>,
+}
+
+impl Upload {
+    /// Start writing to the object, it only appears with [`Upload::finish`].
+    pub fn create(url: &Path) -> io::Result<Upload> {
+        let object = Object::parse(url)?;
+        if object.is_prefix() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: not the key of an object", url.display()),
+            ));
+        }
+        Ok(Upload {
+            client: Client::from_env()?,
+            object,
+            part: Vec::new(),
+            upload_id: None,
+            etags: Vec::new(),
+        })
+    }
+
+    fn upload_part(&mut self) -> io::Result<()> {
+        let upload_id = match &self.upload_id {
+            Some(upload_id) => upload_id.clone(),
+            None => {
+                let xml = self
+                    .client
+                    .request("POST", &self.object, &[("uploads", "")], &[])?
+                    .into_string()?;
+                let upload_id = element(&xml, "UploadId").ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "S3 started no upload")
+                })?;
+                self.upload_id = Some(upload_id.clone());
+                upload_id
+            }
+        };
+        let number = (self.etags.len() + 1).to_string();
+        let response = self.client.request(
+            "PUT",
+            &self.object,
+            &[("partNumber", &number), ("uploadId", &upload_id)],
+            &self.part,
+        )?;
+        self.etags
+            .push(response.header("ETag").unwrap_or_default().to_string());
+        self.part.clear();
+        Ok(())
+    }
+
+    /// Upload what is left and complete the object.
+    pub fn finish(mut self) -> io::Result<()> {
+        let upload_id = match &self.upload_id {
+            // small enough for one request
+            None => {
+                let part = std::mem::take(&mut self.part);
+                self.client.request("PUT", &self.object, &[], &part)?;
+                return Ok(());
+            }
+            Some(upload_id) => upload_id.clone(),
+        };
+        if !self.part.is_empty() {
+            self.upload_part()?;
+        }
+        let mut xml = String::from("<CompleteMultipartUpload>");
+        for (i, etag) in self.etags.iter().enumerate() {
+            xml.push_str(&format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                i + 1,
+                etag
+            ));
+        }
+        xml.push_str("</CompleteMultipartUpload>");
+        let response = self
+            .client
+            .request(
+                "POST",
+                &self.object,
+                &[("uploadId", &upload_id)],
+                xml.as_bytes(),
+            )?
+            .into_string()?;
+        // S3 may report a failure after the status code
+        if let Some(message) = element(&response, "Message") {
+            return Err(io::Error::other(format!(
+                "s3://{}/{}: {}",
+                self.object.bucket, self.object.key, message
+            )));
+        }
+        self.upload_id = None;
+        Ok(())
+    }
+}
+
+impl Write for Upload {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.part.extend_from_slice(buf);
+        if self.part.len() >= PART_SIZE {
+            self.upload_part()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Upload {
+    /// The parts of an unfinished upload are stored (and paid for) until it is aborted.
+    fn drop(&mut self) {
+        if let Some(upload_id) = self.upload_id.take() {
+            let _ = self
+                .client
+                .request("DELETE", &self.object, &[("uploadId", &upload_id)], &[]);
+        }
+    }
+}
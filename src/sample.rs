@@ -0,0 +1,116 @@
+//! A random but reproducible part of the meetings, to try an analysis on a large archive before it runs for hours on all of it.
+use std::{collections::HashMap, convert::TryInto};
+
+use sha2::{Digest, Sha256};
+
+use crate::model::Meeting;
+
+/// Which part of the meetings is kept.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Sampling {
+    /// About this share of the meetings, between 0 and 1.
+    Share(f64),
+    /// Exactly this many meetings, or all if there are fewer.
+    Meetings(usize),
+}
+
+impl Sampling {
+    /// A percentage like `5%`.
+    pub fn share(percent: &str) -> Result<Sampling, String> {
+        let share: f64 = percent
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|e| format!("--sample: {}: {}", percent, e))?;
+        if !(share > 0.0 && share <= 100.0) {
+            return Err(format!("--sample: {} is not between 0% and 100%", percent));
+        }
+        Ok(Sampling::Share(share / 100.0))
+    }
+}
+
+/// Where the meeting falls in the order of the seed, the same in every run.
+///
+/// A meeting is kept or not regardless of the other meetings, so a sample of the logs of a month has the same meetings as the sample of the whole term.
+fn rank(meeting_id: &str, seed: u64) -> u64 {
+    let hash = Sha256::digest(format!("{}:{}", seed, meeting_id).as_bytes());
+    u64::from_be_bytes(hash[..8].try_into().expect("SHA-256 has 32 bytes"))
+}
+
+/// Keep the sample of the meetings that is chosen by the seed.
+pub fn select(meetings: &mut HashMap<String, Meeting>, sampling: Sampling, seed: u64) {
+    match sampling {
+        // the ranks close to u64::MAX are as large as the limit as a float
+        Sampling::Share(share) if share >= 1.0 => {}
+        Sampling::Share(share) => {
+            let limit = share * u64::MAX as f64;
+            meetings.retain(|id, _| (rank(id, seed) as f64) < limit);
+        }
+        Sampling::Meetings(count) => {
+            let mut ranks: Vec<(u64, String)> = meetings
+                .keys()
+                .map(|id| (rank(id, seed), id.clone()))
+                .collect();
+            ranks.sort();
+            for (_, id) in ranks.into_iter().skip(count) {
+                meetings.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn meetings(count: usize) -> HashMap<String, Meeting> {
+        let time = NaiveDate::from_ymd(2021, 3, 1).and_hms(10, 0, 0);
+        (0..count)
+            .map(|i| {
+                let id = format!("meeting-{}", i);
+                (id.clone(), Meeting::new(id, time))
+            })
+            .collect()
+    }
+
+    fn sample(count: usize, sampling: Sampling, seed: u64) -> BTreeSet<String> {
+        let mut meetings = meetings(count);
+        select(&mut meetings, sampling, seed);
+        meetings.into_keys().collect()
+    }
+
+    #[test]
+    fn shares() {
+        assert_eq!(Sampling::share("5%"), Ok(Sampling::Share(0.05)));
+        assert_eq!(Sampling::share("100%"), Ok(Sampling::Share(1.0)));
+        for invalid in ["0%", "-5%", "100.5%", "five%"] {
+            assert!(Sampling::share(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn the_same_seed_chooses_the_same_meetings() {
+        let share = Sampling::Share(0.5);
+        let first = sample(200, share, 7);
+        assert_eq!(sample(200, share, 7), first);
+        assert_ne!(sample(200, share, 8), first);
+        assert!(first.len() > 50 && first.len() < 150, "{}", first.len());
+        // with a share a meeting is kept regardless of the others
+        let fewer = sample(100, share, 7);
+        assert!(fewer.is_subset(&first));
+        let meetings = sample(200, Sampling::Meetings(20), 7);
+        assert_eq!(meetings.len(), 20);
+        assert_eq!(sample(200, Sampling::Meetings(20), 7), meetings);
+    }
+
+    #[test]
+    fn the_boundaries_keep_all_or_nothing() {
+        assert_eq!(sample(100, Sampling::Share(1.0), 0).len(), 100);
+        assert_eq!(sample(100, Sampling::Share(0.0), 0).len(), 0);
+        assert_eq!(sample(10, Sampling::Meetings(20), 0).len(), 10);
+        assert_eq!(sample(10, Sampling::Meetings(0), 0).len(), 0);
+    }
+}
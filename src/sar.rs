@@ -0,0 +1,190 @@
+//! The `sar` subcommand: a bundle of everything that is known about one person for a subject access request.
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use json::{object, JsonValue};
+use zip::ZipWriter;
+
+use crate::{
+    checksum,
+    cli::{Format, Options},
+    export::{csv_field, timestamp},
+    model::{self, Meeting, Message, User},
+    output,
+    recording::Recordings,
+    split::Target,
+};
+
+const FORMATS: [Format; 6] = [
+    Format::Text,
+    Format::Json,
+    Format::Ndjson,
+    Format::Csv,
+    Format::Markdown,
+    Format::Html,
+];
+
+fn is_subject_user(user: &User, subject: &str) -> bool {
+    user.ext_id.as_deref() == Some(subject) || user.name == subject
+}
+
+pub fn is_subject_message(msg: &Message, subject: &str) -> bool {
+    msg.ext_id.as_deref() == Some(subject) || msg.author == subject
+}
+
+/// The meetings reduced to the messages the subject wrote.
+///
+/// Everything the other participants wrote is left out, also in the private chats with the subject. The welcome message of the moderators is only kept if the subject was one of them, otherwise they never saw it.
+fn subject_meetings(
+    meetings: &HashMap<String, Meeting>,
+    subject: &str,
+) -> HashMap<String, Meeting> {
+    let mut selected = HashMap::new();
+    for meeting in meetings.values() {
+        let mut part = meeting.without_chats();
+        part.users.retain(|_, user| is_subject_user(user, subject));
+        for chat in meeting.chats.values() {
+            let messages: Vec<Message> = chat
+                .messages
+                .iter()
+                .filter(|msg| is_subject_message(msg, subject))
+                .cloned()
+                .collect();
+            if !messages.is_empty() {
+                let mut chat = chat.without_messages();
+                chat.messages = messages;
+                part.chats.insert(chat.chat_id.clone(), chat);
+            }
+        }
+        let moderator = part
+            .users
+            .values()
+            .filter_map(|user| user.role.as_deref())
+            .chain(
+                part.chats
+                    .values()
+                    .flat_map(|c| &c.messages)
+                    .filter_map(|msg| msg.role.as_deref()),
+            )
+            .any(|role| role.eq_ignore_ascii_case("moderator"));
+        if !moderator {
+            part.moderator_message = None;
+        }
+        if !part.chats.is_empty() || !part.users.is_empty() {
+            selected.insert(part.meeting_id.clone(), part);
+        }
+    }
+    selected
+}
+
+/// The times the subject joined and left the meetings as CSV.
+fn write_presence<W: Write>(out: &mut W, meetings: &HashMap<String, Meeting>) -> io::Result<usize> {
+    writeln!(out, "meeting_id,name,ext_user_id,joined,left")?;
+    let mut records = 0;
+    for meeting in model::sorted(meetings) {
+        for user in meeting.sorted_users() {
+            for (joined, left) in &user.sessions {
+                let row = [
+                    meeting.meeting_id.clone(),
+                    user.name.clone(),
+                    user.ext_id.clone().unwrap_or_default(),
+                    timestamp(joined),
+                    left.as_ref().map(timestamp).unwrap_or_default(),
+                ];
+                let row: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+                writeln!(out, "{}", row.join(","))?;
+                records += 1;
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Write the bundle for the subject of the options into the archive of the options.
+pub fn write(
+    options: &Options,
+    inputs: &[PathBuf],
+    meetings: &HashMap<String, Meeting>,
+    recordings: Option<&Recordings>,
+    archive: &Path,
+) -> io::Result<()> {
+    let subject = options.subject.as_deref().unwrap_or_default();
+    let selected = subject_meetings(meetings, subject);
+    let mut target = Target::Archive(Box::new(ZipWriter::new(File::create(archive)?)));
+    let mut files = Vec::new();
+    let mut sums = Vec::new();
+
+    for format in FORMATS {
+        let name = format!("messages.{}", format.extension());
+        let sum = target.file(&name, "", |mut out| {
+            if format == Format::Text {
+                output::print_lines(&mut out, options.time_display, &selected)
+            } else {
+                output::render_transcript(&mut out, format, options, &selected, recordings, None)
+            }
+        })?;
+        sums.push((name.clone(), sum));
+        files.push(name);
+    }
+
+    let mut presence_records = 0;
+    let sum = target.file("presence.csv", "", |mut out| {
+        presence_records = write_presence(&mut out, &selected)?;
+        Ok(())
+    })?;
+    sums.push(("presence.csv".to_string(), sum));
+    files.push("presence.csv".to_string());
+
+    let messages: usize = selected
+        .values()
+        .flat_map(|m| m.chats.values())
+        .map(|c| c.messages.len())
+        .sum();
+    // reproducible bundles neither tell when nor on which platform they were created
+    let inputs: Vec<String> = inputs
+        .iter()
+        .map(|p| p.display().to_string())
+        .map(|p| {
+            if options.deterministic {
+                p.replace('\\', "/")
+            } else {
+                p
+            }
+        })
+        .collect();
+    let mut manifest = object! {
+        generator: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        subject: subject,
+        inputs: inputs,
+        meetings: model::sorted(&selected).into_iter().map(|m| object! {
+            meeting_id: m.meeting_id.clone(),
+            time: timestamp(&m.time),
+        }).collect::<Vec<JsonValue>>(),
+        messages: messages,
+        presence_records: presence_records,
+        processing: "Contains the chat messages written by the subject and the times the subject joined and left the meetings, \
+            selected by matching the subject against the external user ids and the display names. \
+            Messages of other participants are excluded, also in private chats with the subject. \
+            Timestamps are UTC.",
+        normalization: object! {
+            whitespace: options.normalize.whitespace,
+            strip_emoji: options.normalize.strip_emoji,
+            ascii: options.normalize.ascii,
+            max_length: options.normalize.max_length,
+        },
+        files: files,
+    };
+    if !options.deterministic {
+        manifest["created"] = timestamp(&Utc::now().naive_utc()).into();
+    }
+    let sum = target.file("manifest.json", "", |mut out| {
+        manifest.write_pretty(&mut out, 2)
+    })?;
+    sums.push(("manifest.json".to_string(), sum));
+    checksum::finish(options, target, &sums)
+}
@@ -0,0 +1,177 @@
+//! The JSON Schema of the json and ndjson exports, so consumers can validate them and notice breaking changes.
+//!
+//! Every meeting of the json export and every line of the ndjson export carries the [`SCHEMA_VERSION`]. It is raised when a field is removed, renamed or changes its type, new optional fields keep it.
+use std::io::{self, Write};
+
+use json::{array, object, JsonValue};
+
+use crate::cli::Format;
+
+/// The version of the structure of the json, ndjson and SQLite outputs.
+pub const SCHEMA_VERSION: u32 = 1;
+
+fn time() -> JsonValue {
+    object! {
+        "type": "string",
+        description: "UTC with milliseconds, without an offset",
+        pattern: r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d{3}$",
+    }
+}
+
+fn message() -> JsonValue {
+    object! {
+        "type": "object",
+        required: array!["id", "time", "author", "author_id", "ext_user_id", "role", "guest", "message", "words", "characters"],
+        properties: {
+            id: { "type": "string", description: "the id BigBlueButton gave the message or a hash of it, the same in every run and unique within its meeting" },
+            time: { "$ref": "#/$defs/time" },
+            author: { "type": "string" },
+            author_id: { "type": "string", description: "the internal user id" },
+            ext_user_id: { "type": ["string", "null"], description: "the external user id, e.g. the Moodle user id" },
+            role: { "type": ["string", "null"], description: "moderator or viewer" },
+            guest: { "type": "boolean" },
+            message: { "type": "string" },
+            words: { "type": "integer", minimum: 0 },
+            characters: { "type": "integer", minimum: 0 },
+            score: { "type": "number", description: "with --score-cmd" },
+            language: { "type": "string", description: "the ISO 639-3 code, with --detect-language" },
+            raw: { "type": "string", description: "the log line, with --include-raw" },
+            oversized_bytes: { "type": "integer", minimum: 1, description: "the size of a text longer than --max-message-bytes, with --oversized flag" },
+            segment: { "type": "integer", minimum: 1, description: "the number of the conversation in the chat, with --segment-gap or --segment-marker" },
+            mentions: {
+                "type": "array",
+                description: "the participants addressed with @Name",
+                items: {
+                    "type": "object",
+                    required: array!["user_id", "name", "ext_user_id"],
+                    properties: {
+                        user_id: { "type": "string" },
+                        name: { "type": "string" },
+                        ext_user_id: { "type": ["string", "null"] },
+                    },
+                },
+            },
+            withheld: { "const": true, description: "the message is empty because the text was left out with --no-bodies" },
+        },
+    }
+}
+
+fn meeting() -> JsonValue {
+    object! {
+        "type": "object",
+        required: array!["schema_version", "meeting_id", "time", "chats"],
+        properties: {
+            schema_version: { "const": SCHEMA_VERSION },
+            meeting_id: { "type": "string" },
+            handle: { "type": "string", description: "the day the meeting started and a hash of its id, e.g. 2021-03-01-4f1c9a" },
+            time: { "$ref": "#/$defs/time" },
+            chats: {
+                "type": "array",
+                items: {
+                    "type": "object",
+                    required: array!["chat_id", "messages"],
+                    properties: {
+                        chat_id: { "type": "string" },
+                        messages: { "type": "array", items: { "$ref": "#/$defs/message" } },
+                    },
+                },
+            },
+            name: { "type": "string", description: "from the BigBlueButton API" },
+            metadata: { "type": "object", additionalProperties: { "type": "string" } },
+            playback_url: { "type": "string" },
+            tenant: { "type": "string" },
+            welcome: { "type": "string", description: "the welcome message of the chat the meeting was created with" },
+            moderator_message: { "type": "string", description: "the welcome message only the moderators saw" },
+            languages: {
+                "type": "object",
+                description: "the share of the messages in every language, with --detect-language",
+                additionalProperties: { "type": "number" },
+            },
+            public_chat_locks: {
+                "type": "array",
+                items: {
+                    "type": "object",
+                    required: array!["time", "locked"],
+                    properties: {
+                        time: { "$ref": "#/$defs/time" },
+                        locked: { "type": "boolean" },
+                    },
+                },
+            },
+            provenance: {
+                "type": "object",
+                description: "where the export comes from, with --provenance",
+                required: array!["generator", "arguments", "filters", "inputs"],
+                properties: {
+                    generator: { "type": "string", description: "chatter and its version" },
+                    arguments: { "type": "array", items: { "type": "string" }, description: "the command line, the values of secrets are ***" },
+                    filters: { "type": "array", items: { "type": "string" }, description: "the options of the command line that select or change the messages" },
+                    inputs: {
+                        "type": "array",
+                        items: {
+                            "type": "object",
+                            required: array!["path"],
+                            properties: {
+                                path: { "type": "string", description: "- for standard in" },
+                                bytes: { "type": "integer", minimum: 0 },
+                                sha256: { "type": "string", description: "of a local file as it was read" },
+                            },
+                        },
+                    },
+                    created: { "$ref": "#/$defs/time", description: "the time of the run, not with --deterministic" },
+                },
+            },
+            public_chat_clears: {
+                "type": "array",
+                description: "the times a moderator cleared the public chat",
+                items: {
+                    "type": "object",
+                    required: array!["time", "user_id", "name", "ext_user_id"],
+                    properties: {
+                        time: { "$ref": "#/$defs/time" },
+                        user_id: { "type": "string" },
+                        name: { "type": "string" },
+                        ext_user_id: { "type": ["string", "null"] },
+                    },
+                },
+            },
+        },
+    }
+}
+
+/// The schema of the json export, or of one line of the ndjson export.
+pub fn schema(format: Format) -> JsonValue {
+    let title = if format == Format::Ndjson {
+        "chatter ndjson export line"
+    } else {
+        "chatter json export"
+    };
+    let mut schema = object! {
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        title: title,
+        "$defs": {
+            time: time(),
+            message: message(),
+        },
+    };
+    if format == Format::Ndjson {
+        schema["$ref"] = "#/$defs/message".into();
+        schema["required"] = array!["schema_version", "meeting_id", "chat_id"];
+        schema["properties"] = object! {
+            schema_version: { "const": SCHEMA_VERSION },
+            meeting_id: { "type": "string" },
+            chat_id: { "type": "string" },
+        };
+    } else {
+        schema["$defs"]["meeting"] = meeting();
+        schema["type"] = "array".into();
+        schema["items"] = object! { "$ref": "#/$defs/meeting" };
+    }
+    schema
+}
+
+/// Print the schema of the json export, or of the ndjson export with `--format ndjson`.
+pub fn print<W: Write>(out: &mut W, format: Format) -> io::Result<()> {
+    schema(format).write_pretty(out, 2)?;
+    writeln!(out)
+}
@@ -0,0 +1,89 @@
+//! Scores of the messages from an external command, e.g. a toxicity or sentiment model, so no model has to be part of chatter.
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use crate::{
+    model::{Message, Score},
+    transform::MessageTransform,
+};
+
+/// The running score command: it gets one message per line on its standard in and answers every line with a number on its own line.
+pub struct Scorer {
+    command: String,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Scorer {
+    /// Start the shell command.
+    pub fn spawn(command: &str) -> io::Result<Scorer> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        Ok(Scorer {
+            command: command.to_string(),
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    /// Score one text, its line breaks are sent as spaces.
+    pub fn score(&mut self, text: &str) -> io::Result<f64> {
+        let line: Vec<&str> = text.lines().collect();
+        writeln!(self.stdin, "{}", line.join(" "))?;
+        self.stdin.flush()?;
+        let mut answer = String::new();
+        if self.stdout.read_line(&mut answer)? == 0 {
+            return Err(io::Error::other(format!(
+                "{} ended without a score for: {}",
+                self.command, text
+            )));
+        }
+        answer
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|score| score.is_finite())
+            .ok_or_else(|| {
+                io::Error::other(format!(
+                    "{} answered {:?} instead of a score for: {}",
+                    self.command,
+                    answer.trim(),
+                    text
+                ))
+            })
+    }
+}
+
+impl Drop for Scorer {
+    fn drop(&mut self) {
+        // the command would wait for more lines
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl MessageTransform for Scorer {
+    fn message(&mut self, msg: &mut Message) -> io::Result<bool> {
+        msg.score = Some(Score(self.score(&msg.lines().join("\n"))?));
+        Ok(true)
+    }
+}
+
+/// Drop the messages with a lower score, after the [`Scorer`].
+pub struct MinScore(pub f64);
+
+impl MessageTransform for MinScore {
+    fn message(&mut self, msg: &mut Message) -> io::Result<bool> {
+        Ok(msg.score.is_some_and(|score| score.0 >= self.0))
+    }
+}
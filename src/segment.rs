@@ -0,0 +1,40 @@
+//! Conversation segments: long chats, e.g. of office hours, split at the pauses and at topic markers so they can be read one conversation at a time.
+use std::collections::HashMap;
+
+use chrono::Duration;
+use regex::Regex;
+
+use crate::model::Meeting;
+
+/// Where a chat is split.
+pub struct Segmenter {
+    /// A pause longer than this starts a new segment.
+    pub gap: Option<Duration>,
+    /// A message matching this starts a new segment, e.g. `^#topic`.
+    pub marker: Option<Regex>,
+}
+
+impl Segmenter {
+    /// Number the segments of every chat from 1, the messages have to be sorted by their time.
+    pub fn assign(&self, meetings: &mut HashMap<String, Meeting>) {
+        for chat in meetings.values_mut().flat_map(|m| m.chats.values_mut()) {
+            let mut segment = 0;
+            let mut last = None;
+            for msg in &mut chat.messages {
+                let pause = match (last, self.gap) {
+                    (Some(last), Some(gap)) => msg.time - last > gap,
+                    _ => false,
+                };
+                let marked = self
+                    .marker
+                    .as_ref()
+                    .is_some_and(|marker| marker.is_match(&msg.message));
+                if last.is_none() || pause || marked {
+                    segment += 1;
+                }
+                msg.segment = Some(segment);
+                last = Some(msg.time);
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Stopping the modes that run until they are stopped cleanly on SIGINT (Ctrl-C) and SIGTERM (e.g. by systemd), and reloading their files on SIGHUP.
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use signal_hook::{consts::TERM_SIGNALS, flag};
+
+/// Whether a signal asked to stop, checked between the lines that are read.
+pub struct Shutdown(Arc<AtomicBool>);
+
+impl Shutdown {
+    /// Catch the signals that end the process, a second one while stopping ends it right away.
+    pub fn register() -> io::Result<Shutdown> {
+        let requested = Arc::new(AtomicBool::new(false));
+        for &signal in TERM_SIGNALS {
+            // registered first so it only fires once the flag is set
+            flag::register_conditional_shutdown(signal, 1, Arc::clone(&requested))?;
+            flag::register(signal, Arc::clone(&requested))?;
+        }
+        Ok(Shutdown(requested))
+    }
+
+    pub fn requested(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Whether a SIGHUP asked to read the files given on the command line again, checked like [`Shutdown`].
+pub struct Reload(Arc<AtomicBool>);
+
+impl Reload {
+    pub fn register() -> io::Result<Reload> {
+        let requested = Arc::new(AtomicBool::new(false));
+        #[cfg(unix)]
+        flag::register(signal_hook::consts::SIGHUP, Arc::clone(&requested))?;
+        Ok(Reload(requested))
+    }
+
+    /// Whether a SIGHUP arrived since the last call, several of them are one reload.
+    pub fn requested(&self) -> bool {
+        self.0.swap(false, Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,147 @@
+//! Collapsing floods of repeated messages in the transcripts.
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::model::{Chat, Message, Repeated};
+
+/// A message has to be repeated at least this often to be collapsed.
+const MIN_REPEATS: usize = 3;
+
+/// The maximal time between two repetitions that still counts as the same flood.
+fn window() -> Duration {
+    Duration::minutes(2)
+}
+
+/// Messages that only differ in case, punctuation or whitespace are considered the same.
+fn normalize(message: &str) -> String {
+    message
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Replace every flood of near-identical messages of one author by its first message with the number of repetitions.
+pub fn collapse(chat: &mut Chat) {
+    // the flood each message belongs to
+    let mut floods: Vec<usize> = Vec::with_capacity(chat.messages.len());
+    let mut sizes: Vec<usize> = Vec::new();
+    // the current flood of each author and the text and time of its last message.
+    let mut current: HashMap<&str, (usize, String, NaiveDateTime)> = HashMap::new();
+    for msg in &chat.messages {
+        let text = normalize(&msg.message);
+        let flood = match current.get_mut(msg.author_id.as_str()) {
+            Some((flood, last_text, last_time))
+                if *last_text == text && msg.time - *last_time <= window() =>
+            {
+                *last_time = msg.time;
+                *flood
+            }
+            _ => {
+                sizes.push(0);
+                current.insert(&msg.author_id, (sizes.len() - 1, text, msg.time));
+                sizes.len() - 1
+            }
+        };
+        sizes[flood] += 1;
+        floods.push(flood);
+    }
+
+    let mut first: HashMap<usize, usize> = HashMap::new();
+    let mut kept = Vec::with_capacity(chat.messages.len());
+    for (msg, flood) in chat.messages.drain(..).zip(floods) {
+        if sizes[flood] < MIN_REPEATS {
+            kept.push(msg);
+        } else if let Some(&index) = first.get(&flood) {
+            let first: &mut Message = &mut kept[index];
+            if let Some(repeated) = &mut first.repeated {
+                repeated.last = msg.time;
+            }
+        } else {
+            first.insert(flood, kept.len());
+            let mut msg = msg;
+            msg.repeated = Some(Repeated {
+                count: sizes[flood],
+                last: msg.time,
+            });
+            kept.push(msg);
+        }
+    }
+    chat.messages = kept;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn chat(messages: &[(&str, &str, i64)]) -> Chat {
+        let start = NaiveDate::from_ymd(2021, 3, 1).and_hms(10, 0, 0);
+        Chat {
+            chat_id: "chat".to_string(),
+            messages: messages
+                .iter()
+                .map(|&(author, text, seconds)| {
+                    Message::example(author, author, text, start + Duration::seconds(seconds))
+                })
+                .collect(),
+        }
+    }
+
+    fn counts(chat: &Chat) -> Vec<(&str, usize)> {
+        chat.messages
+            .iter()
+            .map(|m| {
+                (
+                    m.message.as_str(),
+                    m.repeated.as_ref().map_or(1, |r| r.count),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_flood_at_the_threshold_is_collapsed() {
+        let mut flood = chat(&[
+            ("a", "Hello!", 0),
+            ("a", "hello", 30),
+            ("a", "HELLO !!", 60),
+        ]);
+        collapse(&mut flood);
+        assert_eq!(counts(&flood), vec![("Hello!", 3)]);
+        let repeated = flood.messages[0].repeated.as_ref().unwrap();
+        assert_eq!(
+            repeated.last,
+            flood.messages[0].time + Duration::seconds(60)
+        );
+    }
+
+    #[test]
+    fn fewer_repetitions_are_kept() {
+        let mut chat = chat(&[("a", "hello", 0), ("a", "hello", 30), ("a", "bye", 60)]);
+        collapse(&mut chat);
+        assert_eq!(counts(&chat), vec![("hello", 1), ("hello", 1), ("bye", 1)]);
+    }
+
+    #[test]
+    fn a_pause_longer_than_the_window_ends_the_flood() {
+        let mut chat = chat(&[("a", "hi", 0), ("a", "hi", 60), ("a", "hi", 300)]);
+        collapse(&mut chat);
+        assert_eq!(counts(&chat), vec![("hi", 1), ("hi", 1), ("hi", 1)]);
+    }
+
+    #[test]
+    fn floods_are_per_author() {
+        let mut chat = chat(&[
+            ("a", "hi", 0),
+            ("b", "hi", 1),
+            ("a", "hi", 2),
+            ("b", "other", 3),
+            ("a", "hi", 4),
+        ]);
+        collapse(&mut chat);
+        assert_eq!(counts(&chat), vec![("hi", 3), ("hi", 1), ("other", 1)]);
+    }
+}
@@ -0,0 +1,225 @@
+//! Meetings moved out of memory while the logs are read, for archives whose chats do not fit into memory at once.
+//!
+//! With `--max-memory` every log line of a meeting is also written to a temporary SQLite database. When the meetings in memory get too large the ones that went quiet the longest ago, normally the ones that ended, are dropped from memory and their later lines only go to the database. For the output the meetings are read again from their lines in batches that fit into memory.
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::PathBuf,
+};
+
+use chrono::NaiveDateTime;
+use rusqlite::Connection;
+
+use crate::{collect::Collector, model::Meeting};
+
+fn to_io(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+/// What is known about a meeting without having it in memory.
+struct Entry {
+    /// The size of its log lines, an estimate of the memory it takes.
+    size: usize,
+    /// Its earliest event so far, to write the meetings in their order.
+    start: NaiveDateTime,
+    /// Its latest event so far.
+    last: NaiveDateTime,
+    /// Only its lines in the database are left.
+    spilled: bool,
+}
+
+/// The temporary database with the log lines of all the meetings.
+pub struct Spill {
+    path: PathBuf,
+    db: Connection,
+    max_memory: usize,
+    /// The estimated size of the meetings in memory.
+    in_memory: usize,
+    entries: HashMap<String, Entry>,
+}
+
+impl Spill {
+    /// Create the database in the temporary directory, the meetings in memory may take about `max_memory` bytes.
+    pub fn new(max_memory: usize) -> io::Result<Spill> {
+        let path =
+            std::env::temp_dir().join(format!("chatter-spill-{}.sqlite", std::process::id()));
+        // the database of a run that crashed or was killed may be left, a later run with the same process id (e.g. in a container) must not read its lines
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let db = Connection::open(&path).map_err(to_io)?;
+        // the database is thrown away at the end, it does not need to survive a crash
+        db.execute_batch(
+            "PRAGMA journal_mode = OFF;
+            PRAGMA synchronous = OFF;
+            CREATE TABLE lines (meeting_id TEXT, tenant TEXT, line TEXT);
+            CREATE INDEX lines_meeting ON lines (meeting_id);
+            BEGIN;",
+        )
+        .map_err(to_io)?;
+        Ok(Spill {
+            path,
+            db,
+            max_memory,
+            in_memory: 0,
+            entries: HashMap::new(),
+        })
+    }
+
+    /// Keep the line of the meeting, `tenant` is the one of the file it is in. Returns whether the meeting is still in memory.
+    pub fn record(
+        &mut self,
+        meeting_id: &str,
+        time: NaiveDateTime,
+        tenant: Option<&str>,
+        line: &str,
+    ) -> io::Result<bool> {
+        self.db
+            .prepare_cached("INSERT INTO lines VALUES (?1, ?2, ?3)")
+            .and_then(|mut insert| insert.execute((meeting_id, tenant, line)))
+            .map_err(to_io)?;
+        let entry = self.entries.entry(meeting_id.to_string()).or_insert(Entry {
+            size: 0,
+            start: time,
+            last: time,
+            spilled: false,
+        });
+        entry.size += line.len();
+        entry.start = entry.start.min(time);
+        entry.last = entry.last.max(time);
+        if !entry.spilled {
+            self.in_memory += line.len();
+        }
+        Ok(!entry.spilled)
+    }
+
+    /// Drop the meetings that went quiet the longest ago from memory until they take at most half of `max_memory`, but never the one of the current line.
+    pub fn evict(&mut self, meetings: &mut HashMap<String, Meeting>, current: &str) {
+        if self.in_memory <= self.max_memory {
+            return;
+        }
+        let mut quiet: Vec<(NaiveDateTime, &String)> = self
+            .entries
+            .iter()
+            .filter(|(id, entry)| !entry.spilled && id.as_str() != current)
+            .map(|(id, entry)| (entry.last, id))
+            .collect();
+        quiet.sort();
+        let mut evicted = Vec::new();
+        for (_, meeting_id) in quiet {
+            if self.in_memory <= self.max_memory / 2 {
+                break;
+            }
+            self.in_memory -= self.entries[meeting_id].size;
+            evicted.push(meeting_id.clone());
+        }
+        for meeting_id in evicted {
+            meetings.remove(&meeting_id);
+            if let Some(entry) = self.entries.get_mut(&meeting_id) {
+                entry.spilled = true;
+            }
+        }
+    }
+
+    /// Whether any meeting had to leave the memory.
+    pub fn spilled(&self) -> bool {
+        self.entries.values().any(|entry| entry.spilled)
+    }
+
+    /// The ids of all the meetings in the order of their start, in batches that fit into `max_memory`.
+    pub fn batches(&self, meetings: &HashMap<String, Meeting>) -> Vec<Vec<String>> {
+        let start = |id: &String| match meetings.get(id) {
+            Some(meeting) => meeting.time,
+            None => self.entries[id].start,
+        };
+        let mut ids: HashSet<&String> = meetings.keys().collect();
+        ids.extend(self.entries.keys());
+        let mut ids: Vec<&String> = ids.into_iter().collect();
+        ids.sort_by(|a, b| (start(a), a).cmp(&(start(b), b)));
+        let mut batches: Vec<Vec<String>> = Vec::new();
+        let mut size = 0;
+        for id in ids {
+            let meeting_size = self.entries.get(id).map_or(0, |entry| entry.size);
+            if batches.is_empty() || size + meeting_size > self.max_memory {
+                batches.push(Vec::new());
+                size = 0;
+            }
+            size += meeting_size;
+            batches.last_mut().expect("a batch").push(id.clone());
+        }
+        batches
+    }
+
+    /// Take the meetings of a batch out of the collector or read them again from their lines.
+    ///
+    /// The collector must not spill any more and its clock must not report the skews again.
+    pub fn load(
+        &self,
+        collector: &mut Collector,
+        batch: &[String],
+    ) -> io::Result<HashMap<String, Meeting>> {
+        let mut select = self
+            .db
+            .prepare_cached("SELECT tenant, line FROM lines WHERE meeting_id = ?1 ORDER BY rowid")
+            .map_err(to_io)?;
+        for meeting_id in batch {
+            let entry = match self.entries.get(meeting_id) {
+                Some(entry) if entry.spilled => entry,
+                _ => continue,
+            };
+            // known already, so it is not announced again
+            collector.meetings.insert(
+                meeting_id.clone(),
+                Meeting::new(meeting_id.clone(), entry.start),
+            );
+            let mut rows = select.query([meeting_id]).map_err(to_io)?;
+            while let Some(row) = rows.next().map_err(to_io)? {
+                collector.source_tenant = row.get(0).map_err(to_io)?;
+                let line: String = row.get(1).map_err(to_io)?;
+                collector.line(&line);
+            }
+        }
+        let mut meetings = HashMap::new();
+        for meeting_id in batch {
+            if let Some(mut meeting) = collector.meetings.remove(meeting_id) {
+                collector.assign_audio_to(&mut meeting);
+                meetings.insert(meeting_id.clone(), meeting);
+            }
+        }
+        Ok(meetings)
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn a_database_left_by_an_earlier_run_is_not_read() {
+        let time = NaiveDate::from_ymd(2021, 3, 1).and_hms(10, 0, 0);
+        let mut killed = Spill::new(0).unwrap();
+        killed.record("m", time, None, "old line").unwrap();
+        killed.db.execute_batch("COMMIT;").unwrap();
+        // like a run that was killed, the database is not removed
+        std::mem::forget(killed);
+        let spill = Spill::new(0).unwrap();
+        let lines: i64 = spill
+            .db
+            .query_row("SELECT COUNT(*) FROM lines", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(lines, 0);
+    }
+}
@@ -0,0 +1,724 @@
+//! Splitting the output into several files.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{self, BufWriter, Cursor, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Condvar, Mutex,
+    },
+    thread,
+};
+
+use json::JsonValue;
+use sha2::{Digest, Sha256};
+use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipWriter};
+
+use crate::{
+    checksum::{self, Hashing},
+    cli::{Format, Options},
+    compress::Output,
+    document::escape_html,
+    model::{self, Meeting},
+    output, provenance,
+    recording::Recordings,
+};
+
+/// How the output is split into files.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SplitBy {
+    /// One file per participant with all their messages of all meetings.
+    Author,
+    /// One file per meeting.
+    Meeting,
+    /// One file per calendar month with the meetings that started in it, in a directory per year.
+    Month,
+    /// One file per ISO week, in a directory per (ISO) year.
+    Week,
+    /// One file per day, in a directory per year.
+    Day,
+}
+
+impl FromStr for SplitBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "author" => Ok(SplitBy::Author),
+            "meeting" => Ok(SplitBy::Meeting),
+            "month" => Ok(SplitBy::Month),
+            "week" => Ok(SplitBy::Week),
+            "day" => Ok(SplitBy::Day),
+            _ => Err(format!("can not split by {}", s)),
+        }
+    }
+}
+
+/// Replace everything that could cause trouble in a file name.
+pub fn file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    name.trim_start_matches('.').to_string()
+}
+
+/// Divide the meetings into the parts that go into separate files keyed by the file name (without the extension), the calendar periods are paths like `2024/2024-03`.
+pub fn split(
+    meetings: &HashMap<String, Meeting>,
+    by: SplitBy,
+) -> BTreeMap<String, HashMap<String, Meeting>> {
+    match by {
+        SplitBy::Author => by_author(meetings),
+        SplitBy::Meeting => meetings
+            .values()
+            .map(|meeting| {
+                let name = format!(
+                    "{}_{}",
+                    meeting.time.format("%Y-%m-%d_%H-%M"),
+                    meeting.meeting_id
+                );
+                let part = HashMap::from([(meeting.meeting_id.clone(), meeting.clone())]);
+                (file_name(&name), part)
+            })
+            .collect(),
+        SplitBy::Month | SplitBy::Week | SplitBy::Day => {
+            let period = match by {
+                SplitBy::Month => "%Y/%Y-%m",
+                SplitBy::Week => "%G/%G-W%V",
+                _ => "%Y/%Y-%m-%d",
+            };
+            let mut parts: BTreeMap<String, HashMap<String, Meeting>> = BTreeMap::new();
+            for meeting in meetings.values() {
+                parts
+                    .entry(meeting.time.format(period).to_string())
+                    .or_default()
+                    .insert(meeting.meeting_id.clone(), meeting.clone());
+            }
+            parts
+        }
+    }
+}
+
+/// The internal user ids change with every join so authors are identified by their external id or their name.
+fn by_author(meetings: &HashMap<String, Meeting>) -> BTreeMap<String, HashMap<String, Meeting>> {
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut parts: BTreeMap<String, HashMap<String, Meeting>> = BTreeMap::new();
+    for meeting in model::sorted(meetings) {
+        for chat in meeting.sorted_chats() {
+            for msg in &chat.messages {
+                let participant = match &msg.ext_id {
+                    Some(ext_id) => format!("ext:{}", ext_id),
+                    None => format!("name:{}", msg.author),
+                };
+                let name = names
+                    .entry(participant)
+                    .or_insert_with(|| match &msg.ext_id {
+                        Some(ext_id) => file_name(&format!("{}-{}", msg.author, ext_id)),
+                        None => file_name(&msg.author),
+                    });
+                let part = parts
+                    .entry(name.clone())
+                    .or_default()
+                    .entry(meeting.meeting_id.clone())
+                    .or_insert_with(|| meeting.without_chats());
+                part.chats
+                    .entry(chat.chat_id.clone())
+                    .or_insert_with(|| chat.without_messages())
+                    .messages
+                    .push(msg.clone());
+            }
+        }
+    }
+    parts
+}
+
+/// The name of the file in the output directory that records which files have been completely written.
+const JOURNAL: &str = ".chatter-journal";
+
+/// Records the completely written files of an output directory so an interrupted run can be resumed.
+///
+/// Every line is `name<TAB>size<TAB>fingerprint`, the fingerprint describes the content the file was rendered from.
+pub struct Journal {
+    done: HashMap<String, (u64, String)>,
+    file: File,
+}
+
+impl Journal {
+    fn open(dir: &Path) -> io::Result<Journal> {
+        let path = dir.join(JOURNAL);
+        let mut done = HashMap::new();
+        if let Ok(content) = fs::read_to_string(&path) {
+            for line in content.lines() {
+                let mut fields = line.splitn(3, '\t');
+                if let (Some(name), Some(Ok(size)), Some(fingerprint)) =
+                    (fields.next(), fields.next().map(str::parse), fields.next())
+                {
+                    done.insert(name.to_string(), (size, fingerprint.to_string()));
+                }
+            }
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Journal { done, file })
+    }
+}
+
+/// A hash of what a part is rendered from - if it changes the file has to be rendered again.
+///
+/// The filters, `--anonymize-key` and `--no-bodies` change the meetings themselves, so the authors, ids and texts are hashed together with the options that change how the same meetings are written.
+fn fingerprint(options: &Options, meetings: &HashMap<String, Meeting>) -> String {
+    let mut hasher = Sha256::new();
+    let rendering = format!(
+        "{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{}|{}|{}|{:?}",
+        options.format,
+        options.time_display,
+        options.header_fields,
+        options.html,
+        options.compress,
+        options.recording_base_url,
+        options.include_raw,
+        options.deterministic,
+        options.provenance,
+        options.split_by,
+    );
+    hasher.update(rendering.as_bytes());
+    let mut field = |value: &str| {
+        hasher.update(value.as_bytes());
+        hasher.update([0]);
+    };
+    for meeting in model::sorted(meetings) {
+        field(&meeting.meeting_id);
+        field(meeting.name.as_deref().unwrap_or_default());
+        field(meeting.playback_url.as_deref().unwrap_or_default());
+        for user in meeting.sorted_users() {
+            field(&user.user_id);
+            field(&user.name);
+            field(user.ext_id.as_deref().unwrap_or_default());
+        }
+        let mut chats: Vec<_> = meeting.chats.values().collect();
+        chats.sort_by(|a, b| a.chat_id.cmp(&b.chat_id));
+        for chat in chats {
+            field(&chat.chat_id);
+            for msg in &chat.messages {
+                field(&msg.id);
+                field(&msg.time.timestamp_millis().to_string());
+                field(&msg.author);
+                field(&msg.author_id);
+                field(msg.ext_id.as_deref().unwrap_or_default());
+                field(&msg.message);
+                field(&format!(
+                    "{}|{:?}|{:?}|{:?}",
+                    msg.withheld.is_some(),
+                    msg.segment,
+                    msg.score,
+                    msg.language
+                ));
+            }
+        }
+    }
+    checksum::hex(&hasher.finalize())
+}
+
+/// The options of the entries of the archives. Nothing depends on the time or the platform of the run, so the same content gives the same archive.
+pub fn entry_options() -> SimpleFileOptions {
+    SimpleFileOptions::default()
+        .last_modified_time(DateTime::default())
+        .unix_permissions(0o644)
+}
+
+/// A ZIP in memory that starts with the uncompressed `mimetype`, so an EPUB or OpenDocument file is recognized by its first bytes.
+///
+/// A ZIP has to be completed before it can be written to a stream, so these documents are put together in memory first.
+pub fn package(mimetype: &str) -> io::Result<ZipWriter<Cursor<Vec<u8>>>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file(
+        "mimetype",
+        entry_options().compression_method(CompressionMethod::Stored),
+    )?;
+    zip.write_all(mimetype.as_bytes())?;
+    Ok(zip)
+}
+
+/// Remove the files that were not completely written by an interrupted run, also in the directories of the years.
+fn remove_partial(dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if path.is_dir() {
+            remove_partial(&path)?;
+        } else if name.starts_with('.') && name.ends_with(".tmp") {
+            eprintln!("removing the partially written {}", path.display());
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Where the files of a split output are written to.
+pub enum Target {
+    /// Plain files in a directory.
+    Dir(PathBuf, Journal),
+    /// Entries of a ZIP archive.
+    Archive(Box<ZipWriter<File>>),
+}
+
+impl Target {
+    /// The target given on the command line, the archive wins over the directory.
+    pub fn create(options: &Options) -> io::Result<Target> {
+        match (&options.archive, &options.output_dir) {
+            (Some(archive), _) => Ok(Target::Archive(Box::new(ZipWriter::new(File::create(
+                archive,
+            )?)))),
+            (None, Some(dir)) => {
+                fs::create_dir_all(dir)?;
+                remove_partial(dir)?;
+                if !options.resume {
+                    // a new run - the old journal does not describe the files anymore
+                    let _ = fs::remove_file(dir.join(JOURNAL));
+                }
+                let journal = Journal::open(dir)?;
+                Ok(Target::Dir(dir.clone(), journal))
+            }
+            (None, None) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "neither an archive nor an output directory is given",
+            )),
+        }
+    }
+
+    /// Check if a file has been completely written from the same content by an earlier run.
+    pub fn is_done(&self, name: &str, fingerprint: &str) -> bool {
+        match self {
+            Target::Dir(dir, journal) => journal.done.get(name).is_some_and(|(size, done)| {
+                done == fingerprint
+                    && fs::metadata(dir.join(name)).is_ok_and(|meta| meta.len() == *size)
+            }),
+            Target::Archive(_) => false,
+        }
+    }
+
+    /// The checksum of a file an earlier run has written.
+    pub fn checksum_of_done(&self, name: &str) -> io::Result<String> {
+        match self {
+            Target::Dir(dir, _) => checksum::of_file(&dir.join(name)),
+            Target::Archive(_) => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "archives are always written completely",
+            )),
+        }
+    }
+
+    /// Write one file and return its checksum.
+    ///
+    /// In a directory the file is written under a temporary name and only renamed and recorded in the journal once it is complete.
+    pub fn file(
+        &mut self,
+        name: &str,
+        fingerprint: &str,
+        write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+    ) -> io::Result<String> {
+        match self {
+            Target::Dir(dir, journal) => {
+                let path = dir.join(name);
+                let file_name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let tmp = path.with_file_name(format!(".{}.tmp", file_name));
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let mut out = BufWriter::new(File::create(&tmp)?);
+                let mut hashing = Hashing::new(&mut out);
+                write(&mut hashing)?;
+                let sum = hashing.digest();
+                let file = out.into_inner().map_err(|e| e.into_error())?;
+                file.sync_all()?;
+                fs::rename(&tmp, &path)?;
+                let size = fs::metadata(&path)?.len();
+                writeln!(journal.file, "{}\t{}\t{}", name, size, fingerprint)?;
+                journal
+                    .done
+                    .insert(name.to_string(), (size, fingerprint.to_string()));
+                Ok(sum)
+            }
+            Target::Archive(zip) => {
+                zip.start_file(name, entry_options())?;
+                let mut hashing = Hashing::new(zip.as_mut());
+                write(&mut hashing)?;
+                Ok(hashing.digest())
+            }
+        }
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        if let Target::Archive(zip) = self {
+            zip.finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// An overview of the files of an archive, so it can be handed over to people that just want to open the right file.
+fn write_index<W: Write>(
+    out: &mut W,
+    files: &[(String, &HashMap<String, Meeting>)],
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Chats</title>\n</head>\n<body>\n<table>\n<tr><th>File</th><th>Meetings</th><th>Messages</th></tr>"
+    )?;
+    for (name, meetings) in files {
+        let mut times: Vec<_> = meetings.values().map(|m| m.time).collect();
+        times.sort();
+        let messages: usize = meetings
+            .values()
+            .flat_map(|m| m.chats.values())
+            .map(|c| c.messages.len())
+            .sum();
+        let times: Vec<String> = times
+            .iter()
+            .map(|t| t.format("%d.%m.%Y %H:%M").to_string())
+            .collect();
+        writeln!(
+            out,
+            "<tr><td><a href=\"{0}\">{0}</a></td><td>{1}</td><td>{2}</td></tr>",
+            escape_html(name),
+            times.join(", "),
+            messages
+        )?;
+    }
+    writeln!(out, "</table>\n</body>\n</html>")
+}
+
+/// The name of the file of a part, with the extension of the format and of the compression.
+fn part_file(options: &Options, name: &str) -> String {
+    let name = format!("{}.{}", name, options.format.extension());
+    match options.compress {
+        Some(compression) => format!("{}.{}", name, compression.extension()),
+        None => name,
+    }
+}
+
+/// Write one part in the format of the command line, compressed if asked to.
+fn write_part(
+    out: &mut dyn Write,
+    options: &Options,
+    by: SplitBy,
+    part: &HashMap<String, Meeting>,
+    recordings: Option<&Recordings>,
+    provenance: Option<&JsonValue>,
+) -> io::Result<()> {
+    let mut out = Output::wrap(out, options.compress)?;
+    // the messages of one author are spread over many meetings so every line carries its context.
+    if by == SplitBy::Author && options.format == Format::Text {
+        output::print_lines(&mut out, options.time_display, part)?;
+    } else {
+        output::render(&mut out, options, part, recordings, provenance)?;
+    }
+    out.finish()
+}
+
+/// The files [`write`] would write and their sizes, without writing anything.
+pub fn plan(
+    options: &Options,
+    by: SplitBy,
+    meetings: &HashMap<String, Meeting>,
+    recordings: Option<&Recordings>,
+    provenance: Option<&JsonValue>,
+) -> io::Result<Vec<(String, u64)>> {
+    let parts = split(meetings, by);
+    let mut files = Vec::new();
+    let mut index = Vec::new();
+    for (name, part) in &parts {
+        let mut counter = Counter::default();
+        write_part(&mut counter, options, by, part, recordings, provenance)?;
+        files.push((part_file(options, name), counter.0));
+        index.push((part_file(options, name), part));
+    }
+    if options.archive.is_some() {
+        let mut counter = Counter::default();
+        write_index(&mut counter, &index)?;
+        files.push(("index.html".to_string(), counter.0));
+    }
+    if let Some(provenance) = provenance {
+        files.push((
+            provenance::FILE.to_string(),
+            provenance.pretty(2).len() as u64 + 1,
+        ));
+    }
+    if options.checksum.is_some() {
+        // a hex digest, two spaces and the name per line
+        let sums = files
+            .iter()
+            .map(|(name, _)| 64 + 2 + name.len() as u64 + 1)
+            .sum();
+        files.push((checksum::SUMS.to_string(), sums));
+    }
+    Ok(files)
+}
+
+/// Counts the bytes written to it and drops them.
+#[derive(Default)]
+pub struct Counter(pub u64);
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// How many files are rendered at the same time, by default one per processor.
+fn jobs(options: &Options) -> usize {
+    options
+        .jobs
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+}
+
+/// Render the parts on `jobs` threads and hand them to `write` in their order.
+///
+/// The rendering runs at most a few files ahead of the writing so the rendered files waiting for a slow one do not fill the memory.
+fn render_in_order(
+    options: &Options,
+    by: SplitBy,
+    parts: &[&HashMap<String, Meeting>],
+    recordings: Option<&Recordings>,
+    provenance: Option<&JsonValue>,
+    mut write: impl FnMut(usize, Vec<u8>) -> io::Result<()>,
+) -> io::Result<()> {
+    let jobs = jobs(options).min(parts.len()).max(1);
+    let window = 2 * jobs;
+    let next = AtomicUsize::new(0);
+    let written = (Mutex::new(0), Condvar::new());
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (next, written, stop) = (&next, &written, &stop);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= parts.len() {
+                    return;
+                }
+                let (lock, ahead) = written;
+                let mut done = lock.lock().expect("no thread panics");
+                while i >= *done + window && !stop.load(Ordering::Relaxed) {
+                    done = ahead.wait(done).expect("no thread panics");
+                }
+                drop(done);
+                if stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                let mut rendered = Vec::new();
+                let result =
+                    write_part(&mut rendered, options, by, parts[i], recordings, provenance)
+                        .map(|()| rendered);
+                if sender.send((i, result)).is_err() {
+                    return;
+                }
+            });
+        }
+        drop(sender);
+        let mut rendered = BTreeMap::new();
+        let mut result = Ok(());
+        let mut count = 0;
+        while count < parts.len() {
+            let (i, part) = match receiver.recv() {
+                Ok(part) => part,
+                Err(_) => break,
+            };
+            rendered.insert(i, part);
+            while let Some(part) = rendered.remove(&count) {
+                result = part.and_then(|bytes| write(count, bytes));
+                if result.is_err() {
+                    break;
+                }
+                count += 1;
+                *written.0.lock().expect("no thread panics") = count;
+                written.1.notify_all();
+            }
+            if result.is_err() {
+                break;
+            }
+        }
+        // the threads that wait for the writer end without rendering their files
+        stop.store(true, Ordering::Relaxed);
+        written.1.notify_all();
+        result
+    })
+}
+
+/// Write the meetings split into several files as given on the command line.
+///
+/// The files are rendered in parallel with `--jobs` but written one after the other in the order of their names, so the journal, the archive and the checksums do not depend on which thread was faster.
+pub fn write(
+    options: &Options,
+    by: SplitBy,
+    meetings: &HashMap<String, Meeting>,
+    recordings: Option<&Recordings>,
+    provenance: Option<&JsonValue>,
+) -> io::Result<()> {
+    let mut target = Target::create(options)?;
+    let parts = split(meetings, by);
+    let mut files = Vec::new();
+    let mut pending = Vec::new();
+    let mut skipped = 0;
+    for (name, part) in &parts {
+        let name = part_file(options, name);
+        let fingerprint = fingerprint(options, part);
+        if options.resume && target.is_done(&name, &fingerprint) {
+            skipped += 1;
+        } else {
+            pending.push((name.clone(), fingerprint, part));
+        }
+        files.push((name, part));
+    }
+    let total = pending.len();
+    let mut written = HashMap::new();
+    let to_render: Vec<_> = pending.iter().map(|(_, _, part)| *part).collect();
+    render_in_order(
+        options,
+        by,
+        &to_render,
+        recordings,
+        provenance,
+        |i, bytes| {
+            let (name, fingerprint, _) = &pending[i];
+            let sum = target.file(name, fingerprint, |out| out.write_all(&bytes))?;
+            if options.progress {
+                eprintln!("{}/{} {}", i + 1, total, name);
+            }
+            written.insert(name.clone(), sum);
+            Ok(())
+        },
+    )?;
+    let mut sums = Vec::new();
+    for (name, _) in &files {
+        match written.remove(name) {
+            Some(sum) => sums.push((name.clone(), sum)),
+            None if options.checksum.is_some() => {
+                sums.push((name.clone(), target.checksum_of_done(name)?))
+            }
+            None => {}
+        }
+    }
+    if skipped > 0 {
+        eprintln!("skipped {} files that were already written", skipped);
+    }
+    if let Target::Archive(_) = target {
+        let sum = target.file("index.html", "", |mut out| write_index(&mut out, &files))?;
+        sums.push(("index.html".to_string(), sum));
+    }
+    if let Some(provenance) = provenance {
+        let sum = target.file(provenance::FILE, "", |mut out| {
+            provenance.write_pretty(&mut out, 2)?;
+            writeln!(out)
+        })?;
+        sums.push((provenance::FILE.to_string(), sum));
+    }
+    checksum::finish(options, target, &sums)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::model::{Chat, Message, PUBLIC_CHAT};
+
+    fn part(text: &str) -> HashMap<String, Meeting> {
+        let time = NaiveDate::from_ymd(2021, 3, 1).and_hms(10, 0, 0);
+        let mut meeting = Meeting::new("meeting".to_string(), time);
+        meeting.chats.insert(
+            PUBLIC_CHAT.to_string(),
+            Chat {
+                chat_id: PUBLIC_CHAT.to_string(),
+                messages: vec![Message::example("w_1", "Alice", text, time)],
+            },
+        );
+        HashMap::from([("meeting".to_string(), meeting)])
+    }
+
+    /// The options of a resumed run into a fresh directory.
+    fn resume(test: &str) -> Options {
+        let dir = std::env::temp_dir().join(format!("chatter-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Options {
+            output_dir: Some(dir),
+            resume: true,
+            ..Options::default()
+        }
+    }
+
+    fn write_file(options: &Options, name: &str, fingerprint: &str) {
+        let mut target = Target::create(options).unwrap();
+        target
+            .file(name, fingerprint, |out| out.write_all(b"the transcript"))
+            .unwrap();
+        target.finish().unwrap();
+    }
+
+    #[test]
+    fn a_complete_file_of_the_same_content_is_skipped() {
+        let options = resume("skipped");
+        let fingerprint = fingerprint(&options, &part("hi"));
+        write_file(&options, "a.txt", &fingerprint);
+        let target = Target::create(&options).unwrap();
+        assert!(target.is_done("a.txt", &fingerprint));
+        assert!(!target.is_done("b.txt", &fingerprint));
+        // a new run without --resume starts over
+        let target = Target::create(&Options {
+            resume: false,
+            ..options.clone()
+        })
+        .unwrap();
+        assert!(!target.is_done("a.txt", &fingerprint));
+        fs::remove_dir_all(options.output_dir.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn a_truncated_file_is_written_again() {
+        let options = resume("truncated");
+        let fingerprint = fingerprint(&options, &part("hi"));
+        write_file(&options, "a.txt", &fingerprint);
+        let path = options.output_dir.as_ref().unwrap().join("a.txt");
+        fs::write(&path, "the").unwrap();
+        assert!(!Target::create(&options)
+            .unwrap()
+            .is_done("a.txt", &fingerprint));
+        fs::remove_dir_all(options.output_dir.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn the_fingerprint_changes_with_the_messages_and_the_rendering() {
+        let options = Options::default();
+        let before = fingerprint(&options, &part("hi"));
+        assert_eq!(fingerprint(&options, &part("hi")), before);
+        assert_ne!(fingerprint(&options, &part("hi!")), before);
+        let json = Options {
+            format: Format::Json,
+            ..Options::default()
+        };
+        assert_ne!(fingerprint(&json, &part("hi")), before);
+    }
+}
@@ -0,0 +1,691 @@
+//! The `stats` subcommand: numbers about the meetings instead of their transcripts.
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+use chrono::{Duration, NaiveDateTime, Timelike};
+
+use crate::{
+    cli::{Format, Options},
+    collation::Collation,
+    language,
+    model::{self, Meeting, Message, User},
+    table::{Table, Value},
+    tenant,
+};
+
+/// A minute only counts as a burst if it has at least this many messages, no matter how quiet the rest of the meeting was.
+const MIN_BURST_MESSAGES: usize = 10;
+/// How many standard deviations above the average rate of the meeting a minute has to be to count as a burst.
+const BURST_DEVIATIONS: f64 = 3.0;
+/// Meetings with fewer messages are not checked for authors dominating the chat.
+const MIN_MESSAGES_FOR_SHARE: usize = 10;
+
+/// The sparklines are at most this many characters long, the minutes of longer meetings are put together.
+const SPARKLINE_WIDTH: usize = 40;
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// The length of the longest bar of the histogram.
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// The messages per minute from the first to the last message as block characters, minutes without messages are blank.
+fn sparkline(meeting: &Meeting) -> String {
+    let mut per_minute: BTreeMap<NaiveDateTime, usize> = BTreeMap::new();
+    for msg in meeting.chats.values().flat_map(|c| &c.messages) {
+        *per_minute.entry(minute(msg.time)).or_insert(0) += 1;
+    }
+    let (first, last) = match (per_minute.keys().next(), per_minute.keys().next_back()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return String::new(),
+    };
+    let minutes = (last - first).num_minutes() as usize + 1;
+    let per_bucket = minutes.div_ceil(SPARKLINE_WIDTH);
+    let mut buckets = vec![0; minutes.div_ceil(per_bucket)];
+    for (time, count) in per_minute {
+        buckets[(time - first).num_minutes() as usize / per_bucket] += count;
+    }
+    let highest = buckets.iter().copied().max().unwrap_or(1);
+    buckets
+        .iter()
+        .map(|&n| match n {
+            0 => ' ',
+            n => BLOCKS[(n * BLOCKS.len() - 1) / highest],
+        })
+        .collect()
+}
+
+/// The collation the options ask for, it was checked when they were parsed.
+pub fn collation(options: &Options) -> Collation {
+    Collation::new(options.collation_locale.as_deref()).expect("checked with the options")
+}
+
+/// Print how many messages every author wrote in the meeting as a bar chart, the most active author first.
+fn print_histogram<W: Write>(
+    out: &mut W,
+    meeting: &Meeting,
+    collation: &Collation,
+) -> io::Result<()> {
+    let mut counts: Vec<(&str, usize)> = message_counts(meeting).into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| collation.compare(a.0, b.0)));
+    let highest = counts.first().map_or(1, |(_, n)| *n);
+    let width = counts
+        .iter()
+        .map(|(author, _)| author.chars().count())
+        .max()
+        .unwrap_or(0);
+    writeln!(
+        out,
+        "\nmessages per author in {} ({})",
+        meeting.meeting_id,
+        meeting.handle()
+    )?;
+    for (author, n) in counts {
+        // every author with messages gets at least a sliver
+        let bar = (n * HISTOGRAM_WIDTH).div_ceil(highest);
+        writeln!(
+            out,
+            "{:<width$} {} {}",
+            author,
+            "█".repeat(bar),
+            n,
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+/// The columns of the table the options print, for checking a `--sort-by`.
+pub fn columns(options: &Options) -> Vec<&'static str> {
+    if options.compare_room.is_some() {
+        return ROOM_COLUMNS.to_vec();
+    }
+    if options.anomalies {
+        return ANOMALY_COLUMNS.to_vec();
+    }
+    if options.participation {
+        return PARTICIPATION_COLUMNS.to_vec();
+    }
+    if options.flagged {
+        return FLAGGED_COLUMNS.to_vec();
+    }
+    if options.mentions {
+        return MENTION_COLUMNS.to_vec();
+    }
+    if options.name_collisions {
+        return NAME_COLUMNS.to_vec();
+    }
+    let mut columns = vec!["meeting", "handle", "start", "chats", "messages", "authors"];
+    if options.tenant_map.is_some() {
+        columns.push("tenant");
+    }
+    if options.detect_language {
+        columns.push("languages");
+    }
+    if options.sparklines {
+        columns.push("activity");
+    }
+    columns
+}
+
+/// Print one row per meeting with the number of chats, messages and authors.
+///
+/// With a `--tenant-map` the tenant of the meeting is added, with `--sparklines` every row ends with the activity of the meeting, with `--histogram` the messages per author of that meeting follow.
+pub fn print_stats<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    options: &Options,
+) -> io::Result<()> {
+    let mut table = Table::new(columns(options));
+    for meeting in model::sorted(meetings) {
+        let messages = meeting
+            .chats
+            .values()
+            .map(|c| c.messages.len())
+            .sum::<usize>();
+        let mut row: Vec<Value> = vec![
+            meeting.meeting_id.as_str().into(),
+            meeting.handle().into(),
+            meeting.time.format("%d.%m.%Y %H:%M").to_string().into(),
+            meeting.chats.len().into(),
+            messages.into(),
+            message_counts(meeting).len().into(),
+        ];
+        if options.tenant_map.is_some() {
+            row.push(tenant::of(meeting).into());
+        }
+        if options.detect_language {
+            row.push(language::summary(meeting).into());
+        }
+        if options.sparklines {
+            row.push(sparkline(meeting).into());
+        }
+        table.push(row);
+    }
+    table.sort(options.sort_by.as_deref(), &collation(options));
+    table.write(out, options.format)?;
+    if let Some(id) = &options.histogram {
+        match meetings.values().find(|meeting| meeting.is(id)) {
+            Some(meeting) => print_histogram(out, meeting, &collation(options))?,
+            None => eprintln!("there is no meeting {}", id),
+        }
+    }
+    Ok(())
+}
+
+/// How much the values change from one to the next: the slope of their least squares line.
+fn trend(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f64>() / n;
+    let (covariance, variance) = values
+        .iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(c, v), (x, y)| {
+            let dx = x as f64 - mean_x;
+            (c + dx * (y - mean_y), v + dx * dx)
+        });
+    if variance == 0.0 {
+        0.0
+    } else {
+        covariance / variance
+    }
+}
+
+const ROOM_COLUMNS: [&str; 7] = [
+    "session",
+    "meeting",
+    "start",
+    "messages",
+    "participants",
+    "authors",
+    "participation",
+];
+
+/// Print the sessions of a room (the meetings with the same external meeting id) one per row with the trend of their messages, participants and participation.
+///
+/// The participants are the users that joined, the participation is the share of them that wrote in the chat. The trend is only printed with the text and markdown tables.
+pub fn print_room<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    options: &Options,
+) -> io::Result<()> {
+    let external_id = options.compare_room.as_deref().unwrap_or_default();
+    let sessions: Vec<&Meeting> = model::sorted(meetings)
+        .into_iter()
+        .filter(|m| m.external_id.as_deref() == Some(external_id))
+        .collect();
+    if sessions.is_empty() {
+        eprintln!("there are no meetings of the room {}", external_id);
+        return Ok(());
+    }
+    let (mut messages, mut participants, mut participation) = (Vec::new(), Vec::new(), Vec::new());
+    let mut table = Table::new(ROOM_COLUMNS.to_vec());
+    for (number, meeting) in sessions.iter().enumerate() {
+        let count = meeting
+            .chats
+            .values()
+            .map(|c| c.messages.len())
+            .sum::<usize>();
+        let authors = message_counts(meeting).len();
+        // without join events at least the authors were there
+        let joined = meeting.users.len().max(authors);
+        let share = if joined == 0 {
+            0.0
+        } else {
+            authors as f64 * 100.0 / joined as f64
+        };
+        table.push(vec![
+            (number + 1).into(),
+            meeting.meeting_id.as_str().into(),
+            meeting.time.format("%d.%m.%Y %H:%M").to_string().into(),
+            count.into(),
+            joined.into(),
+            authors.into(),
+            Value::Percent(share),
+        ]);
+        messages.push(count as f64);
+        participants.push(joined as f64);
+        participation.push(share);
+    }
+    table.sort(options.sort_by.as_deref(), &collation(options));
+    table.write(out, options.format)?;
+    if !matches!(options.format, Format::Text | Format::Markdown) {
+        return Ok(());
+    }
+    if sessions.len() < 2 {
+        return writeln!(
+            out,
+            "
+only one session, there is no trend yet"
+        );
+    }
+    writeln!(
+        out,
+        "
+trend per session: {:+.1} messages, {:+.1} participants, {:+.1} percentage points participation",
+        trend(&messages),
+        trend(&participants),
+        trend(&participation)
+    )
+}
+
+const PARTICIPATION_COLUMNS: [&str; 7] = [
+    "participant",
+    "ext_user_id",
+    "role",
+    "meetings",
+    "messages",
+    "words",
+    "characters",
+];
+
+/// What one participant wrote over all meetings.
+#[derive(Default)]
+pub struct Participation<'a> {
+    pub name: &'a str,
+    pub ext_id: Option<&'a str>,
+    pub role: Option<&'a str>,
+    pub meetings: usize,
+    pub messages: usize,
+    pub words: usize,
+    pub characters: usize,
+}
+
+/// What every participant wrote over all meetings, sorted by their names.
+///
+/// The participants are told apart by their external user id, without one by their name. Only the messages that passed the filters count, e.g. within `--since` and `--until`.
+pub fn participation<'a>(
+    meetings: &'a HashMap<String, Meeting>,
+    collation: &Collation,
+) -> Vec<Participation<'a>> {
+    let mut participants: HashMap<&str, Participation> = HashMap::new();
+    for meeting in model::sorted(meetings) {
+        let mut seen = Vec::new();
+        for msg in meeting.chats.values().flat_map(|c| &c.messages) {
+            let key = msg.ext_id.as_deref().unwrap_or(&msg.author);
+            let participant = participants.entry(key).or_default();
+            // the latest name and role, e.g. after a rename in the external system
+            participant.name = &msg.author;
+            participant.ext_id = msg.ext_id.as_deref();
+            participant.role = msg.role.as_deref().or(participant.role);
+            if !seen.contains(&key) {
+                seen.push(key);
+                participant.meetings += 1;
+            }
+            participant.messages += 1;
+            participant.words += msg.words();
+            participant.characters += msg.characters();
+        }
+    }
+    let mut participants: Vec<Participation> = participants.into_values().collect();
+    participants.sort_by(|a, b| {
+        collation
+            .compare(a.name, b.name)
+            .then(a.ext_id.cmp(&b.ext_id))
+    });
+    participants
+}
+
+/// Print the messages, words and characters of every participant over all meetings, see [`participation`].
+pub fn print_participation<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    options: &Options,
+) -> io::Result<()> {
+    let collation = collation(options);
+    let mut table = Table::new(PARTICIPATION_COLUMNS.to_vec());
+    for p in participation(meetings, &collation) {
+        table.push(vec![
+            p.name.into(),
+            p.ext_id.unwrap_or_default().into(),
+            p.role.unwrap_or_default().to_lowercase().into(),
+            p.meetings.into(),
+            p.messages.into(),
+            p.words.into(),
+            p.characters.into(),
+        ]);
+    }
+    table.sort(options.sort_by.as_deref(), &collation);
+    table.write(out, options.format)
+}
+
+const MENTION_COLUMNS: [&str; 4] = ["author", "mentioned", "mentions", "meetings"];
+
+/// How often one participant addressed another one.
+#[derive(Default)]
+struct Addressed<'a> {
+    author: &'a str,
+    mentioned: &'a str,
+    mentions: usize,
+    meetings: usize,
+}
+
+/// Print who addresses whom with `@Name` over all meetings, the pairs with the most mentions first.
+///
+/// The participants are told apart by their external user id like for [`participation`], a participant mentioning themselves is left out.
+pub fn print_mentions<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    options: &Options,
+) -> io::Result<()> {
+    let mut pairs: HashMap<(&str, &str), Addressed> = HashMap::new();
+    for meeting in model::sorted(meetings) {
+        let mut seen = Vec::new();
+        for msg in meeting.chats.values().flat_map(|c| &c.messages) {
+            let author = msg.ext_id.as_deref().unwrap_or(&msg.author);
+            for mention in &msg.mentions {
+                let mentioned = mention.ext_id.as_deref().unwrap_or(&mention.name);
+                if mention.user_id == msg.author_id || mentioned == author {
+                    continue;
+                }
+                let pair = pairs.entry((author, mentioned)).or_default();
+                pair.author = &msg.author;
+                pair.mentioned = &mention.name;
+                pair.mentions += 1;
+                if !seen.contains(&(author, mentioned)) {
+                    seen.push((author, mentioned));
+                    pair.meetings += 1;
+                }
+            }
+        }
+    }
+    let collation = collation(options);
+    let mut pairs: Vec<Addressed> = pairs.into_values().collect();
+    pairs.sort_by(|a, b| {
+        b.mentions
+            .cmp(&a.mentions)
+            .then_with(|| collation.compare(a.author, b.author))
+            .then_with(|| collation.compare(a.mentioned, b.mentioned))
+    });
+    let mut table = Table::new(MENTION_COLUMNS.to_vec());
+    for pair in pairs {
+        table.push(vec![
+            pair.author.into(),
+            pair.mentioned.into(),
+            pair.mentions.into(),
+            pair.meetings.into(),
+        ]);
+    }
+    table.sort(options.sort_by.as_deref(), &collation);
+    table.write(out, options.format)
+}
+
+const FLAGGED_COLUMNS: [&str; 6] = ["score", "meeting", "chat", "time", "author", "message"];
+
+/// Print the scored messages with the highest score first, with `--min-score` only the flagged ones remain.
+pub fn print_flagged<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    options: &Options,
+) -> io::Result<()> {
+    let mut flagged: Vec<(f64, &Meeting, &str, &model::Message)> = model::sorted(meetings)
+        .into_iter()
+        .flat_map(|m| m.sorted_chats().into_iter().map(move |c| (m, c)))
+        .flat_map(|(m, c)| {
+            c.messages
+                .iter()
+                .filter_map(move |msg| msg.score.map(|s| (s.0, m, c.chat_id.as_str(), msg)))
+        })
+        .collect();
+    flagged.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut table = Table::new(FLAGGED_COLUMNS.to_vec());
+    for (score, meeting, chat_id, msg) in flagged {
+        table.push(vec![
+            Value::Number(score, 2),
+            meeting.meeting_id.as_str().into(),
+            chat_id.into(),
+            msg.time.format("%d.%m.%Y %H:%M").to_string().into(),
+            msg.author.as_str().into(),
+            msg.lines().join(" ").into(),
+        ]);
+    }
+    table.sort(options.sort_by.as_deref(), &collation(options));
+    table.write(out, options.format)
+}
+
+/// The number of messages per author (name) of a meeting.
+fn message_counts(meeting: &Meeting) -> BTreeMap<&str, usize> {
+    let mut counts = BTreeMap::new();
+    for msg in meeting.chats.values().flat_map(|c| &c.messages) {
+        *counts.entry(msg.author.as_str()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Something about a meeting that should be looked at by a moderator.
+pub struct Anomaly<'a> {
+    pub meeting: &'a Meeting,
+    /// How far the threshold is exceeded - 1.0 is exactly at the threshold. Used to rank the anomalies.
+    pub severity: f64,
+    pub kind: AnomalyKind<'a>,
+}
+
+pub enum AnomalyKind<'a> {
+    /// A minute with a lot more messages than usual in this meeting.
+    Burst {
+        minute: NaiveDateTime,
+        messages: usize,
+        average: f64,
+    },
+    /// One author wrote most of the messages of the meeting.
+    Dominance { author: &'a str, share: f64 },
+}
+
+fn minute(time: NaiveDateTime) -> NaiveDateTime {
+    time - Duration::seconds(time.second() as i64) - Duration::nanoseconds(time.nanosecond() as i64)
+}
+
+/// Find the minutes with an unusual high message rate compared to the rest of the meeting.
+fn bursts(meeting: &Meeting) -> Vec<Anomaly<'_>> {
+    let mut per_minute: BTreeMap<NaiveDateTime, usize> = BTreeMap::new();
+    for msg in meeting.chats.values().flat_map(|c| &c.messages) {
+        *per_minute.entry(minute(msg.time)).or_insert(0) += 1;
+    }
+    let (first, last) = match (per_minute.keys().min(), per_minute.keys().max()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return Vec::new(),
+    };
+    // the quiet minutes in between count as well for the baseline
+    let minutes = ((last - first).num_minutes() + 1) as f64;
+    let total: usize = per_minute.values().sum();
+    let average = total as f64 / minutes;
+    let squares: f64 = per_minute
+        .values()
+        .map(|&n| (n as f64 - average).powi(2))
+        .sum::<f64>()
+        + (minutes - per_minute.len() as f64) * average.powi(2);
+    let deviation = (squares / minutes).sqrt();
+    let limit = average + BURST_DEVIATIONS * deviation;
+
+    per_minute
+        .into_iter()
+        .filter(|&(_, n)| n >= MIN_BURST_MESSAGES && n as f64 > limit)
+        .map(|(minute, messages)| Anomaly {
+            meeting,
+            severity: messages as f64 / limit,
+            kind: AnomalyKind::Burst {
+                minute,
+                messages,
+                average,
+            },
+        })
+        .collect()
+}
+
+/// Find the authors that wrote more than `max_share` percent of the messages of the meeting.
+fn dominance(meeting: &Meeting, max_share: f64) -> Vec<Anomaly<'_>> {
+    let counts = message_counts(meeting);
+    let total: usize = counts.values().sum();
+    if total < MIN_MESSAGES_FOR_SHARE {
+        return Vec::new();
+    }
+    counts
+        .into_iter()
+        .map(|(author, n)| (author, n as f64 * 100.0 / total as f64))
+        .filter(|&(_, share)| share > max_share)
+        .map(|(author, share)| Anomaly {
+            meeting,
+            severity: share / max_share,
+            kind: AnomalyKind::Dominance { author, share },
+        })
+        .collect()
+}
+
+/// All the anomalies of all meetings, the most severe first.
+pub fn anomalies<'a>(
+    meetings: &'a HashMap<String, Meeting>,
+    options: &Options,
+) -> Vec<Anomaly<'a>> {
+    let mut anomalies: Vec<Anomaly> = model::sorted(meetings)
+        .into_iter()
+        .flat_map(|m| {
+            let mut found = bursts(m);
+            found.extend(dominance(m, options.max_author_share));
+            found
+        })
+        .collect();
+    anomalies.sort_by(|a, b| b.severity.total_cmp(&a.severity));
+    anomalies
+}
+
+const ANOMALY_COLUMNS: [&str; 4] = ["severity", "meeting", "start", "anomaly"];
+
+/// Print the ranked list of anomalies.
+pub fn print_anomalies<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    options: &Options,
+) -> io::Result<()> {
+    let mut table = Table::new(ANOMALY_COLUMNS.to_vec());
+    for anomaly in anomalies(meetings, options) {
+        let description = match anomaly.kind {
+            AnomalyKind::Burst {
+                minute,
+                messages,
+                average,
+            } => format!(
+                "{} messages at {} (average {:.1} per minute)",
+                messages,
+                minute.format("%H:%M"),
+                average
+            ),
+            AnomalyKind::Dominance { author, share } => {
+                format!("{} wrote {:.0}% of the messages", author, share)
+            }
+        };
+        table.push(vec![
+            Value::Number(anomaly.severity, 2),
+            anomaly.meeting.meeting_id.as_str().into(),
+            anomaly
+                .meeting
+                .time
+                .format("%d.%m.%Y %H:%M")
+                .to_string()
+                .into(),
+            description.into(),
+        ]);
+    }
+    table.sort(options.sort_by.as_deref(), &collation(options));
+    table.write(out, options.format)
+}
+
+const NAME_COLUMNS: [&str; 5] = ["meeting", "kind", "names", "user_ids", "ext_user_ids"];
+
+/// Whether two participants with the same name were there at the same time, then it is not one person joining again.
+fn overlap(a: &User, b: &User) -> bool {
+    // a session without a leave lasts until the end
+    let before = |joined: &NaiveDateTime, left: &Option<NaiveDateTime>| {
+        left.is_none_or(|left| *joined < left)
+    };
+    a.sessions.iter().any(|(joined_a, left_a)| {
+        b.sessions
+            .iter()
+            .any(|(joined_b, left_b)| before(joined_a, left_b) && before(joined_b, left_a))
+    })
+}
+
+/// Whether two participants with the same name are different people: their external user ids differ, or both have none and were there at the same time.
+fn different(a: &User, b: &User) -> bool {
+    match (&a.ext_id, &b.ext_id) {
+        (None, None) => overlap(a, b),
+        (a, b) => a != b,
+    }
+}
+
+/// Print the names of every meeting used by different people and the internal user ids whose name changed during the meeting, e.g. to map the identities or to find impersonations during an exam.
+///
+/// The internal user id changes with every join, so the same name under several user ids only counts if the external user ids differ or if they were there at the same time. A rename is seen in the names of the messages of a user id that differ from the name it joined with.
+pub fn print_name_collisions<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    options: &Options,
+) -> io::Result<()> {
+    let collation = collation(options);
+    let mut table = Table::new(NAME_COLUMNS.to_vec());
+    for meeting in model::sorted(meetings) {
+        let mut by_name: BTreeMap<&str, Vec<&User>> = BTreeMap::new();
+        for user in meeting.sorted_users() {
+            by_name.entry(&user.name).or_default().push(user);
+        }
+        let mut names: Vec<&str> = by_name.keys().copied().collect();
+        names.sort_by(|a, b| collation.compare(a, b));
+        for name in names {
+            let users = &by_name[name];
+            let shared = users
+                .iter()
+                .enumerate()
+                .any(|(i, a)| users[i + 1..].iter().any(|b| different(a, b)));
+            if !shared {
+                continue;
+            }
+            let mut ext_ids: Vec<&str> = users.iter().filter_map(|u| u.ext_id.as_deref()).collect();
+            ext_ids.sort_unstable();
+            ext_ids.dedup();
+            table.push(vec![
+                meeting.meeting_id.as_str().into(),
+                "shared name".into(),
+                name.into(),
+                users
+                    .iter()
+                    .map(|u| u.user_id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    .into(),
+                ext_ids.join(", ").into(),
+            ]);
+        }
+
+        // the names in the order they were used, starting with the one of the join
+        let mut renamed: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for user in meeting.sorted_users() {
+            renamed.insert(&user.user_id, vec![&user.name]);
+        }
+        let mut messages: Vec<&Message> =
+            meeting.chats.values().flat_map(|c| &c.messages).collect();
+        messages.sort_by_key(|msg| msg.time);
+        let mut ext_ids: HashMap<&str, &str> = HashMap::new();
+        for msg in messages {
+            let names = renamed.entry(&msg.author_id).or_default();
+            if !names.contains(&msg.author.as_str()) {
+                names.push(&msg.author);
+            }
+            if let Some(ext_id) = &msg.ext_id {
+                ext_ids.insert(&msg.author_id, ext_id);
+            }
+        }
+        for (user_id, names) in renamed {
+            if names.len() < 2 {
+                continue;
+            }
+            table.push(vec![
+                meeting.meeting_id.as_str().into(),
+                "renamed".into(),
+                names.join(" → ").into(),
+                user_id.into(),
+                ext_ids.get(user_id).copied().unwrap_or_default().into(),
+            ]);
+        }
+    }
+    table.sort(options.sort_by.as_deref(), &collation);
+    table.write(out, options.format)
+}
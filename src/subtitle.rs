@@ -0,0 +1,111 @@
+//! Subtitles of the public chat, to see it over a downloaded recording, e.g. when reviewing the accessibility of a lecture.
+use std::io::{self, Write};
+
+use chrono::Duration;
+
+use crate::{
+    model::{Meeting, Message, PUBLIC_CHAT},
+    recording::recorded_offset,
+};
+
+/// The two kinds of subtitle files.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    /// SubRip, understood by nearly every video player.
+    Srt,
+    /// WebVTT, for the `<track>` of an HTML video.
+    Vtt,
+}
+
+/// How long a message is shown: long enough to read it, but not longer than a few seconds.
+fn duration(msg: &Message) -> Duration {
+    let characters = msg.message.chars().count() as i64;
+    Duration::milliseconds((2000 + characters * 60).min(8000))
+}
+
+fn timestamp(kind: Kind, offset: Duration) -> String {
+    let millis = offset.num_milliseconds();
+    let separator = if kind == Kind::Srt { ',' } else { '.' };
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+/// The text of a cue, without the empty lines that would end it.
+fn cue_text(kind: Kind, msg: &Message) -> String {
+    let lines: Vec<&str> = msg
+        .lines()
+        .into_iter()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let author = msg.display_author();
+    match kind {
+        Kind::Srt => format!("{}: {}", author, lines.join("\n")),
+        Kind::Vtt => {
+            // a voice span names the author, the markup characters would start tags
+            let escape = |text: &str| {
+                text.replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;")
+            };
+            format!(
+                "<v {}>{}: {}",
+                escape(&author),
+                escape(&author),
+                escape(&lines.join("\n"))
+            )
+        }
+    }
+}
+
+/// Write the messages of the public chats as subtitles, timed by their position in the recording of their meeting.
+///
+/// The messages that were written while the meeting was not recorded are left out, as is everything of the private chats. A subtitle file belongs to one video, so with several meetings `--split-by meeting` writes one file for each.
+pub fn write<'a, W: Write>(
+    out: &mut W,
+    meetings: impl Iterator<Item = &'a Meeting>,
+    kind: Kind,
+) -> io::Result<()> {
+    if kind == Kind::Vtt {
+        writeln!(out, "WEBVTT")?;
+    }
+    let mut number = 0;
+    for meeting in meetings {
+        let chat = match meeting.chats.get(PUBLIC_CHAT) {
+            Some(chat) => chat,
+            None => continue,
+        };
+        if kind == Kind::Vtt {
+            writeln!(out, "\nNOTE {} ({})", meeting.meeting_id, meeting.handle())?;
+        }
+        for msg in &chat.messages {
+            let start = match recorded_offset(meeting, msg.time) {
+                Some(start) => start,
+                None => continue,
+            };
+            number += 1;
+            // SubRip numbers the cues, they are separated by an empty line in both formats
+            if kind == Kind::Srt {
+                if number > 1 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "{}", number)?;
+            } else {
+                writeln!(out)?;
+            }
+            writeln!(
+                out,
+                "{} --> {}\n{}",
+                timestamp(kind, start),
+                timestamp(kind, start + duration(msg)),
+                cue_text(kind, msg)
+            )?;
+        }
+    }
+    Ok(())
+}
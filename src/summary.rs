@@ -0,0 +1,115 @@
+//! The `list` and `count` subcommands: the two quick answers about the logs, which meetings there are and how much was written, without the transcripts.
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+use crate::{
+    cli::{Format, Options},
+    model::{self, Meeting},
+    table::{Table, Value},
+};
+
+fn messages(meeting: &Meeting) -> usize {
+    meeting.chats.values().map(|c| c.messages.len()).sum()
+}
+
+/// Print one line per meeting: its handle, when it started, its messages, its participants and its name.
+pub fn print_list<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    options: &Options,
+) -> io::Result<()> {
+    let meetings = model::sorted(meetings);
+    if options.format != Format::Text {
+        let mut table = Table::new(vec![
+            "meeting",
+            "handle",
+            "name",
+            "start",
+            "messages",
+            "participants",
+        ]);
+        for meeting in meetings {
+            table.push(vec![
+                meeting.meeting_id.as_str().into(),
+                meeting.handle().into(),
+                meeting.name.as_deref().unwrap_or_default().into(),
+                meeting.time.format("%d.%m.%Y %H:%M").to_string().into(),
+                messages(meeting).into(),
+                meeting.participants().into(),
+            ]);
+        }
+        return table.write(out, options.format);
+    }
+    let width = |count: fn(&Meeting) -> usize| {
+        meetings
+            .iter()
+            .map(|m| count(m).to_string().len())
+            .max()
+            .unwrap_or(0)
+    };
+    let (messages_width, participants_width) = (width(messages), width(Meeting::participants));
+    for meeting in &meetings {
+        let line = format!(
+            "{}  {}  {:>mw$} messages  {:>pw$} participants  {}",
+            meeting.handle(),
+            meeting.time.format("%d.%m.%Y %H:%M"),
+            messages(meeting),
+            meeting.participants(),
+            meeting.name.as_deref().unwrap_or_default(),
+            mw = messages_width,
+            pw = participants_width
+        );
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Print the totals of all meetings, the authors and participants are counted once even if they were in several meetings.
+pub fn print_count<W: Write>(
+    out: &mut W,
+    meetings: &HashMap<String, Meeting>,
+    options: &Options,
+) -> io::Result<()> {
+    let chats: usize = meetings.values().map(|m| m.chats.len()).sum();
+    let messages: usize = meetings.values().map(messages).sum();
+    let authors: HashSet<&str> = meetings
+        .values()
+        .flat_map(|m| m.chats.values())
+        .flat_map(|c| &c.messages)
+        .map(|msg| msg.ext_id.as_deref().unwrap_or(&msg.author))
+        .collect();
+    let participants: HashSet<&str> = meetings
+        .values()
+        .flat_map(|m| m.users.values())
+        .map(|u| u.ext_id.as_deref().unwrap_or(&u.name))
+        .collect();
+    if options.format != Format::Text {
+        let mut table = Table::new(vec![
+            "meetings",
+            "chats",
+            "messages",
+            "authors",
+            "participants",
+        ]);
+        let row: Vec<Value> = vec![
+            meetings.len().into(),
+            chats.into(),
+            messages.into(),
+            authors.len().into(),
+            participants.len().into(),
+        ];
+        table.push(row);
+        return table.write(out, options.format);
+    }
+    writeln!(
+        out,
+        "{} meetings, {} chats, {} messages, {} authors, {} participants",
+        meetings.len(),
+        chats,
+        messages,
+        authors.len(),
+        participants.len()
+    )
+}
@@ -0,0 +1,199 @@
+//! The tables of the statistics: aligned to the width of the terminal or written as CSV, JSON or Markdown.
+use std::{
+    cmp::Ordering,
+    io::{self, Write},
+};
+
+use comfy_table::{presets, CellAlignment, ContentArrangement};
+use json::JsonValue;
+
+use crate::{cli::Format, collation::Collation, export::csv_field};
+
+/// One cell of a table, the numbers are right aligned, sorted by their value and written as JSON numbers.
+pub enum Value {
+    Text(String),
+    /// A number shown with this many decimals.
+    Number(f64, usize),
+    /// A percentage shown without decimals.
+    Percent(f64),
+}
+
+impl Value {
+    fn text(&self) -> String {
+        match self {
+            Value::Text(text) => text.clone(),
+            Value::Number(n, decimals) => format!("{:.1$}", n, decimals),
+            Value::Percent(share) => format!("{:.0}%", share),
+        }
+    }
+
+    fn number(&self) -> Option<f64> {
+        match self {
+            Value::Text(_) => None,
+            Value::Number(n, _) | Value::Percent(n) => Some(*n),
+        }
+    }
+
+    fn json(&self) -> JsonValue {
+        match self.number() {
+            Some(n) => n.into(),
+            None => self.text().into(),
+        }
+    }
+
+    /// Numbers before texts, so a column with some empty cells still sorts by its numbers.
+    fn cmp(&self, other: &Value, collation: &Collation) -> Ordering {
+        match (self.number(), other.number()) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => collation.compare(&self.text(), &other.text()),
+        }
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        Value::Number(n as f64, 0)
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Value::Text(text)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::Text(text.to_string())
+    }
+}
+
+/// Rows of values under named columns.
+pub struct Table {
+    columns: Vec<&'static str>,
+    rows: Vec<Vec<Value>>,
+}
+
+/// Check that a `--sort-by` names one of the columns, a leading `-` sorts descending.
+pub fn check_sort(sort_by: &str, columns: &[&str]) -> Result<(), String> {
+    let column = sort_by.strip_prefix('-').unwrap_or(sort_by);
+    if columns.contains(&column) {
+        Ok(())
+    } else {
+        Err(format!(
+            "--sort-by: there is no column {}, the columns are {}",
+            column,
+            columns.join(", ")
+        ))
+    }
+}
+
+impl Table {
+    pub fn new(columns: Vec<&'static str>) -> Table {
+        Table {
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) {
+        self.rows.push(row);
+    }
+
+    /// Sort the rows by the column of a `--sort-by`, the rows with the same value keep their order.
+    pub fn sort(&mut self, sort_by: Option<&str>, collation: &Collation) {
+        let sort_by = match sort_by {
+            Some(sort_by) => sort_by,
+            None => return,
+        };
+        let (column, descending) = match sort_by.strip_prefix('-') {
+            Some(column) => (column, true),
+            None => (sort_by, false),
+        };
+        // the columns were checked with the options
+        let i = match self.columns.iter().position(|c| *c == column) {
+            Some(i) => i,
+            None => return,
+        };
+        self.rows.sort_by(|a, b| {
+            let order = a[i].cmp(&b[i], collation);
+            if descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+    }
+
+    /// Whether all the values of the column are numbers.
+    fn is_numeric(&self, i: usize) -> bool {
+        !self.rows.is_empty() && self.rows.iter().all(|row| row[i].number().is_some())
+    }
+
+    /// Write the table in the format, the text is aligned and wrapped to the width of the terminal.
+    pub fn write<W: Write>(&self, out: &mut W, format: Format) -> io::Result<()> {
+        match format {
+            Format::Csv => self.write_csv(out),
+            Format::Json => self.write_json(out),
+            Format::Markdown => self.write_markdown(out),
+            _ => self.write_text(out),
+        }
+    }
+
+    fn write_text<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut table = comfy_table::Table::new();
+        table
+            .load_style(presets::UTF8_FULL_CONDENSED)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(self.columns.clone());
+        for row in &self.rows {
+            table.add_row(row.iter().map(Value::text).collect::<Vec<_>>());
+        }
+        for i in 0..self.columns.len() {
+            if self.is_numeric(i) {
+                if let Some(column) = table.column_mut(i) {
+                    column.set_cell_alignment(CellAlignment::Right);
+                }
+            }
+        }
+        writeln!(out, "{}", table)
+    }
+
+    fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{}", self.columns.join(","))?;
+        for row in &self.rows {
+            let row: Vec<String> = row.iter().map(|v| csv_field(&v.text())).collect();
+            writeln!(out, "{}", row.join(","))?;
+        }
+        Ok(())
+    }
+
+    fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut doc = JsonValue::new_array();
+        for row in &self.rows {
+            let mut object = JsonValue::new_object();
+            for (column, value) in self.columns.iter().zip(row) {
+                object[*column] = value.json();
+            }
+            doc.push(object).expect("an array");
+        }
+        doc.write_pretty(out, 2)?;
+        writeln!(out)
+    }
+
+    fn write_markdown<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let cell = |text: String| text.replace('|', "\\|").replace('\n', " ");
+        writeln!(out, "| {} |", self.columns.join(" | "))?;
+        let rules: Vec<&str> = (0..self.columns.len())
+            .map(|i| if self.is_numeric(i) { "---:" } else { "---" })
+            .collect();
+        writeln!(out, "| {} |", rules.join(" | "))?;
+        for row in &self.rows {
+            let row: Vec<String> = row.iter().map(|v| cell(v.text())).collect();
+            writeln!(out, "| {} |", row.join(" | "))?;
+        }
+        Ok(())
+    }
+}
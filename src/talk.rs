@@ -0,0 +1,116 @@
+//! Posting the public chats into a Nextcloud Talk conversation.
+use std::{collections::HashMap, io, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use json::object;
+
+use crate::{
+    cli::Options,
+    deliver::{Delivery, Post},
+    model::{self, Meeting, PUBLIC_CHAT},
+};
+
+/// Talk does not accept longer messages, longer transcripts are posted in several parts.
+const MAX_MESSAGE_LENGTH: usize = 32000;
+
+/// Where the transcripts are sent instead of standard out.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sink {
+    /// A conversation of Nextcloud Talk.
+    NextcloudTalk,
+}
+
+impl FromStr for Sink {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nextcloud-talk" => Ok(Sink::NextcloudTalk),
+            _ => Err(format!("unknown sink: {}", s)),
+        }
+    }
+}
+
+/// The public chat of a meeting as Markdown messages that are short enough for Talk.
+fn messages(meeting: &Meeting) -> Vec<String> {
+    let chat = match meeting.chats.get(PUBLIC_CHAT) {
+        Some(chat) if !chat.messages.is_empty() => chat,
+        _ => return Vec::new(),
+    };
+    let heading = format!(
+        "**{} - {}**\n",
+        meeting.time.format("%d.%m.%Y %H:%M"),
+        meeting.name.clone().unwrap_or_else(|| meeting.handle())
+    );
+    let mut parts = vec![heading.clone()];
+    for msg in &chat.messages {
+        let line = format!(
+            "\n{} **{}**: {}",
+            msg.time.format("%H:%M"),
+            msg.author,
+            msg.lines().join("\n  ")
+        );
+        let current = parts.last_mut().expect("there is always a part");
+        if current.chars().count() + line.chars().count() > MAX_MESSAGE_LENGTH {
+            let mut part = heading.clone();
+            // a single message can exceed the limit on its own
+            part.extend(
+                line.chars()
+                    .take(MAX_MESSAGE_LENGTH - heading.chars().count()),
+            );
+            parts.push(part);
+        } else {
+            current.push_str(&line);
+        }
+    }
+    parts
+}
+
+/// Post the public chat of every meeting into the conversation given on the command line, the oldest meeting first.
+pub fn post(options: &Options, meetings: &HashMap<String, Meeting>) -> io::Result<()> {
+    let (url, user, password, conversation) = match (
+        &options.nextcloud_url,
+        &options.nextcloud_user,
+        &options.nextcloud_password,
+        &options.talk_conversation,
+    ) {
+        (Some(url), Some(user), Some(password), Some(conversation)) => {
+            (url, user, password, conversation)
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the Nextcloud url, user, password and conversation are needed",
+            ))
+        }
+    };
+    let endpoint = format!(
+        "{}/ocs/v2.php/apps/spreed/api/v1/chat/{}",
+        url.trim_end_matches('/'),
+        conversation
+    );
+    let authorization = format!(
+        "Basic {}",
+        STANDARD.encode(format!("{}:{}", user, password))
+    );
+    let mut posts = Vec::new();
+    for meeting in model::sorted(meetings) {
+        for message in messages(meeting) {
+            let body = object! { message: message };
+            posts.push(Post {
+                what: format!("the chat of {}", meeting.meeting_id),
+                url: endpoint.clone(),
+                headers: vec![
+                    ("Authorization", authorization.clone()),
+                    ("OCS-APIRequest", "true".to_string()),
+                    ("Accept", "application/json".to_string()),
+                    ("Content-Type", "application/json".to_string()),
+                ],
+                body: body.dump(),
+            });
+        }
+    }
+    let posted = Delivery::from_options(options).send(posts)?;
+    eprintln!("posted {} messages to {}", posted, conversation);
+    Ok(())
+}
@@ -0,0 +1,64 @@
+//! The tenants (e.g. the faculties) the BigBlueButton servers belong to, so the logs of several clusters can be processed in one run.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+};
+
+use crate::model::Meeting;
+
+/// The name the meetings without a known tenant are grouped under.
+pub const UNKNOWN: &str = "unknown";
+
+/// The placeholder in `--archive` and `--output-dir` that is replaced by the tenant.
+pub const PLACEHOLDER: &str = "{tenant}";
+
+/// Which host belongs to which tenant.
+#[derive(Default)]
+pub struct Tenants {
+    hosts: HashMap<String, String>,
+}
+
+/// The parts of a path or a log line prefix that could be a host name.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, ':' | '/' | '\\' | '[' | ']'))
+        .filter(|w| !w.is_empty())
+}
+
+impl Tenants {
+    /// Read a file with one `host tenant` pair per line (separated by whitespace or a comma).
+    pub fn load(path: &Path) -> io::Result<Tenants> {
+        let mut hosts = HashMap::new();
+        for line in fs::read_to_string(path)?.lines() {
+            let mut fields = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|f| !f.is_empty());
+            if let (Some(host), Some(tenant)) = (fields.next(), fields.next()) {
+                hosts.insert(host.to_string(), tenant.to_string());
+            }
+        }
+        Ok(Tenants { hosts })
+    }
+
+    /// The tenant of the first known host in the text, e.g. the host name of a syslog line or a directory named after the server.
+    pub fn find(&self, text: &str) -> Option<String> {
+        words(text).find_map(|w| self.hosts.get(w).cloned())
+    }
+}
+
+/// The tenant of the meeting or [`UNKNOWN`].
+pub fn of(meeting: &Meeting) -> &str {
+    meeting.tenant.as_deref().unwrap_or(UNKNOWN)
+}
+
+/// Divide the meetings by their tenant.
+pub fn split(meetings: &HashMap<String, Meeting>) -> BTreeMap<&str, HashMap<String, Meeting>> {
+    let mut parts: BTreeMap<&str, HashMap<String, Meeting>> = BTreeMap::new();
+    for meeting in meetings.values() {
+        parts
+            .entry(of(meeting))
+            .or_default()
+            .insert(meeting.meeting_id.clone(), meeting.clone());
+    }
+    parts
+}
@@ -0,0 +1,235 @@
+//! A picture of the activity of every meeting: the messages per minute, the polls, the participants joining and leaving and, from the bbb-web and FreeSWITCH logs, the creation through the API and who was in the audio conference.
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::{
+    document::escape_html,
+    model::{self, Meeting},
+};
+
+const WIDTH: f64 = 1000.0;
+/// The room for the labels left of the axis.
+const MARGIN: f64 = 60.0;
+const ROW_HEIGHT: f64 = 130.0;
+/// The height of the highest bar of the messages per minute.
+const BAR_HEIGHT: f64 = 60.0;
+const TICK_HEIGHT: f64 = 8.0;
+/// The thickness of the (translucent) lines of the audio sessions, overlapping sessions look darker.
+const AUDIO_HEIGHT: f64 = 4.0;
+
+/// The first moment anything is known of the meeting.
+fn start(meeting: &Meeting) -> NaiveDateTime {
+    let api_joins = meeting.api_joins.iter().map(|(time, _)| *time);
+    let audio = meeting.audio.iter().map(|s| s.joined);
+    api_joins
+        .chain(audio)
+        .chain(meeting.created)
+        .fold(meeting.time, NaiveDateTime::min)
+}
+
+/// The last moment anything happened in the meeting.
+fn end(meeting: &Meeting) -> NaiveDateTime {
+    let messages = meeting
+        .chats
+        .values()
+        .flat_map(|c| &c.messages)
+        .map(|m| m.time);
+    let sessions = meeting
+        .users
+        .values()
+        .flat_map(|u| &u.sessions)
+        .flat_map(|(joined, left)| Some(*joined).into_iter().chain(*left));
+    let polls = meeting.polls.iter().map(|(time, _)| *time);
+    let audio = meeting
+        .audio
+        .iter()
+        .flat_map(|s| Some(s.joined).into_iter().chain(s.left));
+    messages
+        .chain(sessions)
+        .chain(polls)
+        .chain(audio)
+        .max()
+        .unwrap_or(meeting.time)
+        .max(start(meeting) + Duration::minutes(1))
+}
+
+/// Draw one meeting with its top at `top`.
+fn write_meeting<W: Write>(out: &mut W, meeting: &Meeting, top: f64) -> io::Result<()> {
+    let start = start(meeting);
+    let span = (end(meeting) - start).num_milliseconds() as f64;
+    let plot = WIDTH - 2.0 * MARGIN;
+    let x = |time: NaiveDateTime| {
+        MARGIN + (time - start).num_milliseconds().max(0) as f64 / span * plot
+    };
+    let axis = top + 20.0 + BAR_HEIGHT;
+
+    let title = match &meeting.name {
+        Some(name) => format!("{} - {}", meeting.meeting_id, name),
+        None => meeting.meeting_id.clone(),
+    };
+    writeln!(
+        out,
+        "<g class=\"meeting\">\n<text x=\"{}\" y=\"{}\" class=\"title\">{} {}</text>",
+        MARGIN,
+        top + 12.0,
+        start.format("%d.%m.%Y %H:%M"),
+        escape_html(&title)
+    )?;
+
+    // the message density as one bar per minute
+    let mut per_minute: BTreeMap<i64, usize> = BTreeMap::new();
+    for msg in meeting.chats.values().flat_map(|c| &c.messages) {
+        *per_minute
+            .entry((msg.time - start).num_minutes())
+            .or_insert(0) += 1;
+    }
+    let highest = per_minute.values().copied().max().unwrap_or(1) as f64;
+    let bar_width = (60_000.0 / span * plot).max(1.0);
+    for (minute, count) in &per_minute {
+        let height = *count as f64 / highest * BAR_HEIGHT;
+        writeln!(
+            out,
+            "<rect class=\"messages\" x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"><title>{} messages at {}</title></rect>",
+            x(start + Duration::minutes(*minute)),
+            axis - height,
+            bar_width,
+            height,
+            count,
+            (start + Duration::minutes(*minute)).format("%H:%M")
+        )?;
+    }
+    writeln!(
+        out,
+        "<line class=\"axis\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+        MARGIN,
+        axis,
+        WIDTH - MARGIN,
+        axis
+    )?;
+    writeln!(
+        out,
+        "<text x=\"{}\" y=\"{}\" class=\"label\" text-anchor=\"end\">{}</text>",
+        MARGIN - 4.0,
+        axis - BAR_HEIGHT + 8.0,
+        highest
+    )?;
+    writeln!(
+        out,
+        "<text x=\"{}\" y=\"{}\" class=\"label\" text-anchor=\"end\">{}</text>",
+        WIDTH - MARGIN,
+        axis + 2.0 * TICK_HEIGHT + AUDIO_HEIGHT + 18.0,
+        end(meeting).format("%H:%M")
+    )?;
+
+    // joins above and leaves below a line under the axis
+    let ticks = axis + TICK_HEIGHT + 2.0;
+    for user in meeting.sorted_users() {
+        for (joined, left) in &user.sessions {
+            let name = escape_html(&user.name);
+            writeln!(
+                out,
+                "<line class=\"join\" x1=\"{0:.1}\" y1=\"{1:.1}\" x2=\"{0:.1}\" y2=\"{2:.1}\"><title>{3} joined at {4}</title></line>",
+                x(*joined),
+                ticks - TICK_HEIGHT,
+                ticks,
+                name,
+                joined.format("%H:%M")
+            )?;
+            if let Some(left) = left {
+                writeln!(
+                    out,
+                    "<line class=\"leave\" x1=\"{0:.1}\" y1=\"{1:.1}\" x2=\"{0:.1}\" y2=\"{2:.1}\"><title>{3} left at {4}</title></line>",
+                    x(*left),
+                    ticks,
+                    ticks + TICK_HEIGHT,
+                    name,
+                    left.format("%H:%M")
+                )?;
+            }
+        }
+    }
+
+    for (time, name) in &meeting.api_joins {
+        writeln!(
+            out,
+            "<circle class=\"api-join\" cx=\"{:.1}\" cy=\"{:.1}\" r=\"2\"><title>{} joined through the API at {}</title></circle>",
+            x(*time),
+            ticks - TICK_HEIGHT,
+            escape_html(name),
+            time.format("%H:%M")
+        )?;
+    }
+
+    // the audio sessions in one lane below the leaves, until the end of the picture if the leave is missing
+    let lane = ticks + TICK_HEIGHT + 4.0 + AUDIO_HEIGHT / 2.0;
+    for session in &meeting.audio {
+        let left = session.left.unwrap_or_else(|| end(meeting));
+        writeln!(
+            out,
+            "<line class=\"audio\" x1=\"{:.1}\" y1=\"{2:.1}\" x2=\"{:.1}\" y2=\"{2:.1}\"><title>{3} in the audio from {4} to {5}</title></line>",
+            x(session.joined),
+            x(left).max(x(session.joined) + 1.0),
+            lane,
+            escape_html(&session.name),
+            session.joined.format("%H:%M"),
+            left.format("%H:%M")
+        )?;
+    }
+
+    if let Some(created) = meeting.created {
+        writeln!(
+            out,
+            "<line class=\"created\" x1=\"{0:.1}\" y1=\"{1:.1}\" x2=\"{0:.1}\" y2=\"{2:.1}\"><title>created at {3}</title></line>",
+            x(created),
+            axis - BAR_HEIGHT,
+            lane,
+            created.format("%H:%M")
+        )?;
+    }
+
+    for (time, question) in &meeting.polls {
+        writeln!(
+            out,
+            "<line class=\"poll\" x1=\"{0:.1}\" y1=\"{1:.1}\" x2=\"{0:.1}\" y2=\"{2:.1}\"><title>poll at {3}: {4}</title></line>",
+            x(*time),
+            axis - BAR_HEIGHT,
+            axis,
+            time.format("%H:%M"),
+            escape_html(question)
+        )?;
+    }
+    writeln!(out, "</g>")
+}
+
+/// Write all meetings as one SVG image, one row per meeting in the order they started.
+pub fn write_svg<W: Write>(out: &mut W, meetings: &HashMap<String, Meeting>) -> io::Result<()> {
+    let height = ROW_HEIGHT * meetings.len().max(1) as f64;
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        WIDTH, height, WIDTH, height
+    )?;
+    writeln!(
+        out,
+        "<style>\n\
+         text {{ font-family: sans-serif; font-size: 11px; }}\n\
+         .title {{ font-weight: bold; }}\n\
+         .messages {{ fill: #4a7ebb; }}\n\
+         .axis {{ stroke: #333; }}\n\
+         .join {{ stroke: #2a9d3a; }}\n\
+         .leave {{ stroke: #c0392b; }}\n\
+         .poll {{ stroke: #e69500; stroke-width: 2; stroke-dasharray: 3 2; }}\n\
+         .api-join {{ fill: #2a9d3a; }}\n\
+         .audio {{ stroke: #8e44ad; stroke-width: 4; stroke-opacity: 0.3; }}\n\
+         .created {{ stroke: #777; stroke-dasharray: 1 2; }}\n\
+         </style>"
+    )?;
+    for (i, meeting) in model::sorted(meetings).into_iter().enumerate() {
+        write_meeting(out, meeting, i as f64 * ROW_HEIGHT)?;
+    }
+    writeln!(out, "</svg>")
+}
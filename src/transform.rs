@@ -0,0 +1,217 @@
+//! The stages the messages go through after they are read from the logs and before they are written, e.g. scores, languages, normalized texts or pseudonyms.
+//!
+//! An embedder can put stages of their own into the [`Pipeline`], e.g. to redact phone numbers:
+//!
+//! ```ignore
+//! struct Redact(Regex);
+//!
+//! impl MessageTransform for Redact {
+//!     fn message(&mut self, msg: &mut Message) -> io::Result<bool> {
+//!         msg.message = self.0.replace_all(&msg.message, "[redacted]").into_owned();
+//!         Ok(true)
+//!     }
+//! }
+//!
+//! let mut pipeline = Pipeline::from_options(&options)?;
+//! pipeline.insert(0, Box::new(Redact(Regex::new(r"\+?\d[\d /-]{7,}")?)));
+//! pipeline.apply(&mut meetings)?;
+//! ```
+use std::{collections::HashMap, io, str::FromStr};
+
+use crate::{
+    anonymize::Pseudonyms,
+    cli::Options,
+    language::Detection,
+    model::{Meeting, Message, Withheld},
+    score::{MinScore, Scorer},
+};
+
+/// One stage of the pipeline.
+pub trait MessageTransform {
+    /// Change the message, `false` drops it.
+    fn message(&mut self, msg: &mut Message) -> io::Result<bool>;
+
+    /// Change the meeting after all its messages went through the pipeline, e.g. its participants.
+    fn meeting(&mut self, _meeting: &mut Meeting) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Keep only the first lines of long messages.
+pub struct MaxLines(pub usize);
+
+impl MessageTransform for MaxLines {
+    fn message(&mut self, msg: &mut Message) -> io::Result<bool> {
+        msg.truncate_lines(self.0);
+        Ok(true)
+    }
+}
+
+/// What happens to a message with more bytes than `--max-message-bytes`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Oversized {
+    /// Keep the first bytes and note how many were cut.
+    Truncate,
+    Drop,
+    /// Keep the whole text and mark the message with its size.
+    Flag,
+}
+
+impl FromStr for Oversized {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truncate" => Ok(Oversized::Truncate),
+            "drop" => Ok(Oversized::Drop),
+            "flag" => Ok(Oversized::Flag),
+            _ => Err(format!(
+                "--oversized: unknown policy {}, use truncate, drop or flag",
+                s
+            )),
+        }
+    }
+}
+
+/// Cap the size of the message texts, e.g. of whole source files pasted into the chat, so cells of a spreadsheet or the pages of a document are not overrun.
+pub struct MaxBytes {
+    pub max: usize,
+    pub policy: Oversized,
+}
+
+impl MessageTransform for MaxBytes {
+    fn message(&mut self, msg: &mut Message) -> io::Result<bool> {
+        let bytes = msg.message.len();
+        if bytes <= self.max {
+            return Ok(true);
+        }
+        match self.policy {
+            Oversized::Truncate => {
+                // at a character boundary, so the text stays valid UTF-8
+                let end = (0..=self.max)
+                    .rev()
+                    .find(|&i| msg.message.is_char_boundary(i))
+                    .unwrap_or(0);
+                msg.message = format!("{}… ({} more bytes)", &msg.message[..end], bytes - end);
+            }
+            Oversized::Drop => return Ok(false),
+            Oversized::Flag => msg.oversized = Some(bytes),
+        }
+        Ok(true)
+    }
+}
+
+/// Leave out the message texts but keep their number of words and characters.
+pub struct NoBodies;
+
+impl MessageTransform for NoBodies {
+    fn message(&mut self, msg: &mut Message) -> io::Result<bool> {
+        msg.withheld = Some(Withheld {
+            words: msg.words(),
+            characters: msg.characters(),
+        });
+        msg.message.clear();
+        Ok(true)
+    }
+}
+
+/// The stages every message goes through one after the other.
+#[derive(Default)]
+pub struct Pipeline {
+    stages: Vec<Box<dyn MessageTransform>>,
+}
+
+impl Pipeline {
+    /// The stages of the command line: the original texts are scored and their language detected before they are normalized and capped, the pseudonyms come last and the texts with `--no-bodies` after everything else.
+    pub fn from_options(options: &Options) -> io::Result<Pipeline> {
+        let mut pipeline = Pipeline::default();
+        if let Some(command) = &options.score_cmd {
+            pipeline.push(Box::new(Scorer::spawn(command)?));
+            if let Some(min) = options.min_score {
+                pipeline.push(Box::new(MinScore(min)));
+            }
+        }
+        if options.detect_language {
+            pipeline.push(Box::new(Detection::new(&options.languages)));
+        }
+        if let Some(max) = options.max_message_lines {
+            pipeline.push(Box::new(MaxLines(max)));
+        }
+        if !options.normalize.is_empty() {
+            pipeline.push(Box::new(options.normalize.clone()));
+        }
+        if let Some(max) = options.max_message_bytes {
+            pipeline.push(Box::new(MaxBytes {
+                max,
+                policy: options.oversized,
+            }));
+        }
+        if let Some(key) = &options.anonymize_key {
+            pipeline.push(Box::new(Pseudonyms::new(key)));
+        }
+        if options.no_bodies {
+            pipeline.push(Box::new(NoBodies));
+        }
+        Ok(pipeline)
+    }
+
+    /// Add a stage at the end.
+    pub fn push(&mut self, stage: Box<dyn MessageTransform>) {
+        self.stages.push(stage);
+    }
+
+    /// Add a stage before the one at `index`.
+    pub fn insert(&mut self, index: usize, stage: Box<dyn MessageTransform>) {
+        self.stages.insert(index, stage);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Put one message through all the stages, `false` if one of them dropped it.
+    pub fn message(&mut self, msg: &mut Message) -> io::Result<bool> {
+        for stage in &mut self.stages {
+            if !stage.message(msg)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Put all the messages of the meetings through the stages, the chats and meetings whose messages were all dropped are dropped as well.
+    pub fn apply(&mut self, meetings: &mut HashMap<String, Meeting>) -> io::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let mut emptied = Vec::new();
+        for meeting in meetings.values_mut() {
+            let mut dropped = false;
+            for chat in meeting.chats.values_mut() {
+                let mut kept = Vec::with_capacity(chat.messages.len());
+                for mut msg in chat.messages.drain(..) {
+                    if self.message(&mut msg)? {
+                        kept.push(msg);
+                    } else {
+                        dropped = true;
+                    }
+                }
+                chat.messages = kept;
+            }
+            // the meetings without any messages, e.g. with just the attendance, stay
+            if dropped {
+                meeting.chats.retain(|_, chat| !chat.messages.is_empty());
+                if meeting.chats.is_empty() {
+                    emptied.push(meeting.meeting_id.clone());
+                }
+            }
+            for stage in &mut self.stages {
+                stage.meeting(meeting)?;
+            }
+        }
+        for meeting_id in emptied {
+            meetings.remove(&meeting_id);
+        }
+        Ok(())
+    }
+}